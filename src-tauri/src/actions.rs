@@ -0,0 +1,130 @@
+//! User actions shared by the Tauri commands, the tray menu and global hotkeys
+//! Each goes through devices::akko::api, then records the result so it is
+//! reapplied after reconnect and shown in the tray. Lighting goes through the
+//! keyboard's lighting queue, which records it once written.
+
+use tauri::{AppHandle, Manager};
+
+use crate::devices::akko::protocol::{next_effect, ProfileInfo, RgbSettings};
+use crate::devices::akko::{self, AkkoModel, CommandResult};
+use crate::last_applied::LastApplied;
+use crate::presets::PresetStore;
+use crate::services::lighting::{LightingChange, LightingQueues};
+use crate::tray;
+
+/// Change only the brightness (0-4)
+pub fn set_brightness(
+    app: &AppHandle,
+    model: AkkoModel,
+    level: u8,
+) -> Result<CommandResult, String> {
+    if level > 4 {
+        return Err(format!("Brightness must be 0-4, got {}", level));
+    }
+    write_lighting(app, model, LightingChange::Brightness(level))
+}
+
+/// Raise or lower brightness by `delta` steps (clamped to 0-4)
+pub fn step_brightness(
+    app: &AppHandle,
+    model: AkkoModel,
+    delta: i8,
+) -> Result<Option<CommandResult>, String> {
+    let current = akko::api::akko_get_lighting(model)?.brightness;
+    let level = (current as i8 + delta).clamp(0, 4) as u8;
+    if level == current {
+        return Ok(None);
+    }
+    set_brightness(app, model, level).map(Some)
+}
+
+/// LEDs off, or back on at the last non-zero brightness
+pub fn toggle_lights(app: &AppHandle, model: AkkoModel) -> Result<CommandResult, String> {
+    set_brightness(app, model, tray::lights_toggle_target(app, model))
+}
+
+/// Current lighting of the active profile
+/// The keyboard does not report its effect or color mode, so they come from
+/// the lighting last sent from the app; fails when nothing was sent yet.
+pub fn current_lighting(app: &AppHandle, model: AkkoModel) -> Result<RgbSettings, String> {
+    let known = app.state::<LastApplied>().lighting(model).ok_or_else(|| {
+        format!(
+            "The current effect of {} is unknown; apply lighting or a preset first",
+            model.name()
+        )
+    })?;
+    Ok(akko::api::akko_get_lighting(model)?.apply_to(known))
+}
+
+/// Switch to the next known effect, keeping color, speed and brightness
+pub fn cycle_effect(app: &AppHandle, model: AkkoModel) -> Result<CommandResult, String> {
+    let mut lighting = current_lighting(app, model)?;
+    lighting.direction = next_effect(lighting.direction);
+    // Sub-modes differ per effect; start the new one at its first
+    lighting.mode &= 0x0F;
+
+    write_lighting(app, model, LightingChange::Set(lighting))
+}
+
+/// Switch to the next profile (wraps)
+pub fn next_profile(app: &AppHandle, model: AkkoModel) -> Result<ProfileInfo, String> {
+    let profiles = akko::api::akko_get_profile_info(model)?;
+    if profiles.count == 0 {
+        return Err("Keyboard reports no profiles".to_string());
+    }
+    set_active_profile(app, model, (profiles.active + 1) % profiles.count)
+}
+
+/// Switch the active profile (0-based)
+pub fn set_active_profile(
+    app: &AppHandle,
+    model: AkkoModel,
+    profile: u8,
+) -> Result<ProfileInfo, String> {
+    let profiles = akko::api::akko_set_active_profile(model, profile)?;
+
+    app.state::<LastApplied>().record_profile(model, profiles);
+    // The new profile has its own lighting
+    tray::sync_device(app, model);
+    Ok(profiles)
+}
+
+/// Send a saved lighting preset
+pub fn apply_preset(
+    app: &AppHandle,
+    model: AkkoModel,
+    name: &str,
+) -> Result<CommandResult, String> {
+    let preset = app.state::<PresetStore>().get(name)?;
+    preset.validate()?;
+    write_lighting(app, model, LightingChange::Set(preset.to_rgb_settings()))
+}
+
+/// Apply the Nth preset (1-based, in library order)
+pub fn apply_preset_slot(
+    app: &AppHandle,
+    model: AkkoModel,
+    slot: usize,
+) -> Result<CommandResult, String> {
+    let presets = app.state::<PresetStore>().list();
+    let preset = slot
+        .checked_sub(1)
+        .and_then(|i| presets.get(i))
+        .ok_or_else(|| format!("No preset in slot {} ({} saved)", slot, presets.len()))?;
+    apply_preset(app, model, &preset.name)
+}
+
+/// Queue a lighting change and wait for it to be written
+pub fn write_lighting(
+    app: &AppHandle,
+    model: AkkoModel,
+    change: LightingChange,
+) -> Result<CommandResult, String> {
+    app.state::<LightingQueues>().write(app, model, change)
+}
+
+/// Record lighting that was sent successfully
+pub fn lighting_applied(app: &AppHandle, model: AkkoModel, lighting: RgbSettings) {
+    app.state::<LastApplied>().record_lighting(model, lighting);
+    tray::update(app, |menu| menu.set_brightness(model, lighting.brightness));
+}
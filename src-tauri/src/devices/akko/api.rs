@@ -1,28 +1,117 @@
 //! High-level API for Akko keyboard operations
 //! Orchestrates HID layer with commands
 
+use super::backup::{
+    self, ConfigDocument, RawTables, RestorePreview, RestoreReport, SkippedSection,
+};
+use super::cloud_import::CloudImport;
 use super::commands::{self, CommandResult, ProbeResult};
+use super::compat;
 use super::detector::AkkoModel;
-use super::hid::AkkoHidDevice;
+use super::protocol::{
+    AkkoOpcode, AkkoPacket, BatteryStatus, DeviceInfoResponse, FirmwareVersion, FnLockSettings,
+    IndicatorLedSettings, ProfileInfo, ResetScope, RgbReadback, RgbSettings, SleepSettings,
+};
+use super::queue::LockedDevice;
+use super::state::{ConfigSection, KeyboardState};
+use super::verify::{self, RetryPolicy};
 
-use log::info;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+/// Device information for UI and bug reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceInfo {
+    pub model: String,
+    pub vid: u16,
+    pub pid: u16,
+    /// None when the handshake response could not be decoded
+    pub firmware: Option<FirmwareVersion>,
+    /// Firmware as display string (e.g. "v1.2"), "unknown" if not decoded
+    pub firmware_string: String,
+    /// Decoded GetDeviceInfo (0x80) response, if the device answered
+    pub info: Option<DeviceInfoResponse>,
+}
+
+/// Open device, handshake and decode firmware version
+fn connect(model: AkkoModel) -> Result<(LockedDevice, Option<FirmwareVersion>), String> {
+    let device = LockedDevice::open(model)?;
+    let result = commands::cmd_handshake(&device)?;
+
+    let firmware = FirmwareVersion::from_response(&result.response);
+    if firmware.is_none() {
+        warn!(
+            "Could not decode firmware version from {}",
+            result.hex_short
+        );
+    }
+
+    Ok((device, firmware))
+}
+
+/// Connect and refuse if the firmware is known not to support `opcode`
+fn connect_for(model: AkkoModel, opcode: AkkoOpcode) -> Result<LockedDevice, String> {
+    let (device, firmware) = connect(model)?;
+    compat::ensure_supported(model, firmware, opcode)?;
+    Ok(device)
+}
 
 /// Perform handshake with an Akko keyboard
 pub fn akko_handshake(model: AkkoModel) -> Result<Vec<u8>, String> {
     info!("Starting handshake with Akko {}", model.name());
 
-    let device = AkkoHidDevice::open(model.vid(), model.pid())?;
+    let device = LockedDevice::open(model)?;
     let result = commands::cmd_handshake(&device)?;
 
     info!("Handshake complete: {}", result.hex_short);
     Ok(result.response)
 }
 
+/// Get firmware version from the handshake response
+pub fn akko_get_firmware_version(model: AkkoModel) -> Result<FirmwareVersion, String> {
+    let (_device, firmware) = connect(model)?;
+    firmware.ok_or_else(|| format!("{} returned an unrecognized handshake", model.name()))
+}
+
+/// Get device info (model, IDs, firmware, GetDeviceInfo response)
+pub fn akko_get_device_info(model: AkkoModel) -> Result<DeviceInfo, String> {
+    let (device, firmware) = connect(model)?;
+
+    let info = match commands::cmd_get_device_info(&device) {
+        Ok(result) => DeviceInfoResponse::from_response(&result.response),
+        Err(e) => {
+            warn!("GetDeviceInfo failed: {}", e);
+            None
+        }
+    };
+
+    Ok(DeviceInfo {
+        model: model.name().to_string(),
+        vid: model.vid(),
+        pid: model.pid(),
+        firmware,
+        firmware_string: firmware
+            .map(|fw| fw.to_string())
+            .unwrap_or_else(|| "unknown".to_string()),
+        info,
+    })
+}
+
 /// Send arbitrary packet to Akko keyboard
-pub fn akko_send_packet(model: AkkoModel, packet: [u8; 64]) -> Result<Vec<u8>, String> {
+/// A wrong checksum is refused unless `override_checksum` is set
+pub fn akko_send_packet(
+    model: AkkoModel,
+    packet: [u8; 64],
+    override_checksum: bool,
+) -> Result<Vec<u8>, String> {
     info!("Sending packet to Akko {}", model.name());
 
-    let device = AkkoHidDevice::open(model.vid(), model.pid())?;
+    let mut packet = AkkoPacket::from_bytes(&packet);
+    if override_checksum {
+        packet = packet.with_checksum_override();
+    }
+
+    let device = LockedDevice::open(model)?;
     let response = device.send_feature_report(&packet)?;
 
     Ok(response)
@@ -32,7 +121,7 @@ pub fn akko_send_packet(model: AkkoModel, packet: [u8; 64]) -> Result<Vec<u8>, S
 pub fn akko_probe_opcode(model: AkkoModel, opcode: u8) -> Result<ProbeResult, String> {
     info!("Probing opcode 0x{:02X} on Akko {}", opcode, model.name());
 
-    let device = AkkoHidDevice::open(model.vid(), model.pid())?;
+    let device = LockedDevice::open(model)?;
 
     // Handshake first
     commands::cmd_handshake(&device)?;
@@ -50,7 +139,7 @@ pub fn akko_probe_range(model: AkkoModel, start: u8, end: u8) -> Result<Vec<Prob
         model.name()
     );
 
-    let device = AkkoHidDevice::open(model.vid(), model.pid())?;
+    let device = LockedDevice::open(model)?;
 
     // Handshake first
     commands::cmd_handshake(&device)?;
@@ -63,51 +152,38 @@ pub fn akko_probe_range(model: AkkoModel, start: u8, end: u8) -> Result<Vec<Prob
 pub fn akko_run_all(model: AkkoModel) -> Result<Vec<CommandResult>, String> {
     info!("Running all commands on Akko {}", model.name());
 
-    let device = AkkoHidDevice::open(model.vid(), model.pid())?;
+    let device = LockedDevice::open(model)?;
     commands::run_all_commands(&device)
 }
 
 /// Get profile count and active profile
 pub fn akko_get_profile_count(model: AkkoModel) -> Result<CommandResult, String> {
-    let device = AkkoHidDevice::open(model.vid(), model.pid())?;
+    let device = LockedDevice::open(model)?;
     commands::cmd_handshake(&device)?;
     commands::cmd_get_profile_count(&device)
 }
 
 /// Get RGB settings
 pub fn akko_get_rgb_settings(model: AkkoModel) -> Result<CommandResult, String> {
-    let device = AkkoHidDevice::open(model.vid(), model.pid())?;
+    let device = LockedDevice::open(model)?;
     commands::cmd_handshake(&device)?;
     commands::cmd_get_rgb_settings(&device)
 }
 
 /// Get RGB mode
 pub fn akko_get_rgb_mode(model: AkkoModel) -> Result<CommandResult, String> {
-    let device = AkkoHidDevice::open(model.vid(), model.pid())?;
+    let device = LockedDevice::open(model)?;
     commands::cmd_handshake(&device)?;
     commands::cmd_get_rgb_mode(&device)
 }
 
 /// Get performance settings (debounce)
 pub fn akko_get_performance(model: AkkoModel) -> Result<CommandResult, String> {
-    let device = AkkoHidDevice::open(model.vid(), model.pid())?;
+    let device = LockedDevice::open(model)?;
     commands::cmd_handshake(&device)?;
     commands::cmd_get_performance(&device)
 }
 
-/// Set RGB settings (brightness, speed, direction, color)
-pub fn akko_set_rgb_settings(
-    model: AkkoModel,
-    brightness: u8,
-    speed: u8,
-    direction: u8,
-    color: (u8, u8, u8),
-) -> Result<CommandResult, String> {
-    let device = AkkoHidDevice::open(model.vid(), model.pid())?;
-    commands::cmd_handshake(&device)?;
-    commands::cmd_set_rgb_settings(&device, brightness, speed, direction, color)
-}
-
 /// Set RGB settings with specific mode
 /// mode: 0x07 = Dazzle, 0x08 = Static Color
 pub fn akko_set_rgb_settings_with_mode(
@@ -118,7 +194,366 @@ pub fn akko_set_rgb_settings_with_mode(
     color: (u8, u8, u8),
     mode: u8,
 ) -> Result<CommandResult, String> {
-    let device = AkkoHidDevice::open(model.vid(), model.pid())?;
-    commands::cmd_handshake(&device)?;
-    commands::cmd_set_rgb_settings_with_mode(&device, brightness, speed, direction, color, mode)
+    let lighting = RgbSettings {
+        direction,
+        speed: speed.min(4),
+        brightness: brightness.min(4),
+        mode,
+        color,
+    };
+    akko_apply_lighting(model, lighting)
+}
+
+/// Switch the active profile; returns the profile info read back
+/// Fails if the keyboard does not report the new profile as active
+/// UNVERIFIED: SetActiveProfile (0x70) is guessed, no capture confirms it
+pub fn akko_set_active_profile(model: AkkoModel, profile: u8) -> Result<ProfileInfo, String> {
+    let device = connect_for(model, AkkoOpcode::SetActiveProfile)?;
+
+    let profiles = commands::cmd_read_profile_info(&device)?;
+    if profile >= profiles.count {
+        return Err(format!(
+            "Profile {} out of range (keyboard has {})",
+            profile, profiles.count
+        ));
+    }
+
+    let expected = ProfileInfo {
+        count: profiles.count,
+        active: profile,
+    };
+    let result = verify::write_verified(
+        &RetryPolicy::default(),
+        &expected,
+        || commands::cmd_set_active_profile(&device, profile),
+        || commands::cmd_read_profile_info(&device),
+    )?;
+    verify::ensure_verified(result)?;
+
+    Ok(expected)
+}
+
+/// Send lighting and read it back
+/// Only speed, brightness and color are read back (see RgbReadback), and
+/// their GET layout is not confirmed by a capture: a mismatch is logged and
+/// reported in `verification`, not retried or turned into an error
+fn write_lighting(device: &LockedDevice, lighting: RgbSettings) -> Result<CommandResult, String> {
+    verify::write_verified(
+        &RetryPolicy::once(),
+        &lighting,
+        || commands::cmd_apply_rgb_settings(device, lighting),
+        || commands::cmd_read_rgb_settings(device).map(|read| read.apply_to(lighting)),
+    )
+    .map(verify::warn_unverified)
+}
+
+/// ensure_verified, except lighting only warns (see write_lighting)
+fn ensure_section_verified(
+    section: ConfigSection,
+    result: CommandResult,
+) -> Result<CommandResult, String> {
+    match section {
+        ConfigSection::Lighting => Ok(verify::warn_unverified(result)),
+        _ => verify::ensure_verified(result),
+    }
+}
+
+/// Read the lighting of the active profile (speed, brightness and color;
+/// the effect is not reported)
+pub fn akko_get_lighting(model: AkkoModel) -> Result<RgbReadback, String> {
+    let (device, _) = connect(model)?;
+    commands::cmd_read_rgb_settings(&device)
+}
+
+/// Send complete lighting settings
+pub fn akko_apply_lighting(
+    model: AkkoModel,
+    lighting: RgbSettings,
+) -> Result<CommandResult, String> {
+    let device = connect_for(model, AkkoOpcode::SetRgbSettings)?;
+    write_lighting(&device, lighting)
+}
+
+/// Read the profile count and active profile
+pub fn akko_get_profile_info(model: AkkoModel) -> Result<ProfileInfo, String> {
+    let (device, _) = connect(model)?;
+    commands::cmd_read_profile_info(&device)
+}
+
+/// Get FN lock state
+pub fn akko_get_fn_lock(model: AkkoModel) -> Result<FnLockSettings, String> {
+    let device = connect_for(model, AkkoOpcode::GetFnLockStatus)?;
+    commands::cmd_read_fn_lock(&device)
+}
+
+/// Set FN lock state (fails if it does not read back)
+/// UNVERIFIED: SetFnLock (0x04) is guessed from its GET, no capture confirms it
+pub fn akko_set_fn_lock(
+    model: AkkoModel,
+    settings: FnLockSettings,
+) -> Result<CommandResult, String> {
+    let device = connect_for(model, AkkoOpcode::SetFnLock)?;
+    verify::write_verified(
+        &RetryPolicy::default(),
+        &settings,
+        || commands::cmd_set_fn_lock(&device, settings),
+        || commands::cmd_read_fn_lock(&device),
+    )
+    .and_then(verify::ensure_verified)
+}
+
+/// Get indicator LED settings
+pub fn akko_get_indicator_led(model: AkkoModel) -> Result<IndicatorLedSettings, String> {
+    let device = connect_for(model, AkkoOpcode::GetIndicatorLed)?;
+    commands::cmd_read_indicator_led(&device)
+}
+
+/// Set indicator LED settings (fails if they do not read back)
+/// UNVERIFIED: SetIndicatorLed (0x11) is guessed from its GET, no capture confirms it
+pub fn akko_set_indicator_led(
+    model: AkkoModel,
+    settings: IndicatorLedSettings,
+) -> Result<CommandResult, String> {
+    let device = connect_for(model, AkkoOpcode::SetIndicatorLed)?;
+    verify::write_verified(
+        &RetryPolicy::default(),
+        &settings,
+        || commands::cmd_set_indicator_led(&device, settings),
+        || commands::cmd_read_indicator_led(&device),
+    )
+    .and_then(verify::ensure_verified)
+}
+
+/// Get sleep settings
+pub fn akko_get_sleep_settings(model: AkkoModel) -> Result<SleepSettings, String> {
+    let device = connect_for(model, AkkoOpcode::GetSleepSettings)?;
+    commands::cmd_read_sleep_settings(&device)
+}
+
+/// Set sleep settings (validated before opening the device, fails if they
+/// do not read back)
+/// UNVERIFIED: SetSleepSettings (0x17) is guessed from its GET, no capture confirms it
+pub fn akko_set_sleep_settings(
+    model: AkkoModel,
+    settings: SleepSettings,
+) -> Result<CommandResult, String> {
+    settings.validate()?;
+
+    let device = connect_for(model, AkkoOpcode::SetSleepSettings)?;
+    verify::write_verified(
+        &RetryPolicy::default(),
+        &settings,
+        || commands::cmd_set_sleep_settings(&device, settings),
+        || commands::cmd_read_sleep_settings(&device),
+    )
+    .and_then(verify::ensure_verified)
+}
+
+/// Get battery percent, charging state and connection mode
+pub fn akko_get_battery_status(model: AkkoModel) -> Result<BatteryStatus, String> {
+    if !model.has_battery() {
+        return Err(format!("{} has no battery", model.name()));
+    }
+
+    let device = connect_for(model, AkkoOpcode::GetBatteryStatus)?;
+    commands::cmd_read_battery_status(&device)
+}
+
+/// Read the typed keyboard state
+/// `known_lighting` is the lighting last sent (see KeyboardState::read)
+pub fn akko_read_state(
+    model: AkkoModel,
+    known_lighting: Option<RgbSettings>,
+) -> Result<KeyboardState, String> {
+    let (device, _firmware) = connect(model)?;
+    Ok(KeyboardState::read(&device, known_lighting))
+}
+
+/// Write sections of a saved state back (e.g. after idle or reconnect)
+/// Returns the sections that were written
+pub fn akko_write_state(
+    model: AkkoModel,
+    state: &KeyboardState,
+    sections: &[ConfigSection],
+) -> Result<Vec<ConfigSection>, String> {
+    let (device, firmware) = connect(model)?;
+    let mut written = Vec::new();
+
+    for &section in sections {
+        if let Some(opcode) = section.set_opcode() {
+            compat::ensure_supported(model, firmware, opcode)?;
+        }
+        if let Some(result) = state.write_section(&device, section)? {
+            ensure_section_verified(section, result)?;
+            written.push(section);
+        }
+    }
+
+    Ok(written)
+}
+
+/// Export everything readable into a configuration document
+/// Lighting is only included when `known_lighting` supplies the effect
+pub fn akko_export_config(
+    model: AkkoModel,
+    known_lighting: Option<RgbSettings>,
+) -> Result<ConfigDocument, String> {
+    info!("Exporting configuration from Akko {}", model.name());
+
+    let (device, firmware) = connect(model)?;
+    let state = KeyboardState::read(&device, known_lighting);
+
+    let read_table = |opcode: AkkoOpcode| match commands::execute_command(&device, opcode) {
+        Ok(result) if result.success => Some(result.response),
+        Ok(_) => None,
+        Err(e) => {
+            warn!("{} failed: {}", opcode.name(), e);
+            None
+        }
+    };
+
+    let tables = RawTables {
+        per_key_colors: read_table(AkkoOpcode::GetCustomRgb),
+        keymaps: read_table(AkkoOpcode::GetLayoutInfo),
+        macros: read_table(AkkoOpcode::GetMacroData),
+    };
+
+    Ok(ConfigDocument::new(model, firmware, state, tables))
+}
+
+/// Compare a document against the keyboard without writing anything
+pub fn akko_preview_restore(
+    model: AkkoModel,
+    doc: &ConfigDocument,
+    sections: &[ConfigSection],
+    allow_model_mapping: bool,
+) -> Result<RestorePreview, String> {
+    let plan = backup::plan_restore(doc, model, sections, allow_model_mapping)?;
+
+    // Lighting is compared on the fields the keyboard reports
+    let (device, _firmware) = connect(model)?;
+    let current = KeyboardState::read(&device, doc.state.lighting);
+
+    let diffs = current
+        .diff(&doc.state)
+        .into_iter()
+        .filter(|d| plan.apply.contains(&d.section))
+        .collect();
+
+    Ok(RestorePreview { plan, diffs })
+}
+
+/// Write the selected sections of a document back to the keyboard
+pub fn akko_restore_config(
+    model: AkkoModel,
+    doc: &ConfigDocument,
+    sections: &[ConfigSection],
+    allow_model_mapping: bool,
+) -> Result<RestoreReport, String> {
+    let plan = backup::plan_restore(doc, model, sections, allow_model_mapping)?;
+    info!(
+        "Restoring {:?} onto Akko {} (skipping {})",
+        plan.apply,
+        model.name(),
+        plan.skipped.len()
+    );
+
+    let (device, firmware) = connect(model)?;
+
+    let mut report = RestoreReport {
+        skipped: plan.skipped,
+        ..Default::default()
+    };
+
+    for section in plan.apply {
+        // Gate per section so one unsupported setter does not abort the rest
+        let outcome = section
+            .set_opcode()
+            .map_or(Ok(()), |opcode| {
+                compat::ensure_supported(model, firmware, opcode)
+            })
+            .and_then(|_| doc.state.write_section(&device, section))
+            .and_then(|result| {
+                result
+                    .map(|result| ensure_section_verified(section, result))
+                    .transpose()
+            });
+
+        match outcome {
+            Ok(_) => report.applied.push(section),
+            Err(reason) => {
+                warn!("Restore of {:?} failed: {}", section, reason);
+                report.failed.push(SkippedSection { section, reason });
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Write profiles from an Akko Cloud export (lighting)
+/// Switching profiles needs the unverified SetActiveProfile, so only the
+/// profile active on the keyboard is written; the others are reported as
+/// skipped until they are made active on the keyboard. Key remaps are
+/// reported as skipped.
+pub fn akko_import_cloud_profiles(
+    model: AkkoModel,
+    import: &CloudImport,
+    indices: &[u8],
+    allow_model_mapping: bool,
+) -> Result<Vec<RestoreReport>, String> {
+    let active = akko_get_profile_info(model)?.active;
+
+    indices
+        .iter()
+        .map(|&index| {
+            let doc = import.to_document(index, model, allow_model_mapping)?;
+            let mut report = if index == active {
+                akko_restore_config(model, &doc, &[ConfigSection::Lighting], allow_model_mapping)?
+            } else {
+                RestoreReport {
+                    skipped: vec![SkippedSection {
+                        section: ConfigSection::Lighting,
+                        reason: format!(
+                            "profile {} is not active (active: {}); switching profiles is unverified, make it active on the keyboard and import again",
+                            index, active
+                        ),
+                    }],
+                    ..Default::default()
+                }
+            };
+            report.skipped.extend(import.skipped_keymap(index));
+            Ok(report)
+        })
+        .collect()
+}
+
+/// Reset the keyboard or a single profile section to factory state
+/// Callers are responsible for confirmation (see reset.rs); `backup` must be
+/// able to undo the reset (see backup::check_reset_backup)
+pub fn akko_reset(
+    model: AkkoModel,
+    scope: ResetScope,
+    backup: &ConfigDocument,
+) -> Result<CommandResult, String> {
+    info!("Reset on Akko {}: {}", model.name(), scope.describe());
+    backup::check_reset_supported(scope)?;
+    backup::check_reset_backup(backup, scope)?;
+
+    let device = connect_for(model, AkkoOpcode::Reset)?;
+
+    if let Some(profile) = scope.profile() {
+        let result = commands::cmd_get_profile_count(&device)?;
+        let profiles = ProfileInfo::from_response(&result.response)
+            .ok_or_else(|| format!("Unexpected profile response: {}", result.hex_short))?;
+
+        if profile >= profiles.count {
+            return Err(format!(
+                "Profile {} out of range (keyboard has {})",
+                profile, profiles.count
+            ));
+        }
+    }
+
+    commands::cmd_reset(&device, scope)
 }
@@ -0,0 +1,359 @@
+//! Portable configuration backups
+//! A versioned JSON document with everything readable from a keyboard
+
+use serde::{Deserialize, Serialize};
+
+use super::detector::AkkoModel;
+use super::protocol::{AkkoOpcode, FirmwareVersion, ResetScope};
+use super::state::{ConfigSection, FieldDiff, KeyboardState};
+
+/// Bump when the document layout changes incompatibly
+pub const CONFIG_FORMAT_VERSION: u32 = 1;
+
+/// Raw first-page responses of multi-packet tables
+/// Kept for reference until paged transfers can write them back
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RawTables {
+    pub per_key_colors: Option<Vec<u8>>,
+    pub keymaps: Option<Vec<u8>>,
+    pub macros: Option<Vec<u8>>,
+}
+
+impl RawTables {
+    fn has_section(&self, section: ConfigSection) -> bool {
+        match section {
+            ConfigSection::PerKeyColors => self.per_key_colors.is_some(),
+            ConfigSection::Keymaps => self.keymaps.is_some(),
+            ConfigSection::Macros => self.macros.is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// Full configuration document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigDocument {
+    pub format_version: u32,
+    pub model: AkkoModel,
+    pub firmware: Option<FirmwareVersion>,
+    /// Unix timestamp (seconds)
+    pub created_at: u64,
+    pub state: KeyboardState,
+    #[serde(default)]
+    pub tables: RawTables,
+}
+
+impl ConfigDocument {
+    pub fn new(
+        model: AkkoModel,
+        firmware: Option<FirmwareVersion>,
+        state: KeyboardState,
+        tables: RawTables,
+    ) -> Self {
+        Self {
+            format_version: CONFIG_FORMAT_VERSION,
+            model,
+            firmware,
+            created_at: unix_now(),
+            state,
+            tables,
+        }
+    }
+
+    /// Suggested file name, e.g. "mod007b-1760000000-pre-reset.json"
+    pub fn file_name(&self, tag: &str) -> String {
+        format!("{}-{}-{}.json", self.model.id(), self.created_at, tag)
+    }
+
+    fn has_section(&self, section: ConfigSection) -> bool {
+        if section.is_table() {
+            self.tables.has_section(section)
+        } else {
+            self.state.has_section(section)
+        }
+    }
+}
+
+/// A section that will not be restored, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedSection {
+    pub section: ConfigSection,
+    pub reason: String,
+}
+
+/// Which sections a restore will write
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestorePlan {
+    pub apply: Vec<ConfigSection>,
+    pub skipped: Vec<SkippedSection>,
+}
+
+/// Plan plus field-level diff, shown before restoring
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestorePreview {
+    pub plan: RestorePlan,
+    pub diffs: Vec<FieldDiff>,
+}
+
+/// Outcome of a restore
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RestoreReport {
+    pub applied: Vec<ConfigSection>,
+    pub skipped: Vec<SkippedSection>,
+    pub failed: Vec<SkippedSection>,
+}
+
+/// Decide which of the requested sections can be restored onto `target`
+///
+/// A document from another model is rejected unless `allow_model_mapping`
+/// is set, in which case layout-specific sections are skipped.
+pub fn plan_restore(
+    doc: &ConfigDocument,
+    target: AkkoModel,
+    sections: &[ConfigSection],
+    allow_model_mapping: bool,
+) -> Result<RestorePlan, String> {
+    if doc.format_version > CONFIG_FORMAT_VERSION {
+        return Err(format!(
+            "Backup format v{} is newer than supported v{}",
+            doc.format_version, CONFIG_FORMAT_VERSION
+        ));
+    }
+
+    let mapped = doc.model != target;
+    if mapped && !allow_model_mapping {
+        return Err(format!(
+            "Backup was made on {} and cannot be restored onto {}",
+            doc.model.name(),
+            target.name()
+        ));
+    }
+
+    let mut plan = RestorePlan::default();
+
+    // Keep restore order regardless of request order
+    for section in ConfigSection::ALL {
+        if !sections.contains(&section) {
+            continue;
+        }
+
+        let reason = if !doc.has_section(section) {
+            Some("not present in backup".to_string())
+        } else if mapped && section.is_layout_specific() {
+            Some(format!("layout of {} differs", doc.model.name()))
+        } else if section.is_table() {
+            Some("multi-packet tables cannot be written yet".to_string())
+        } else {
+            section.unverified_opcode().map(|opcode| {
+                format!(
+                    "{} (0x{:02X}) is unverified and not sent by restore",
+                    opcode.name(),
+                    u8::from(opcode)
+                )
+            })
+        };
+
+        match reason {
+            Some(reason) => plan.skipped.push(SkippedSection { section, reason }),
+            None => plan.apply.push(section),
+        }
+    }
+
+    Ok(plan)
+}
+
+/// Sections a reset wipes
+fn reset_sections(scope: ResetScope) -> &'static [ConfigSection] {
+    match scope {
+        ResetScope::Factory => &ConfigSection::ALL,
+        ResetScope::Lighting { .. } => &[ConfigSection::Lighting],
+        ResetScope::Keymap { .. } => &[ConfigSection::Keymaps],
+        ResetScope::Macros { .. } => &[ConfigSection::Macros],
+    }
+}
+
+/// Refuse resets that cannot be sent safely, before anything is read
+/// Tables cannot be written back yet, so no backup could undo a reset that
+/// wipes them; and the Reset opcode is a guess until a capture confirms it.
+pub fn check_reset_supported(scope: ResetScope) -> Result<(), String> {
+    if let Some(section) = reset_sections(scope).iter().find(|s| s.is_table()) {
+        return Err(format!(
+            "Cannot {}: {:?} cannot be restored from a backup yet",
+            scope.describe(),
+            section
+        ));
+    }
+    if !AkkoOpcode::Reset.is_verified() {
+        return Err(format!(
+            "Cannot {}: the reset command (0x01) has not been confirmed by a capture",
+            scope.describe()
+        ));
+    }
+    Ok(())
+}
+
+/// Check that `backup` can undo `scope` before resetting
+///
+/// Every wiped section must have been read and be restorable. The backup
+/// holds the lighting of the active profile only, so a lighting reset is
+/// only covered for that profile.
+pub fn check_reset_backup(backup: &ConfigDocument, scope: ResetScope) -> Result<(), String> {
+    let plan = plan_restore(backup, backup.model, reset_sections(scope), false)?;
+    if let Some(skipped) = plan.skipped.first() {
+        return Err(format!(
+            "Refusing to {}: the backup cannot restore {:?} ({})",
+            scope.describe(),
+            skipped.section,
+            skipped.reason
+        ));
+    }
+
+    if let ResetScope::Lighting { profile } = scope {
+        match backup.state.profiles {
+            Some(profiles) if profiles.active == profile => {}
+            Some(profiles) => {
+                return Err(format!(
+                    "Refusing to {}: the backup holds the lighting of profile {} only; switch to profile {} first",
+                    scope.describe(),
+                    profiles.active,
+                    profile
+                ))
+            }
+            None => {
+                return Err(format!(
+                    "Refusing to {}: the active profile could not be read",
+                    scope.describe()
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Current unix time in seconds
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::akko::protocol::{FnLockSettings, RgbSettings};
+
+    const LIGHTING: RgbSettings = RgbSettings {
+        direction: 4,
+        speed: 2,
+        brightness: 3,
+        mode: 0x08,
+        color: (255, 0, 0),
+    };
+
+    fn doc(model: AkkoModel) -> ConfigDocument {
+        ConfigDocument::new(
+            model,
+            Some(FirmwareVersion::new(1, 2)),
+            KeyboardState {
+                fn_lock: Some(FnLockSettings { enabled: true }),
+                ..Default::default()
+            },
+            RawTables {
+                keymaps: Some(vec![0x85]),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_plan_same_model() {
+        let mut backup = doc(AkkoModel::Mod007b);
+        backup.state.lighting = Some(LIGHTING);
+        let plan = plan_restore(&backup, AkkoModel::Mod007b, &ConfigSection::ALL, false).unwrap();
+
+        assert_eq!(plan.apply, vec![ConfigSection::Lighting]);
+        assert!(plan
+            .skipped
+            .iter()
+            .any(|s| s.section == ConfigSection::Performance));
+        // Backed up, but its setter is a guess
+        assert!(plan
+            .skipped
+            .iter()
+            .any(|s| s.section == ConfigSection::FnLock && s.reason.contains("unverified")));
+    }
+
+    #[test]
+    fn test_plan_other_model() {
+        let mut backup = doc(AkkoModel::Mod007b);
+        backup.state.lighting = Some(LIGHTING);
+        let sections = [ConfigSection::Lighting, ConfigSection::Keymaps];
+
+        assert!(plan_restore(&backup, AkkoModel::Akko24GWireless, &sections, false).is_err());
+
+        let plan = plan_restore(&backup, AkkoModel::Akko24GWireless, &sections, true).unwrap();
+        assert_eq!(plan.apply, vec![ConfigSection::Lighting]);
+        assert!(plan.skipped[0].reason.contains("layout"));
+    }
+
+    #[test]
+    fn test_reset_scopes_are_refused() {
+        for scope in [
+            ResetScope::Factory,
+            ResetScope::Keymap { profile: 0 },
+            ResetScope::Macros { profile: 0 },
+        ] {
+            assert!(check_reset_supported(scope)
+                .unwrap_err()
+                .contains("cannot be restored"));
+        }
+
+        // Restorable, but the opcode is unconfirmed
+        assert!(check_reset_supported(ResetScope::Lighting { profile: 0 })
+            .unwrap_err()
+            .contains("capture"));
+    }
+
+    #[test]
+    fn test_reset_needs_a_restorable_backup() {
+        use crate::devices::akko::protocol::ProfileInfo;
+
+        let mut backup = doc(AkkoModel::Mod007b);
+        let lighting = ResetScope::Lighting { profile: 1 };
+
+        // Lighting was not read
+        assert!(check_reset_backup(&backup, lighting).is_err());
+
+        backup.state.lighting = Some(RgbSettings {
+            direction: 4,
+            speed: 2,
+            brightness: 3,
+            mode: 0x08,
+            color: (255, 0, 0),
+        });
+        backup.state.profiles = Some(ProfileInfo {
+            count: 3,
+            active: 0,
+        });
+        // Only the active profile's lighting is in the backup
+        assert!(check_reset_backup(&backup, lighting)
+            .unwrap_err()
+            .contains("switch to profile 1"));
+        assert!(check_reset_backup(&backup, ResetScope::Lighting { profile: 0 }).is_ok());
+
+        // Tables are read but cannot be written back
+        let keymap = check_reset_backup(&backup, ResetScope::Keymap { profile: 0 }).unwrap_err();
+        assert!(keymap.contains("Keymaps"));
+        assert!(check_reset_backup(&backup, ResetScope::Factory).is_err());
+    }
+
+    #[test]
+    fn test_document_roundtrip() {
+        let backup = doc(AkkoModel::Mod007b);
+        let json = serde_json::to_string(&backup).unwrap();
+        let parsed: ConfigDocument = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.state, backup.state);
+        assert_eq!(parsed.format_version, CONFIG_FORMAT_VERSION);
+    }
+}
@@ -0,0 +1,518 @@
+//! Importer for Linux usbmon captures (Wireshark pcap / pcapng)
+//! Pulls the HID feature report traffic of one keyboard out of a capture of
+//! Akko Cloud and decodes it with the known opcodes, for reverse engineering
+//!
+//! - SET_REPORT (21 09, report type 3): packet is in the submit URB
+//! - GET_REPORT (A1 01, report type 3): answer is in the completion URB
+//! - usbmon only records bus/device numbers; VID/PID are matched through the
+//!   device descriptor seen during enumeration, or an address is given
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use super::protocol::{AkkoOpcode, AkkoPacket, DecodedField, PacketDirection, PACKET_SIZE};
+
+const LINKTYPE_USB_LINUX: u32 = 189;
+const LINKTYPE_USB_LINUX_MMAPPED: u32 = 220;
+
+const XFER_CONTROL: u8 = 2;
+const HID_SET_REPORT: (u8, u8) = (0x21, 0x09);
+const HID_GET_REPORT: (u8, u8) = (0xA1, 0x01);
+const GET_DESCRIPTOR: (u8, u8) = (0x80, 0x06);
+const FEATURE_REPORT: u8 = 3;
+const DEVICE_DESCRIPTOR: u16 = 0x0100;
+
+/// USB bus / device number pair as seen by usbmon
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct UsbAddress {
+    pub bus: u16,
+    pub device: u8,
+}
+
+impl UsbAddress {
+    /// Parse "bus:device" (as shown by lsusb / Wireshark, e.g. "1:7")
+    pub fn parse(s: &str) -> Option<Self> {
+        let (bus, device) = s.split_once(':')?;
+        Some(Self {
+            bus: bus.trim().parse().ok()?,
+            device: device.trim().parse().ok()?,
+        })
+    }
+}
+
+/// One decoded feature report
+#[derive(Debug, Clone, Serialize)]
+pub struct CapturedPacket {
+    /// Seconds since the first packet of the transcript
+    pub time: f64,
+    pub direction: PacketDirection,
+    pub address: UsbAddress,
+    pub opcode: u8,
+    pub opcode_name: String,
+    pub known: bool,
+    pub checksum_valid: bool,
+    pub fields: Vec<DecodedField>,
+    pub hex: String,
+}
+
+/// Result of importing a capture
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureTranscript {
+    pub vid: u16,
+    pub pid: u16,
+    pub addresses: Vec<UsbAddress>,
+    pub packets: Vec<CapturedPacket>,
+    /// Opcodes seen that are not in the schema yet
+    pub unknown_opcodes: Vec<u8>,
+    /// Annotated, human readable transcript
+    pub text: String,
+}
+
+// ============ pcap / pcapng ============
+
+/// Byte order aware reads
+#[derive(Clone, Copy)]
+struct Endian {
+    little: bool,
+}
+
+impl Endian {
+    fn u16(&self, data: &[u8], at: usize) -> Option<u16> {
+        let bytes: [u8; 2] = data.get(at..at + 2)?.try_into().ok()?;
+        Some(if self.little {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, data: &[u8], at: usize) -> Option<u32> {
+        let bytes: [u8; 4] = data.get(at..at + 4)?.try_into().ok()?;
+        Some(if self.little {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn u64(&self, data: &[u8], at: usize) -> Option<u64> {
+        let bytes: [u8; 8] = data.get(at..at + 8)?.try_into().ok()?;
+        Some(if self.little {
+            u64::from_le_bytes(bytes)
+        } else {
+            u64::from_be_bytes(bytes)
+        })
+    }
+}
+
+/// A captured frame with its link type
+struct Frame<'a> {
+    linktype: u32,
+    endian: Endian,
+    data: &'a [u8],
+}
+
+fn truncated() -> String {
+    "Capture file is truncated".to_string()
+}
+
+fn read_frames(file: &[u8]) -> Result<Vec<Frame<'_>>, String> {
+    match file.get(..4) {
+        Some([0xD4, 0xC3, 0xB2, 0xA1]) | Some([0x4D, 0x3C, 0xB2, 0xA1]) => {
+            read_pcap(file, Endian { little: true })
+        }
+        Some([0xA1, 0xB2, 0xC3, 0xD4]) | Some([0xA1, 0xB2, 0x3C, 0x4D]) => {
+            read_pcap(file, Endian { little: false })
+        }
+        Some([0x0A, 0x0D, 0x0D, 0x0A]) => read_pcapng(file),
+        _ => Err("Not a pcap or pcapng file".to_string()),
+    }
+}
+
+fn read_pcap(file: &[u8], endian: Endian) -> Result<Vec<Frame<'_>>, String> {
+    let linktype = endian.u32(file, 20).ok_or_else(truncated)?;
+    let mut frames = Vec::new();
+    let mut at = 24;
+
+    while at < file.len() {
+        let incl_len = endian.u32(file, at + 8).ok_or_else(truncated)? as usize;
+        let data = file
+            .get(at + 16..at + 16 + incl_len)
+            .ok_or_else(truncated)?;
+        frames.push(Frame {
+            linktype,
+            endian,
+            data,
+        });
+        at += 16 + incl_len;
+    }
+
+    Ok(frames)
+}
+
+fn read_pcapng(file: &[u8]) -> Result<Vec<Frame<'_>>, String> {
+    let mut frames = Vec::new();
+    let mut endian = Endian { little: true };
+    let mut linktypes: Vec<u32> = Vec::new();
+    let mut at = 0;
+
+    while at + 12 <= file.len() {
+        // Section header: byte order magic decides how to read the rest
+        if file[at..at + 4] == [0x0A, 0x0D, 0x0D, 0x0A] {
+            endian.little = file.get(at + 8..at + 12) == Some(&[0x4D, 0x3C, 0x2B, 0x1A]);
+            linktypes.clear();
+        }
+
+        let block_type = endian.u32(file, at).ok_or_else(truncated)?;
+        let length = endian.u32(file, at + 4).ok_or_else(truncated)? as usize;
+        if length < 12 {
+            return Err(format!("Invalid pcapng block length {} at {}", length, at));
+        }
+        let body = file.get(at + 8..at + length - 4).ok_or_else(truncated)?;
+
+        match block_type {
+            // Interface description
+            1 => linktypes.push(endian.u16(body, 0).ok_or_else(truncated)? as u32),
+            // Enhanced packet
+            6 => {
+                let interface = endian.u32(body, 0).ok_or_else(truncated)? as usize;
+                let cap_len = endian.u32(body, 12).ok_or_else(truncated)? as usize;
+                let data = body.get(20..20 + cap_len).ok_or_else(truncated)?;
+                if let Some(&linktype) = linktypes.get(interface) {
+                    frames.push(Frame {
+                        linktype,
+                        endian,
+                        data,
+                    });
+                }
+            }
+            // Simple packet (always interface 0)
+            3 => {
+                let orig_len = endian.u32(body, 0).ok_or_else(truncated)? as usize;
+                let data = &body[4..body.len().min(4 + orig_len)];
+                if let Some(&linktype) = linktypes.first() {
+                    frames.push(Frame {
+                        linktype,
+                        endian,
+                        data,
+                    });
+                }
+            }
+            _ => {}
+        }
+
+        at += length;
+    }
+
+    Ok(frames)
+}
+
+// ============ usbmon ============
+
+/// Parsed usbmon packet header plus data
+struct Urb<'a> {
+    id: u64,
+    complete: bool,
+    control: bool,
+    address: UsbAddress,
+    setup: Option<Setup>,
+    /// Seconds since the epoch
+    time: f64,
+    data: &'a [u8],
+}
+
+#[derive(Clone, Copy)]
+struct Setup {
+    request_type: u8,
+    request: u8,
+    value: u16,
+}
+
+impl Setup {
+    fn is(&self, (request_type, request): (u8, u8)) -> bool {
+        self.request_type == request_type && self.request == request
+    }
+
+    fn is_feature_report(&self) -> bool {
+        (self.value >> 8) as u8 == FEATURE_REPORT
+    }
+}
+
+fn parse_urb<'a>(frame: &Frame<'a>) -> Option<Urb<'a>> {
+    let header_len = match frame.linktype {
+        LINKTYPE_USB_LINUX => 48,
+        LINKTYPE_USB_LINUX_MMAPPED => 64,
+        _ => return None,
+    };
+    let (e, d) = (frame.endian, frame.data);
+    if d.len() < header_len {
+        return None;
+    }
+
+    let setup = (d.get(14) == Some(&0)).then(|| Setup {
+        request_type: d[40],
+        request: d[41],
+        value: e.u16(d, 42).unwrap_or(0),
+    });
+    let len_cap = e.u32(d, 36)? as usize;
+
+    Some(Urb {
+        id: e.u64(d, 0)?,
+        complete: *d.get(8)? == b'C',
+        control: *d.get(9)? == XFER_CONTROL,
+        address: UsbAddress {
+            bus: e.u16(d, 12)?,
+            device: *d.get(11)?,
+        },
+        setup,
+        time: e.u64(d, 16)? as f64 + e.u32(d, 24)? as f64 / 1_000_000.0,
+        data: d.get(header_len..header_len + len_cap)?,
+    })
+}
+
+/// Feature report data without a leading report id byte
+fn report_payload(data: &[u8]) -> &[u8] {
+    if data.len() > PACKET_SIZE {
+        &data[1..]
+    } else {
+        data
+    }
+}
+
+fn decode(
+    time: f64,
+    direction: PacketDirection,
+    address: UsbAddress,
+    data: &[u8],
+) -> CapturedPacket {
+    let packet = AkkoPacket::from_bytes(report_payload(data));
+    let opcode = packet.opcode();
+
+    CapturedPacket {
+        time,
+        direction,
+        address,
+        opcode: opcode.into(),
+        opcode_name: opcode.name().to_string(),
+        known: !matches!(opcode, AkkoOpcode::Unknown(_)),
+        checksum_valid: packet.is_checksum_valid_as(direction),
+        fields: opcode.decode_fields(packet.as_bytes()),
+        hex: packet.to_hex_short(),
+    }
+}
+
+/// Extract and decode the feature reports of `vid`/`pid` from a capture
+///
+/// `address` selects the device directly when the capture does not include
+/// its enumeration.
+pub fn import_capture(
+    file: &[u8],
+    vid: u16,
+    pid: u16,
+    address: Option<UsbAddress>,
+) -> Result<CaptureTranscript, String> {
+    let frames = read_frames(file)?;
+    let urbs: Vec<Urb> = frames.iter().filter_map(parse_urb).collect();
+    if urbs.is_empty() {
+        return Err(
+            "Capture contains no usbmon packets (capture on a usbmonN interface)".to_string(),
+        );
+    }
+
+    let mut pending: HashMap<u64, Setup> = HashMap::new();
+    let mut descriptors: HashMap<UsbAddress, (u16, u16)> = HashMap::new();
+    let mut packets = Vec::new();
+
+    for urb in urbs.iter().filter(|u| u.control) {
+        if !urb.complete {
+            let Some(setup) = urb.setup else { continue };
+            if setup.is(HID_SET_REPORT) && setup.is_feature_report() && !urb.data.is_empty() {
+                packets.push(decode(urb.time, PacketDirection::Tx, urb.address, urb.data));
+            }
+            pending.insert(urb.id, setup);
+            continue;
+        }
+
+        let Some(setup) = pending.remove(&urb.id) else {
+            continue;
+        };
+        if setup.is(HID_GET_REPORT) && setup.is_feature_report() && !urb.data.is_empty() {
+            packets.push(decode(urb.time, PacketDirection::Rx, urb.address, urb.data));
+        } else if setup.is(GET_DESCRIPTOR) && setup.value == DEVICE_DESCRIPTOR {
+            let little = Endian { little: true };
+            if let (Some(v), Some(p)) = (little.u16(urb.data, 8), little.u16(urb.data, 10)) {
+                descriptors.insert(urb.address, (v, p));
+            }
+        }
+    }
+
+    let addresses: BTreeSet<UsbAddress> = match address {
+        Some(address) => [address].into(),
+        None => descriptors
+            .iter()
+            .filter(|(_, ids)| **ids == (vid, pid))
+            .map(|(address, _)| *address)
+            .collect(),
+    };
+    if addresses.is_empty() {
+        return Err(format!(
+            "No device descriptor for {:04X}:{:04X} in the capture; \
+             start capturing before plugging the keyboard in or pass its bus:device address",
+            vid, pid
+        ));
+    }
+
+    packets.retain(|p| addresses.contains(&p.address));
+    if let Some(start) = packets.first().map(|p| p.time) {
+        for packet in &mut packets {
+            packet.time -= start;
+        }
+    }
+
+    let unknown_opcodes: BTreeSet<u8> = packets
+        .iter()
+        .filter(|p| !p.known)
+        .map(|p| p.opcode)
+        .collect();
+
+    let mut transcript = CaptureTranscript {
+        vid,
+        pid,
+        addresses: addresses.into_iter().collect(),
+        packets,
+        unknown_opcodes: unknown_opcodes.into_iter().collect(),
+        text: String::new(),
+    };
+    transcript.text = render(&transcript);
+    Ok(transcript)
+}
+
+/// One line per packet: time, direction, opcode, checksum, decoded fields, hex
+pub fn render(transcript: &CaptureTranscript) -> String {
+    let mut out = format!(
+        "# {:04X}:{:04X}, {} feature reports\n",
+        transcript.vid,
+        transcript.pid,
+        transcript.packets.len()
+    );
+
+    for packet in &transcript.packets {
+        let direction = match packet.direction {
+            PacketDirection::Tx => "TX",
+            PacketDirection::Rx => "RX",
+        };
+        let name = if packet.known {
+            packet.opcode_name.as_str()
+        } else {
+            "??"
+        };
+        let checksum = if packet.checksum_valid {
+            ""
+        } else {
+            " BAD-CHECKSUM"
+        };
+        let fields: Vec<String> = packet
+            .fields
+            .iter()
+            .map(|f| format!("{}={}", f.name, f.value))
+            .collect();
+
+        out.push_str(&format!(
+            "{:>10.6} {} 0x{:02X} {:<18}{} | {}\n",
+            packet.time, direction, packet.opcode, name, checksum, packet.hex
+        ));
+        if !fields.is_empty() {
+            out.push_str(&format!("{:>10} {}\n", "", fields.join(" ")));
+        }
+    }
+
+    if !transcript.unknown_opcodes.is_empty() {
+        let unknown: Vec<String> = transcript
+            .unknown_opcodes
+            .iter()
+            .map(|op| format!("0x{:02X}", op))
+            .collect();
+        out.push_str(&format!("# unknown opcodes: {}\n", unknown.join(", ")));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PCAP: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/usbmon/mod007b_session.pcap"
+    ));
+    const PCAPNG: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/usbmon/mod007b_session.pcapng"
+    ));
+
+    #[test]
+    fn test_import_pcap_and_pcapng() {
+        for file in [PCAP, PCAPNG] {
+            let transcript = import_capture(file, 0x3151, 0x5009, None).unwrap();
+            assert_eq!(transcript.addresses, vec![UsbAddress { bus: 1, device: 7 }]);
+
+            // The mouse's feature report is filtered out
+            let opcodes: Vec<(PacketDirection, u8)> = transcript
+                .packets
+                .iter()
+                .map(|p| (p.direction, p.opcode))
+                .collect();
+            use PacketDirection::{Rx, Tx};
+            assert_eq!(
+                opcodes,
+                vec![
+                    (Tx, 0x8F),
+                    (Rx, 0x8F),
+                    (Tx, 0x87),
+                    (Rx, 0x87),
+                    (Tx, 0x07),
+                    (Rx, 0x07),
+                    (Tx, 0xA3),
+                    (Rx, 0xA3),
+                ]
+            );
+            assert!(transcript.packets.iter().all(|p| p.checksum_valid));
+            assert_eq!(transcript.unknown_opcodes, vec![0xA3]);
+            assert_eq!(transcript.packets[0].time, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_transcript_annotates_fields() {
+        let transcript = import_capture(PCAP, 0x3151, 0x5009, None).unwrap();
+
+        let rgb = &transcript.packets[3];
+        assert_eq!(rgb.opcode_name, "GetRgbSettings");
+        assert_eq!(
+            rgb.fields[0],
+            DecodedField {
+                name: "mode",
+                value: 4
+            }
+        );
+
+        assert!(transcript.text.contains("RX 0x87 GetRgbSettings"));
+        assert!(transcript.text.contains("mode=4 speed=3 brightness=4"));
+        assert!(transcript.text.contains("TX 0xA3 ??"));
+        assert!(transcript.text.ends_with("# unknown opcodes: 0xA3\n"));
+    }
+
+    #[test]
+    fn test_device_selection() {
+        let err = import_capture(PCAP, 0x3151, 0x4011, None).unwrap_err();
+        assert!(err.contains("3151:4011"));
+
+        let mouse = UsbAddress::parse("1:3").unwrap();
+        let transcript = import_capture(PCAP, 0x3151, 0x4011, Some(mouse)).unwrap();
+        assert_eq!(transcript.packets.len(), 1);
+
+        assert!(import_capture(b"not a capture", 0x3151, 0x5009, None).is_err());
+    }
+}
@@ -0,0 +1,328 @@
+//! Importer for profile exports from the official Akko Cloud driver
+//! Converts vendor JSON into ConfigDocument so profiles can be written with restore
+//!
+//! Export layout (from Akko Cloud "Export profile"):
+//! - deviceName / vid / pid: source keyboard
+//! - profiles[]: { name, index, light?, keymap? }
+//! - light: { effect, direction, dazzle, speed (0-4), brightness (0-4), color }
+//!   color is either "#RRGGBB" or [r, g, b]
+//!
+//! Key remaps are parsed for display but cannot be written yet; imports
+//! report them as skipped.
+
+use serde::{Deserialize, Serialize};
+
+use super::backup::{ConfigDocument, RawTables, SkippedSection};
+use super::detector::AkkoModel;
+use super::protocol::{rgb_mode_byte, ColorMode, ProfileInfo, RgbSettings};
+use super::state::{ConfigSection, KeyboardState};
+
+/// Color as exported by the driver
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum CloudColor {
+    Hex(String),
+    Rgb([u8; 3]),
+}
+
+impl CloudColor {
+    fn to_rgb(&self) -> Result<(u8, u8, u8), String> {
+        match self {
+            CloudColor::Rgb([r, g, b]) => Ok((*r, *g, *b)),
+            CloudColor::Hex(hex) => {
+                let digits = hex.trim_start_matches('#');
+                let value = u32::from_str_radix(digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() == 6)
+                    .ok_or_else(|| format!("Invalid color \"{}\"", hex))?;
+                Ok(((value >> 16) as u8, (value >> 8) as u8, value as u8))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CloudLight {
+    effect: u8,
+    #[serde(default)]
+    direction: u8,
+    #[serde(default)]
+    dazzle: bool,
+    speed: u8,
+    brightness: u8,
+    color: CloudColor,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CloudKey {
+    key_index: u16,
+    code: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CloudProfile {
+    name: String,
+    index: u8,
+    light: Option<CloudLight>,
+    #[serde(default)]
+    keymap: Vec<CloudKey>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CloudExport {
+    version: Option<String>,
+    device_name: Option<String>,
+    vid: u16,
+    pid: u16,
+    profiles: Vec<CloudProfile>,
+}
+
+/// Key remap from the export (kept for display, not written yet)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyAssignment {
+    pub key_index: u16,
+    pub code: u32,
+}
+
+/// One profile converted to the app's model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportedProfile {
+    pub name: String,
+    pub index: u8,
+    pub lighting: Option<RgbSettings>,
+    pub keymap: Vec<KeyAssignment>,
+}
+
+/// Parsed vendor export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudImport {
+    /// Driver version that wrote the file
+    pub driver_version: Option<String>,
+    pub device_name: Option<String>,
+    pub vid: u16,
+    pub pid: u16,
+    /// Source model, None if the VID/PID is not registered
+    pub model: Option<AkkoModel>,
+    pub profiles: Vec<ImportedProfile>,
+}
+
+impl CloudImport {
+    /// Parse an export file's contents
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let export: CloudExport =
+            serde_json::from_str(json).map_err(|e| format!("Not an Akko Cloud export: {}", e))?;
+
+        let profiles = export
+            .profiles
+            .into_iter()
+            .map(convert_profile)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            driver_version: export.version,
+            device_name: export.device_name,
+            vid: export.vid,
+            pid: export.pid,
+            model: AkkoModel::from_ids(export.vid, export.pid),
+            profiles,
+        })
+    }
+
+    fn profile(&self, index: u8) -> Result<&ImportedProfile, String> {
+        self.profiles
+            .iter()
+            .find(|p| p.index == index)
+            .ok_or_else(|| format!("Profile {} not found in export", index))
+    }
+
+    /// Build a restorable document for one profile
+    ///
+    /// An export from an unregistered keyboard counts as a model mismatch:
+    /// it is refused unless `allow_model_mapping` is set, and then `target`
+    /// stands in for the source model.
+    pub fn to_document(
+        &self,
+        index: u8,
+        target: AkkoModel,
+        allow_model_mapping: bool,
+    ) -> Result<ConfigDocument, String> {
+        let profile = self.profile(index)?;
+
+        let model = match self.model {
+            Some(model) => model,
+            None if allow_model_mapping => target,
+            None => {
+                return Err(format!(
+                    "Export is from an unknown keyboard ({}, VID: 0x{:04X}, PID: 0x{:04X}); confirm to import it onto {}",
+                    self.device_name.as_deref().unwrap_or("unnamed"),
+                    self.vid,
+                    self.pid,
+                    target.name()
+                ))
+            }
+        };
+
+        let state = KeyboardState {
+            profiles: Some(ProfileInfo {
+                count: 0,
+                active: profile.index,
+            }),
+            lighting: profile.lighting,
+            ..Default::default()
+        };
+
+        Ok(ConfigDocument::new(
+            model,
+            None,
+            state,
+            RawTables::default(),
+        ))
+    }
+
+    /// Key remaps of a profile that an import leaves unwritten
+    pub fn skipped_keymap(&self, index: u8) -> Option<SkippedSection> {
+        let keymap = &self.profile(index).ok()?.keymap;
+        (!keymap.is_empty()).then(|| SkippedSection {
+            section: ConfigSection::Keymaps,
+            reason: format!(
+                "{} key remap(s) not written: keymap writes are not supported yet",
+                keymap.len()
+            ),
+        })
+    }
+}
+
+fn convert_profile(profile: CloudProfile) -> Result<ImportedProfile, String> {
+    let lighting = profile
+        .light
+        .as_ref()
+        .map(|light| convert_light(light).map_err(|e| format!("{}: {}", profile.name, e)))
+        .transpose()?;
+
+    Ok(ImportedProfile {
+        name: profile.name,
+        index: profile.index,
+        lighting,
+        keymap: profile
+            .keymap
+            .iter()
+            .map(|k| KeyAssignment {
+                key_index: k.key_index,
+                code: k.code,
+            })
+            .collect(),
+    })
+}
+
+/// Driver light settings -> protocol RgbSettings
+fn convert_light(light: &CloudLight) -> Result<RgbSettings, String> {
+    if light.brightness > 4 {
+        return Err(format!("brightness must be 0-4, got {}", light.brightness));
+    }
+    if light.speed > 4 {
+        return Err(format!("speed must be 0-4, got {}", light.speed));
+    }
+    if light.direction > 3 {
+        return Err(format!("direction must be 0-3, got {}", light.direction));
+    }
+
+    let color_mode = if light.dazzle {
+        ColorMode::Dazzle
+    } else {
+        ColorMode::Color
+    };
+
+    Ok(RgbSettings {
+        direction: light.effect,
+        speed: light.speed,
+        brightness: light.brightness,
+        mode: rgb_mode_byte(color_mode, light.direction),
+        color: light.color.to_rgb()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILES: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/akko_cloud/mod007b_profiles.json"
+    ));
+    const INVALID: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/akko_cloud/invalid_brightness.json"
+    ));
+
+    #[test]
+    fn test_parse_export() {
+        let import = CloudImport::parse(PROFILES).unwrap();
+        assert_eq!(import.model, Some(AkkoModel::Mod007b));
+        assert_eq!(import.profiles.len(), 3);
+
+        let work = &import.profiles[0];
+        assert_eq!(
+            work.lighting,
+            Some(RgbSettings {
+                direction: 4,
+                speed: 2,
+                brightness: 3,
+                mode: 0x18,
+                color: (0x33, 0xCC, 0xFF),
+            })
+        );
+        assert_eq!(work.keymap.len(), 2);
+
+        let gaming = &import.profiles[1];
+        assert_eq!(gaming.lighting.unwrap().mode, 0x07);
+        assert_eq!(gaming.lighting.unwrap().color, (255, 0, 0));
+
+        assert!(import.profiles[2].lighting.is_none());
+    }
+
+    #[test]
+    fn test_to_document() {
+        let import = CloudImport::parse(PROFILES).unwrap();
+        let doc = import.to_document(1, AkkoModel::Mod007b, false).unwrap();
+
+        assert_eq!(doc.model, AkkoModel::Mod007b);
+        assert_eq!(doc.state.profiles.unwrap().active, 1);
+        assert!(doc.state.lighting.is_some());
+        assert!(import.to_document(7, AkkoModel::Mod007b, false).is_err());
+
+        assert!(import
+            .skipped_keymap(0)
+            .unwrap()
+            .reason
+            .starts_with("2 key"));
+        assert!(import.skipped_keymap(2).is_none());
+    }
+
+    #[test]
+    fn test_unknown_model_needs_confirmation() {
+        let import = CloudImport {
+            model: None,
+            ..CloudImport::parse(PROFILES).unwrap()
+        };
+
+        let err = import
+            .to_document(0, AkkoModel::Akko24GWireless, false)
+            .unwrap_err();
+        assert!(err.contains("unknown keyboard"));
+
+        let doc = import
+            .to_document(0, AkkoModel::Akko24GWireless, true)
+            .unwrap();
+        assert_eq!(doc.model, AkkoModel::Akko24GWireless);
+    }
+
+    #[test]
+    fn test_rejects_invalid_values() {
+        let err = CloudImport::parse(INVALID).unwrap_err();
+        assert!(err.contains("Too bright"));
+        assert!(err.contains("brightness"));
+        assert!(CloudImport::parse("{}").is_err());
+    }
+}
@@ -2,7 +2,11 @@
 //! Based on real Akko Cloud protocol capture
 
 use super::hid::AkkoHidDevice;
-use super::protocol::{AkkoOpcode, AkkoPacket};
+use super::protocol::{
+    AkkoOpcode, AkkoPacket, BatteryStatus, FnLockSettings, IndicatorLedSettings,
+    PerformanceSettings, ProfileInfo, ResetScope, RgbReadback, RgbSettings, SleepSettings,
+};
+use super::verify::Verification;
 use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
 
@@ -14,17 +18,28 @@ pub struct CommandResult {
     pub opcode_name: String,
     pub response: Vec<u8>,
     pub hex_short: String,
+    /// Read-back check for writes (None = not verified, e.g. GETs and resets)
+    #[serde(default)]
+    pub verification: Option<Verification>,
 }
 
 impl CommandResult {
     pub fn from_response(opcode: AkkoOpcode, response: Vec<u8>) -> Self {
         let packet = AkkoPacket::from_bytes(&response);
+        if !packet.is_response_checksum_valid() {
+            warn!(
+                "{} response has a bad checksum: {}",
+                opcode.name(),
+                packet.to_hex_short()
+            );
+        }
         Self {
             success: packet.has_data() || response[0] == u8::from(opcode),
             opcode: opcode.into(),
             opcode_name: opcode.name().to_string(),
             hex_short: packet.to_hex_short(),
             response,
+            verification: None,
         }
     }
 }
@@ -49,7 +64,7 @@ pub fn execute_command(
     info!("Executing: {} (0x{:02X})", opcode.name(), u8::from(opcode));
     debug!("TX: {}", packet.to_hex_string());
 
-    let response = device.send_feature_report(packet.as_bytes())?;
+    let response = device.request(&packet)?;
 
     Ok(CommandResult::from_response(opcode, response))
 }
@@ -71,7 +86,28 @@ pub fn execute_command_with_params(
         param2
     );
 
-    let response = device.send_feature_report(packet.as_bytes())?;
+    let response = device.request(&packet)?;
+
+    Ok(CommandResult::from_response(opcode, response))
+}
+
+/// Execute a SET command with field bytes 1-6 and sum checksum at byte 7
+pub fn execute_set_command(
+    device: &AkkoHidDevice,
+    opcode: AkkoOpcode,
+    fields: [u8; 6],
+) -> Result<CommandResult, String> {
+    let packet = AkkoPacket::with_fields(opcode, &fields);
+
+    info!(
+        "Executing: {} (0x{:02X}) fields={:?}",
+        opcode.name(),
+        u8::from(opcode),
+        fields
+    );
+    debug!("TX: {}", packet.to_hex_string());
+
+    let response = device.request(&packet)?;
 
     Ok(CommandResult::from_response(opcode, response))
 }
@@ -118,27 +154,141 @@ pub fn cmd_get_indicator_led(device: &AkkoHidDevice) -> Result<CommandResult, St
     execute_command(device, AkkoOpcode::GetIndicatorLed)
 }
 
+/// Get sleep settings
+pub fn cmd_get_sleep_settings(device: &AkkoHidDevice) -> Result<CommandResult, String> {
+    execute_command(device, AkkoOpcode::GetSleepSettings)
+}
+
+/// Read and decode profile count and active profile
+pub fn cmd_read_profile_info(device: &AkkoHidDevice) -> Result<ProfileInfo, String> {
+    let result = cmd_get_profile_count(device)?;
+    ProfileInfo::from_response(&result.response)
+        .ok_or_else(|| format!("Unexpected profile response: {}", result.hex_short))
+}
+
+/// Read and decode RGB settings of the active profile
+pub fn cmd_read_rgb_settings(device: &AkkoHidDevice) -> Result<RgbReadback, String> {
+    let result = cmd_get_rgb_settings(device)?;
+    RgbReadback::from_response(&result.response)
+        .ok_or_else(|| format!("Unexpected RGB response: {}", result.hex_short))
+}
+
+/// Read and decode performance settings
+pub fn cmd_read_performance(device: &AkkoHidDevice) -> Result<PerformanceSettings, String> {
+    let result = cmd_get_performance(device)?;
+    PerformanceSettings::from_response(&result.response)
+        .ok_or_else(|| format!("Unexpected performance response: {}", result.hex_short))
+}
+
+/// Switch active profile
+pub fn cmd_set_active_profile(
+    device: &AkkoHidDevice,
+    profile: u8,
+) -> Result<CommandResult, String> {
+    let fields = ProfileInfo {
+        count: 0,
+        active: profile,
+    }
+    .to_fields();
+    execute_set_command(device, AkkoOpcode::SetActiveProfile, fields)
+}
+
+/// Set debounce times
+pub fn cmd_set_performance(
+    device: &AkkoHidDevice,
+    settings: PerformanceSettings,
+) -> Result<CommandResult, String> {
+    execute_set_command(device, AkkoOpcode::SetPerformance, settings.to_fields())
+}
+
+/// Apply decoded RGB settings (same packet as cmd_set_rgb_settings_with_mode)
+pub fn cmd_apply_rgb_settings(
+    device: &AkkoHidDevice,
+    settings: RgbSettings,
+) -> Result<CommandResult, String> {
+    cmd_set_rgb_settings_with_mode(
+        device,
+        settings.brightness,
+        settings.speed,
+        settings.direction,
+        settings.color,
+        settings.mode,
+    )
+}
+
+/// Get battery status (wireless models only)
+pub fn cmd_get_battery_status(device: &AkkoHidDevice) -> Result<CommandResult, String> {
+    execute_command(device, AkkoOpcode::GetBatteryStatus)
+}
+
+/// Read and decode battery status
+pub fn cmd_read_battery_status(device: &AkkoHidDevice) -> Result<BatteryStatus, String> {
+    let result = cmd_get_battery_status(device)?;
+    BatteryStatus::from_response(&result.response)
+        .ok_or_else(|| format!("Unexpected battery response: {}", result.hex_short))
+}
+
+/// Read and decode FN lock state
+pub fn cmd_read_fn_lock(device: &AkkoHidDevice) -> Result<FnLockSettings, String> {
+    let result = cmd_get_fn_lock(device)?;
+    FnLockSettings::from_response(&result.response)
+        .ok_or_else(|| format!("Unexpected FN lock response: {}", result.hex_short))
+}
+
+/// Read and decode indicator LED settings
+pub fn cmd_read_indicator_led(device: &AkkoHidDevice) -> Result<IndicatorLedSettings, String> {
+    let result = cmd_get_indicator_led(device)?;
+    IndicatorLedSettings::from_response(&result.response)
+        .ok_or_else(|| format!("Unexpected indicator LED response: {}", result.hex_short))
+}
+
+/// Read and decode sleep settings
+pub fn cmd_read_sleep_settings(device: &AkkoHidDevice) -> Result<SleepSettings, String> {
+    let result = cmd_get_sleep_settings(device)?;
+    SleepSettings::from_response(&result.response)
+        .ok_or_else(|| format!("Unexpected sleep settings response: {}", result.hex_short))
+}
+
+/// Set FN lock on/off
+pub fn cmd_set_fn_lock(
+    device: &AkkoHidDevice,
+    settings: FnLockSettings,
+) -> Result<CommandResult, String> {
+    execute_set_command(device, AkkoOpcode::SetFnLock, settings.to_fields())
+}
+
+/// Set indicator LED on/off and behaviour
+pub fn cmd_set_indicator_led(
+    device: &AkkoHidDevice,
+    settings: IndicatorLedSettings,
+) -> Result<CommandResult, String> {
+    execute_set_command(device, AkkoOpcode::SetIndicatorLed, settings.to_fields())
+}
+
+/// Set sleep timeout and lights-off-on-sleep
+pub fn cmd_set_sleep_settings(
+    device: &AkkoHidDevice,
+    settings: SleepSettings,
+) -> Result<CommandResult, String> {
+    settings.validate()?;
+    execute_set_command(device, AkkoOpcode::SetSleepSettings, settings.to_fields())
+}
+
+/// Reset keyboard or a single profile section to factory state
+pub fn cmd_reset(device: &AkkoHidDevice, scope: ResetScope) -> Result<CommandResult, String> {
+    warn!("Resetting: {}", scope.describe());
+    execute_set_command(device, AkkoOpcode::Reset, scope.to_fields())
+}
+
 /// Set RGB settings (brightness, speed, direction, and mode with color)
 /// Based on web capture analysis:
 /// - Byte 0: Opcode (0x07)
-/// - Byte 1: Sub-command (0x04)
+/// - Byte 1: Effect ID (historically "direction")
 /// - Byte 2: Speed (INVERTED: UI 4 → protocol 1, UI 0 → protocol 5)
 /// - Byte 3: Brightness (DIRECT: UI value = protocol value)
 /// - Byte 4: Mode (0x07 = Dazzle, 0x08 = Static Color, etc.)
 /// - Bytes 5-7: R, G, B
 /// - Byte 8: Checksum
-pub fn cmd_set_rgb_settings(
-    device: &AkkoHidDevice,
-    brightness: u8,
-    speed: u8,
-    direction: u8,
-    color: (u8, u8, u8),
-) -> Result<CommandResult, String> {
-    cmd_set_rgb_settings_with_mode(device, brightness, speed, direction, color, 0x07)
-    // Default: Dazzle
-}
-
-/// Set RGB settings with specific mode
 pub fn cmd_set_rgb_settings_with_mode(
     device: &AkkoHidDevice,
     brightness: u8,
@@ -147,33 +297,32 @@ pub fn cmd_set_rgb_settings_with_mode(
     color: (u8, u8, u8),
     mode: u8,
 ) -> Result<CommandResult, String> {
-    let mut packet = AkkoPacket::with_opcode(AkkoOpcode::SetRgbSettings);
-
     // Set parameters based on ACTUAL web capture:
     // [7, 1, 5, 4, 7, 255, 0, 0, 232] for Dazzle
     // [7, 1, 5, 4, 8, 255, 0, 0, 231] for Color
-    let data = packet.as_bytes_mut();
-    data[0] = 0x07; // Opcode
-    data[1] = direction; // Direction (Byte 1)
-    data[2] = 5_u8.saturating_sub(speed.min(4)); // Speed INVERTED (UI 4→1, UI 0→5)
-    data[3] = brightness.min(4); // Brightness DIRECT (0-4)
-    data[4] = mode; // Mode: 0x07=Dazzle, 0x08=Static Color
-    data[5] = color.0; // R
-    data[6] = color.1; // G
-    data[7] = color.2; // B
-
-    // Calculate checksum at byte 8: 0xFF - (sum of bytes 0-7 mod 256)
-    let sum: u16 = data[0..8].iter().map(|&b| b as u16).sum();
-    data[8] = (0xFF_u16.wrapping_sub(sum % 256)) as u8;
-
-    println!(
+    // Checksum at byte 8 is filled in by the packet builder
+    let packet = AkkoPacket::with_fields(
+        AkkoOpcode::SetRgbSettings,
+        &[
+            direction,                         // Direction (Byte 1)
+            5_u8.saturating_sub(speed.min(4)), // Speed INVERTED (UI 4→1, UI 0→5)
+            brightness.min(4),                 // Brightness DIRECT (0-4)
+            mode,                              // Mode: 0x07=Dazzle, 0x08=Static Color
+            color.0,                           // R
+            color.1,                           // G
+            color.2,                           // B
+        ],
+    );
+    let data = packet.as_bytes();
+
+    info!(
         "[SET_RGB] brightness={}, speed={} (protocol={}), dir={}, mode=0x{:02X}, color=({},{},{})",
         brightness, speed, data[2], direction, mode, color.0, color.1, color.2
     );
-    println!("[SET_RGB] Packet bytes: {:?}", &data[0..16]);
+    debug!("[SET_RGB] Packet bytes: {:?}", &data[0..16]);
 
-    let response = device.send_feature_report(packet.as_bytes())?;
-    println!(
+    let response = device.request(&packet)?;
+    debug!(
         "[SET_RGB] Response: {:?}",
         &response[0..16.min(response.len())]
     );
@@ -189,11 +338,12 @@ pub fn cmd_set_rgb_settings_with_mode(
 /// Probe a single opcode
 pub fn probe_opcode(device: &AkkoHidDevice, opcode: u8) -> Result<ProbeResult, String> {
     let akko_opcode = AkkoOpcode::from(opcode);
-    let packet = AkkoPacket::with_opcode(akko_opcode);
+    // Unknown opcodes may use another checksum; send them anyway
+    let packet = AkkoPacket::with_opcode(akko_opcode).with_checksum_override();
 
     info!("Probing: 0x{:02X} ({})", opcode, akko_opcode.name());
 
-    match device.send_feature_report(packet.as_bytes()) {
+    match device.send_feature_report(&packet) {
         Ok(response) => {
             let resp_packet = AkkoPacket::from_bytes(&response);
             let has_data = resp_packet.has_data();
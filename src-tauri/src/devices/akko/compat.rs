@@ -0,0 +1,110 @@
+//! Firmware compatibility table
+//! Gates opcodes per model by the firmware version reported in the handshake
+//!
+//! Only lists opcodes KNOWN to be missing on older firmware.
+//! Anything not listed (or an undecodable firmware version) is allowed.
+
+use super::detector::AkkoModel;
+use super::protocol::{AkkoOpcode, FirmwareVersion};
+
+/// Minimum firmware version required for an opcode
+#[derive(Debug, Clone, Copy)]
+pub struct Requirement {
+    pub opcode: AkkoOpcode,
+    pub min_firmware: FirmwareVersion,
+}
+
+const MOD007B_REQUIREMENTS: &[Requirement] = &[
+    // Indicator behaviour byte was added together with the SET command
+    Requirement {
+        opcode: AkkoOpcode::SetIndicatorLed,
+        min_firmware: FirmwareVersion::new(1, 2),
+    },
+    Requirement {
+        opcode: AkkoOpcode::SetSleepSettings,
+        min_firmware: FirmwareVersion::new(1, 2),
+    },
+];
+
+const AKKO_24G_WIRELESS_REQUIREMENTS: &[Requirement] = &[
+    // Early dongle firmware answers 0x9D with zeros
+    Requirement {
+        opcode: AkkoOpcode::GetBatteryStatus,
+        min_firmware: FirmwareVersion::new(1, 1),
+    },
+];
+
+/// Get firmware requirements for a model
+pub fn requirements(model: AkkoModel) -> &'static [Requirement] {
+    match model {
+        AkkoModel::Mod007b => MOD007B_REQUIREMENTS,
+        AkkoModel::Akko24GWireless => AKKO_24G_WIRELESS_REQUIREMENTS,
+    }
+}
+
+/// Check whether the firmware supports an opcode
+pub fn is_supported(model: AkkoModel, firmware: FirmwareVersion, opcode: AkkoOpcode) -> bool {
+    requirements(model)
+        .iter()
+        .filter(|r| r.opcode == opcode)
+        .all(|r| firmware >= r.min_firmware)
+}
+
+/// Refuse with a clear error when the firmware is known not to support an opcode
+pub fn ensure_supported(
+    model: AkkoModel,
+    firmware: Option<FirmwareVersion>,
+    opcode: AkkoOpcode,
+) -> Result<(), String> {
+    let Some(firmware) = firmware else {
+        return Ok(());
+    };
+
+    match requirements(model).iter().find(|r| r.opcode == opcode) {
+        Some(req) if firmware < req.min_firmware => Err(format!(
+            "{} firmware {} does not support {} (0x{:02X}); requires {} or newer",
+            model.name(),
+            firmware,
+            opcode.name(),
+            u8::from(opcode),
+            req.min_firmware
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gating() {
+        let old = FirmwareVersion::new(1, 1);
+        let new = FirmwareVersion::new(1, 2);
+
+        assert!(!is_supported(
+            AkkoModel::Mod007b,
+            old,
+            AkkoOpcode::SetSleepSettings
+        ));
+        assert!(is_supported(
+            AkkoModel::Mod007b,
+            new,
+            AkkoOpcode::SetSleepSettings
+        ));
+        // Unlisted opcodes are always allowed
+        assert!(is_supported(
+            AkkoModel::Mod007b,
+            old,
+            AkkoOpcode::SetRgbSettings
+        ));
+
+        let err = ensure_supported(AkkoModel::Mod007b, Some(old), AkkoOpcode::SetSleepSettings)
+            .unwrap_err();
+        assert!(err.contains("v1.1"));
+        assert!(err.contains("v1.2"));
+
+        // Unknown firmware is not gated
+        assert!(ensure_supported(AkkoModel::Mod007b, None, AkkoOpcode::SetSleepSettings).is_ok());
+    }
+}
@@ -0,0 +1,197 @@
+//! HID report descriptor parsing
+//! Just enough of the HID 1.11 item format to answer two questions about an
+//! interface: which top-level collections (usage page / usage) it declares,
+//! and how large each feature report is.
+//!
+//! Short items only; long items are skipped. Sizes are summed per report ID
+//! (0 = the descriptor uses no report IDs).
+
+use serde::{Deserialize, Serialize};
+
+/// Size of one feature report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureReport {
+    pub report_id: u8,
+    /// Payload bytes (without the report ID)
+    pub size: usize,
+}
+
+/// What an interface's report descriptor declares
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportDescriptor {
+    /// Top-level collections as (usage page, usage)
+    pub collections: Vec<(u16, u16)>,
+    pub feature_reports: Vec<FeatureReport>,
+}
+
+impl ReportDescriptor {
+    pub fn feature_report_size(&self, report_id: u8) -> Option<usize> {
+        self.feature_reports
+            .iter()
+            .find(|r| r.report_id == report_id)
+            .map(|r| r.size)
+    }
+
+    pub fn has_collection(&self, usage_page: u16, usage: u16) -> bool {
+        self.collections.contains(&(usage_page, usage))
+    }
+}
+
+/// Global item state (saved by Push, restored by Pop)
+#[derive(Debug, Clone, Copy, Default)]
+struct Globals {
+    usage_page: u16,
+    report_size: u32,
+    report_count: u32,
+    report_id: u8,
+}
+
+/// Parse a report descriptor
+pub fn parse(bytes: &[u8]) -> Result<ReportDescriptor, String> {
+    let mut descriptor = ReportDescriptor::default();
+    let mut globals = Globals::default();
+    let mut stack: Vec<Globals> = Vec::new();
+    let mut usages: Vec<(u16, u16)> = Vec::new();
+    let mut depth = 0usize;
+    // Feature bits per report ID, in order of appearance
+    let mut feature_bits: Vec<(u8, u32)> = Vec::new();
+
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let prefix = bytes[pos];
+
+        // Long item: 0xFE, data size, tag, data
+        if prefix == 0xFE {
+            let size = *bytes
+                .get(pos + 1)
+                .ok_or_else(|| format!("Truncated long item at byte {}", pos))?;
+            pos += 3 + size as usize;
+            continue;
+        }
+
+        let size = match prefix & 0x03 {
+            3 => 4,
+            n => n as usize,
+        };
+        let data = bytes
+            .get(pos + 1..pos + 1 + size)
+            .ok_or_else(|| format!("Truncated item 0x{:02X} at byte {}", prefix, pos))?;
+        let value = data
+            .iter()
+            .rev()
+            .fold(0u32, |acc, &b| (acc << 8) | b as u32);
+        pos += 1 + size;
+
+        match prefix & 0xFC {
+            // Main items
+            0xB0 => {
+                let bits = globals.report_size * globals.report_count;
+                match feature_bits
+                    .iter_mut()
+                    .find(|(id, _)| *id == globals.report_id)
+                {
+                    Some((_, total)) => *total += bits,
+                    None => feature_bits.push((globals.report_id, bits)),
+                }
+            }
+            0xA0 => {
+                if depth == 0 {
+                    if let Some(&usage) = usages.first() {
+                        descriptor.collections.push(usage);
+                    }
+                }
+                depth += 1;
+            }
+            0xC0 => depth = depth.saturating_sub(1),
+            // Global items
+            0x04 => globals.usage_page = value as u16,
+            0x74 => globals.report_size = value,
+            0x84 => globals.report_id = value as u8,
+            0x94 => globals.report_count = value,
+            0xA4 => stack.push(globals),
+            0xB4 => {
+                globals = stack
+                    .pop()
+                    .ok_or_else(|| format!("Pop without Push at byte {}", pos - 1 - size))?
+            }
+            // Local items: a 4-byte usage carries its own usage page
+            0x08 => usages.push(if size == 4 {
+                ((value >> 16) as u16, value as u16)
+            } else {
+                (globals.usage_page, value as u16)
+            }),
+            _ => {}
+        }
+
+        // Local items only apply to the next main item
+        if (prefix >> 2) & 0x03 == 0 {
+            usages.clear();
+        }
+    }
+
+    descriptor.feature_reports = feature_bits
+        .into_iter()
+        .map(|(report_id, bits)| FeatureReport {
+            report_id,
+            size: bits.div_ceil(8) as usize,
+        })
+        .collect();
+    Ok(descriptor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Vendor collection with one 64-byte feature report, no report IDs
+    const VENDOR: &[u8] = &[
+        0x06, 0xFF, 0xFF, // Usage Page (0xFFFF)
+        0x09, 0x02, // Usage (0x02)
+        0xA1, 0x01, // Collection (Application)
+        0x09, 0x02, //   Usage (0x02)
+        0x15, 0x00, //   Logical Minimum (0)
+        0x26, 0xFF, 0x00, //   Logical Maximum (255)
+        0x75, 0x08, //   Report Size (8)
+        0x95, 0x40, //   Report Count (64)
+        0xB1, 0x02, //   Feature (Data, Var, Abs)
+        0xC0, // End Collection
+    ];
+
+    /// Boot keyboard plus a consumer collection with report IDs, no features
+    const KEYBOARD: &[u8] = &[
+        0x05, 0x01, // Usage Page (Generic Desktop)
+        0x09, 0x06, // Usage (Keyboard)
+        0xA1, 0x01, // Collection (Application)
+        0x85, 0x01, //   Report ID (1)
+        0x05, 0x07, //   Usage Page (Keyboard)
+        0x19, 0xE0, 0x29, 0xE7, //   Usage Minimum / Maximum
+        0x75, 0x01, 0x95, 0x08, //   Report Size (1), Count (8)
+        0x81, 0x02, //   Input (Data, Var, Abs)
+        0xC0, // End Collection
+        0x05, 0x0C, // Usage Page (Consumer)
+        0x09, 0x01, // Usage (Consumer Control)
+        0xA1, 0x01, // Collection (Application)
+        0x85, 0x02, //   Report ID (2)
+        0x75, 0x10, 0x95, 0x01, //   Report Size (16), Count (1)
+        0x81, 0x00, //   Input (Data, Array)
+        0xC0, // End Collection
+    ];
+
+    #[test]
+    fn test_vendor_feature_report() {
+        let descriptor = parse(VENDOR).unwrap();
+        assert_eq!(descriptor.collections, [(0xFFFF, 0x02)]);
+        assert_eq!(descriptor.feature_report_size(0), Some(64));
+        assert!(descriptor.has_collection(0xFFFF, 0x02));
+    }
+
+    #[test]
+    fn test_keyboard_has_no_feature_report() {
+        let descriptor = parse(KEYBOARD).unwrap();
+        assert_eq!(descriptor.collections, [(0x0001, 0x06), (0x000C, 0x01)]);
+        assert!(descriptor.feature_reports.is_empty());
+
+        // Feature item cut off before its data byte
+        assert!(parse(&VENDOR[..VENDOR.len() - 2]).is_err());
+    }
+}
@@ -4,7 +4,7 @@
 use serde::{Deserialize, Serialize};
 
 /// Supported Akko keyboard models
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AkkoModel {
     Mod007b,
     Akko24GWireless,
@@ -12,6 +12,9 @@ pub enum AkkoModel {
 }
 
 impl AkkoModel {
+    /// All registered models
+    pub const ALL: [AkkoModel; 2] = [AkkoModel::Mod007b, AkkoModel::Akko24GWireless];
+
     /// Get VID for this model
     pub fn vid(&self) -> u16 {
         match self {
@@ -28,6 +31,15 @@ impl AkkoModel {
         }
     }
 
+    /// Usage page and usage of the vendor interface that takes the 64-byte
+    /// feature reports (the keyboard, media key and mouse interfaces do not)
+    pub fn feature_usage(&self) -> (u16, u16) {
+        match self {
+            AkkoModel::Mod007b => (0xFFFF, 0x0002),
+            AkkoModel::Akko24GWireless => (0xFFFF, 0x0002),
+        }
+    }
+
     /// Parse model from string
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
@@ -37,6 +49,29 @@ impl AkkoModel {
         }
     }
 
+    /// Stable identifier (accepted by from_str, used in file names)
+    pub fn id(&self) -> &'static str {
+        match self {
+            AkkoModel::Mod007b => "mod007b",
+            AkkoModel::Akko24GWireless => "akko24gwireless",
+        }
+    }
+
+    /// Whether this model has a battery (wireless models)
+    pub fn has_battery(&self) -> bool {
+        match self {
+            AkkoModel::Mod007b => false,
+            AkkoModel::Akko24GWireless => true,
+        }
+    }
+
+    /// Find a registered model by VID/PID
+    pub fn from_ids(vid: u16, pid: u16) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|m| m.vid() == vid && m.pid() == pid)
+    }
+
     /// Get model name as string
     pub fn name(&self) -> &'static str {
         match self {
@@ -49,19 +84,15 @@ impl AkkoModel {
 /// Detect connected Akko devices
 /// Returns list of found models
 pub fn detect_akko_devices() -> Vec<AkkoModel> {
-    use hidapi::HidApi;
-
     let mut found = Vec::new();
 
-    if let Ok(api) = HidApi::new() {
-        let models = [AkkoModel::Mod007b, AkkoModel::Akko24GWireless];
-
-        for model in models {
-            for device in api.device_list() {
-                if device.vendor_id() == model.vid() && device.product_id() == model.pid() {
-                    found.push(model);
-                    break;
-                }
+    if let Ok(backend) = super::transport::backend() {
+        for model in AkkoModel::ALL {
+            if backend
+                .interfaces(model.vid(), model.pid())
+                .is_ok_and(|interfaces| !interfaces.is_empty())
+            {
+                found.push(model);
             }
         }
     }
@@ -0,0 +1,303 @@
+//! Support report for one keyboard
+//! Collects what a support ticket needs in one serializable struct: the HID
+//! interfaces, which one was opened and why, the handshake, the answer to
+//! every known GET with timings and, on Linux, hidraw node permissions.
+//!
+//! Nothing here fails: errors are recorded in the report so a partly working
+//! keyboard still produces everything that could be gathered.
+
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use serde::Serialize;
+
+use super::detector::AkkoModel;
+use super::hid::{self, HidInterfaceInfo, InterfaceSelection};
+use super::protocol::{AkkoOpcode, AkkoPacket, DecodedField, FirmwareVersion, OpcodeDirection};
+use super::queue::LockedDevice;
+use super::transfer::PacketExchange;
+use super::transport::{self, TransportKind};
+
+/// Answer to one GET
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GetResponse {
+    pub opcode: u8,
+    pub name: String,
+    /// Full response as hex, None if the request failed
+    pub hex: Option<String>,
+    pub checksum_valid: Option<bool>,
+    pub fields: Vec<DecodedField>,
+    /// Round trip including retries
+    pub elapsed_ms: f64,
+    pub error: Option<String>,
+}
+
+/// Round trip times over all GETs (milliseconds)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TimingStats {
+    pub count: usize,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+}
+
+impl TimingStats {
+    pub fn from_durations(durations: &[Duration]) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+        let ms: Vec<f64> = durations.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+        Some(Self {
+            count: ms.len(),
+            min_ms: ms.iter().copied().fold(f64::INFINITY, f64::min),
+            max_ms: ms.iter().copied().fold(0.0, f64::max),
+            mean_ms: ms.iter().sum::<f64>() / ms.len() as f64,
+        })
+    }
+}
+
+/// Access to a /dev/hidraw node (Linux)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HidrawPermission {
+    pub path: String,
+    /// Octal permission bits, e.g. "660"
+    pub mode: Option<String>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    /// Opened read/write by this process
+    pub accessible: bool,
+    pub error: Option<String>,
+}
+
+/// Everything known about one keyboard
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiagnoseReport {
+    pub model: String,
+    pub vid: u16,
+    pub pid: u16,
+    /// Backend used to list and open the interfaces
+    pub transport: Option<TransportKind>,
+    /// Every HID interface with this VID/PID
+    pub interfaces: Vec<HidInterfaceInfo>,
+    /// None if the device could not be opened
+    pub selection: Option<InterfaceSelection>,
+    pub firmware: Option<FirmwareVersion>,
+    /// Handshake first, then every other known GET
+    pub gets: Vec<GetResponse>,
+    pub timing: Option<TimingStats>,
+    /// Empty on other platforms
+    pub hidraw: Vec<HidrawPermission>,
+    pub errors: Vec<String>,
+}
+
+/// Send one GET; also returns the raw answer and the round trip time
+fn query<E: PacketExchange>(
+    device: &E,
+    opcode: AkkoOpcode,
+) -> (GetResponse, Option<Vec<u8>>, Duration) {
+    let packet = AkkoPacket::with_opcode(opcode);
+    let start = Instant::now();
+    let result = device.exchange(&packet);
+    let elapsed = start.elapsed();
+
+    let mut response = GetResponse {
+        opcode: opcode.into(),
+        name: opcode.name().to_string(),
+        hex: None,
+        checksum_valid: None,
+        fields: Vec::new(),
+        elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+        error: None,
+    };
+    let data = match result {
+        Ok(data) => {
+            let answer = AkkoPacket::from_bytes(&data);
+            response.hex = Some(answer.to_hex_string());
+            response.checksum_valid = Some(answer.is_response_checksum_valid());
+            response.fields = opcode.decode_fields(&data);
+            Some(data)
+        }
+        Err(e) => {
+            response.error = Some(e);
+            None
+        }
+    };
+    (response, data, elapsed)
+}
+
+/// Handshake, then every other known GET; fills `gets`, `firmware` and `timing`
+pub fn query_gets<E: PacketExchange>(device: &E, report: &mut DiagnoseReport) {
+    let opcodes = std::iter::once(AkkoOpcode::Handshake).chain(
+        AkkoOpcode::ALL
+            .iter()
+            .copied()
+            .filter(|op| op.direction() == OpcodeDirection::Get && *op != AkkoOpcode::Handshake),
+    );
+
+    let mut durations = Vec::new();
+    for opcode in opcodes {
+        let (response, data, elapsed) = query(device, opcode);
+        if opcode == AkkoOpcode::Handshake {
+            report.firmware = data.as_deref().and_then(FirmwareVersion::from_response);
+            if data.is_some() && report.firmware.is_none() {
+                report
+                    .errors
+                    .push("Handshake response has no firmware version".to_string());
+            }
+        }
+        if let Some(e) = &response.error {
+            warn!("Diagnose: {} failed: {}", opcode.name(), e);
+        }
+        durations.push(elapsed);
+        report.gets.push(response);
+    }
+    report.timing = TimingStats::from_durations(&durations);
+}
+
+/// Permissions of the hidraw nodes among `interfaces`
+#[cfg(target_os = "linux")]
+pub fn hidraw_permissions(interfaces: &[HidInterfaceInfo]) -> Vec<HidrawPermission> {
+    interfaces
+        .iter()
+        .filter(|i| i.path.starts_with("/dev/hidraw"))
+        .map(|i| hidraw_permission(&i.path))
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn hidraw_permissions(_interfaces: &[HidInterfaceInfo]) -> Vec<HidrawPermission> {
+    Vec::new()
+}
+
+#[cfg(target_os = "linux")]
+fn hidraw_permission(path: &str) -> HidrawPermission {
+    use std::fs::{self, OpenOptions};
+    use std::os::unix::fs::MetadataExt;
+
+    let mut permission = HidrawPermission {
+        path: path.to_string(),
+        mode: None,
+        uid: None,
+        gid: None,
+        accessible: false,
+        error: None,
+    };
+
+    match fs::metadata(path) {
+        Ok(meta) => {
+            permission.mode = Some(format!("{:o}", meta.mode() & 0o7777));
+            permission.uid = Some(meta.uid());
+            permission.gid = Some(meta.gid());
+        }
+        Err(e) => permission.error = Some(e.to_string()),
+    }
+    match OpenOptions::new().read(true).write(true).open(path) {
+        Ok(_) => permission.accessible = true,
+        Err(e) => permission.error = Some(e.to_string()),
+    }
+    permission
+}
+
+/// Build the report for `model`
+pub fn diagnose(model: AkkoModel) -> DiagnoseReport {
+    info!("Diagnosing Akko {}", model.name());
+
+    let mut report = DiagnoseReport {
+        model: model.name().to_string(),
+        vid: model.vid(),
+        pid: model.pid(),
+        transport: Some(transport::selected()),
+        ..Default::default()
+    };
+
+    match hid::list_interfaces(model.vid(), model.pid()) {
+        Ok(interfaces) => report.interfaces = interfaces,
+        Err(e) => report.errors.push(e),
+    }
+    report.hidraw = hidraw_permissions(&report.interfaces);
+
+    match LockedDevice::open(model) {
+        Ok(device) => {
+            report.selection = Some(device.interface_selection().clone());
+            query_gets(&*device, &mut report);
+        }
+        Err(e) => report.errors.push(e),
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Echoes GETs, answers the handshake with v1.2 and ignores 0x9D
+    struct Fake;
+
+    impl PacketExchange for Fake {
+        fn exchange(&self, packet: &AkkoPacket) -> Result<Vec<u8>, String> {
+            match u8::from(packet.opcode()) {
+                0x9D => Err("Timed out waiting for 0x9D".to_string()),
+                0x8F => Ok(AkkoPacket::from_bytes(&[0x8F, 1, 2, 0, 0, 0, 0, 0x70])
+                    .as_bytes()
+                    .to_vec()),
+                _ => Ok(packet.as_bytes().to_vec()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_query_gets_covers_every_get() {
+        let mut report = DiagnoseReport::default();
+        query_gets(&Fake, &mut report);
+
+        let gets = AkkoOpcode::ALL
+            .iter()
+            .filter(|op| op.direction() == OpcodeDirection::Get)
+            .count();
+        assert_eq!(report.gets.len(), gets);
+        assert_eq!(report.gets[0].name, "Handshake");
+        assert_eq!(report.firmware, Some(FirmwareVersion::new(1, 2)));
+
+        let battery = report.gets.iter().find(|g| g.opcode == 0x9D).unwrap();
+        assert!(battery.hex.is_none() && battery.error.is_some());
+        assert!(report
+            .gets
+            .iter()
+            .filter(|g| g.opcode != 0x9D)
+            .all(|g| g.error.is_none()));
+        assert_eq!(report.timing.unwrap().count, gets);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["gets"][0]["fields"][0]["name"], "fw_major");
+    }
+
+    #[test]
+    fn test_timing_stats() {
+        let stats = TimingStats::from_durations(&[
+            Duration::from_millis(2),
+            Duration::from_millis(6),
+            Duration::from_millis(4),
+        ])
+        .unwrap();
+        assert_eq!((stats.min_ms, stats.max_ms, stats.mean_ms), (2.0, 6.0, 4.0));
+        assert!(TimingStats::from_durations(&[]).is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_hidraw_permission_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("keyboard-hidraw-{}", std::process::id()));
+        std::fs::write(&path, b"").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o660)).unwrap();
+
+        let permission = hidraw_permission(path.to_str().unwrap());
+        assert_eq!(permission.mode.as_deref(), Some("660"));
+
+        let missing = hidraw_permission("/dev/hidraw-does-not-exist");
+        assert!(!missing.accessible && missing.error.is_some());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -5,45 +5,393 @@
 //! - Buffer must be 65 bytes: [report_id (1 byte)] + [payload (64 bytes)]
 //! - report_id = 0 when device doesn't use report IDs
 //! - hidapi does NOT auto-prepend report_id (unlike WebHID)
+//!
+//! Every response echoes the request opcode in byte 0. request() uses that to
+//! wait for the right answer (with a timeout), retry with backoff and
+//! re-handshake when the device answers out of sequence. The timeout is only
+//! checked between reads: feature report reads block (neither hidapi nor
+//! HIDIOCGFEATURE take a timeout), so a device that never answers a read
+//! hangs the caller until the OS gives up on the transfer.
+//!
+//! Keyboards expose several HID interfaces; open() picks the one whose usage
+//! matches the model and whose report descriptor declares a 64-byte feature
+//! report, and remembers it per device path, interface number and usage
+//! (hidraw paths are reassigned on replug).
+//!
+//! Interfaces are found and opened through the selected transport backend
+//! (hidapi or, on Linux, raw hidraw ioctls; see transport.rs).
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use hidapi::{DeviceInfo, HidApi, HidDevice};
+use hidapi::DeviceInfo;
 use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use super::descriptor::{self, ReportDescriptor};
+use super::detector::AkkoModel;
+use super::inspector;
+use super::protocol::{AkkoOpcode, AkkoPacket, OpcodeDirection, PacketDirection};
+use super::transport::{self, HidBackend, HidTransport};
+use super::udev;
 
 const PAYLOAD_SIZE: usize = 64;
 const BUFFER_SIZE: usize = 65; // report_id (1) + payload (64)
 const REPORT_ID: u8 = 0;
 
-/// Wrapper around HidDevice for Akko keyboards
+/// Timeouts and retries for request()
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferPolicy {
+    /// How long to keep polling for the matching response per attempt.
+    /// Checked between reads only; it cannot cut a blocking read short.
+    #[serde(rename = "timeout_ms", with = "millis")]
+    pub timeout: Duration,
+    /// Pause between reads while waiting
+    #[serde(rename = "poll_interval_ms", with = "millis")]
+    pub poll_interval: Duration,
+    /// Re-sends after a failed attempt
+    pub retries: u32,
+    /// Delay before the first re-send, doubled for each further one
+    #[serde(rename = "backoff_ms", with = "millis")]
+    pub backoff: Duration,
+}
+
+impl TransferPolicy {
+    const DEFAULT: Self = Self {
+        timeout: Duration::from_millis(500),
+        poll_interval: Duration::from_millis(10),
+        retries: 2,
+        backoff: Duration::from_millis(20),
+    };
+
+    /// Delay before re-send number `retry` (1-based)
+    pub fn backoff_delay(&self, retry: u32) -> Duration {
+        self.backoff * 2u32.saturating_pow(retry.saturating_sub(1))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.timeout.is_zero() || self.poll_interval.is_zero() {
+            return Err("Timeout and poll interval must be above 0 ms".to_string());
+        }
+        if self.poll_interval > self.timeout {
+            return Err("Poll interval must not exceed the timeout".to_string());
+        }
+        if self.retries > MAX_RETRIES {
+            return Err(format!("At most {} retries are allowed", MAX_RETRIES));
+        }
+        Ok(())
+    }
+}
+
+impl Default for TransferPolicy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Durations as whole milliseconds for the frontend
+mod millis {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(value.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+const MAX_RETRIES: u32 = 10;
+
+static POLICY: Mutex<TransferPolicy> = Mutex::new(TransferPolicy::DEFAULT);
+
+/// Policy for newly opened devices
+pub fn transfer_policy() -> TransferPolicy {
+    *POLICY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Change the policy (applies from the next open)
+pub fn set_transfer_policy(policy: TransferPolicy) -> Result<(), String> {
+    policy.validate()?;
+    *POLICY.lock().unwrap_or_else(|e| e.into_inner()) = policy;
+    Ok(())
+}
+
+/// How a response relates to the request that was sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseCheck {
+    /// The answer: echoes a GET's opcode, or any data after a SET
+    Match,
+    /// Nothing yet (all zero)
+    Empty,
+    /// Answer to another opcode (left over from an earlier request)
+    Stale(u8),
+}
+
+/// Classify a response by its opcode byte
+///
+/// Only GET answers are known to echo the request opcode; no capture shows
+/// what a SET answer looks like, so for SETs any data is accepted (the
+/// original has_data heuristic) and the read-back decides whether it took.
+pub fn check_response(request_opcode: u8, response: &[u8]) -> ResponseCheck {
+    let is_set = AkkoOpcode::from(request_opcode).direction() == OpcodeDirection::Set;
+    match response.first() {
+        _ if response.iter().all(|&b| b == 0) => ResponseCheck::Empty,
+        Some(&op) if op == request_opcode || is_set => ResponseCheck::Match,
+        Some(&op) => ResponseCheck::Stale(op),
+        None => ResponseCheck::Empty,
+    }
+}
+
+/// Refuse packets whose checksum is wrong (unless overridden for probing)
+fn check_sendable(packet: &AkkoPacket) -> Result<(), String> {
+    if packet.is_sendable() {
+        return Ok(());
+    }
+    let scheme = packet.checksum_scheme();
+    Err(format!(
+        "Refusing to send 0x{:02X} with checksum {:02X?} (expected {:02X?} for {:?})",
+        packet.as_bytes()[0],
+        packet.checksum(),
+        scheme.compute(packet.as_bytes()),
+        scheme
+    ))
+}
+
+/// Why one attempt of request() failed
+enum AttemptError {
+    Transport(String),
+    Timeout,
+    Stale(u8),
+}
+
+/// One HID interface of a device (for diagnostics and bug reports)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HidInterfaceInfo {
+    pub path: String,
+    pub interface_number: i32,
+    pub usage_page: u16,
+    pub usage: u16,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub release_number: u16,
+}
+
+impl HidInterfaceInfo {
+    pub(super) fn from_device_info(info: &DeviceInfo) -> Self {
+        Self {
+            path: info.path().to_string_lossy().into_owned(),
+            interface_number: info.interface_number(),
+            usage_page: info.usage_page(),
+            usage: info.usage(),
+            manufacturer: info.manufacturer_string().map(str::to_string),
+            product: info.product_string().map(str::to_string),
+            release_number: info.release_number(),
+        }
+    }
+}
+
+/// List every HID interface with `vid`/`pid` without opening any
+pub fn list_interfaces(vid: u16, pid: u16) -> Result<Vec<HidInterfaceInfo>, String> {
+    transport::backend()?.interfaces(vid, pid)
+}
+
+/// How one interface fared while open() looked for the protocol interface
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterfaceProbe {
+    pub interface: HidInterfaceInfo,
+    pub opened: bool,
+    /// Usage page/usage (from hidapi or a descriptor collection) match the model's
+    pub usage_match: bool,
+    /// Parsed report descriptor, None if it could not be read
+    pub descriptor: Option<ReportDescriptor>,
+    /// Answered a test feature report read (only tried as a last resort)
+    pub feature_reports: bool,
+    pub error: Option<String>,
+}
+
+impl InterfaceProbe {
+    fn new(interface: HidInterfaceInfo) -> Self {
+        Self {
+            interface,
+            opened: false,
+            usage_match: false,
+            descriptor: None,
+            feature_reports: false,
+            error: None,
+        }
+    }
+
+    fn has_usage(&self, usage_page: u16, usage: u16) -> bool {
+        (self.interface.usage_page == usage_page && self.interface.usage == usage)
+            || self
+                .descriptor
+                .as_ref()
+                .is_some_and(|d| d.has_collection(usage_page, usage))
+    }
+
+    /// The descriptor declares the 64-byte feature report (no report IDs)
+    fn declares_feature_report(&self) -> bool {
+        self.descriptor
+            .as_ref()
+            .and_then(|d| d.feature_report_size(REPORT_ID))
+            == Some(PAYLOAD_SIZE)
+    }
+}
+
+/// Pick the protocol interface among opened probes, best first:
+/// 1. expected usage and a 64-byte feature report in the descriptor
+/// 2. a 64-byte feature report in the descriptor
+/// 3. expected usage, descriptor unreadable
+fn choose_interface(probes: &[InterfaceProbe]) -> Option<(usize, String)> {
+    let find =
+        |pred: &dyn Fn(&InterfaceProbe) -> bool| probes.iter().position(|p| p.opened && pred(p));
+
+    if let Some(idx) = find(&|p| p.usage_match && p.declares_feature_report()) {
+        return Some((
+            idx,
+            format!(
+                "Interface {} has the expected usage and declares a 64-byte feature report",
+                idx
+            ),
+        ));
+    }
+    if let Some(idx) = find(&|p| p.declares_feature_report()) {
+        let info = &probes[idx].interface;
+        return Some((
+            idx,
+            format!(
+                "Interface {} declares a 64-byte feature report (usage 0x{:04X}/0x{:04X} is not the expected one)",
+                idx, info.usage_page, info.usage
+            ),
+        ));
+    }
+    find(&|p| p.usage_match && p.descriptor.is_none()).map(|idx| {
+        (
+            idx,
+            format!(
+                "Interface {} has the expected usage (report descriptor unavailable)",
+                idx
+            ),
+        )
+    })
+}
+
+fn read_report_descriptor(device: &dyn HidTransport) -> Result<ReportDescriptor, String> {
+    descriptor::parse(&device.report_descriptor()?)
+}
+
+/// Interface chosen by an earlier open()
+struct CachedInterface {
+    vid: u16,
+    pid: u16,
+    interface: HidInterfaceInfo,
+    reason: String,
+}
+
+/// Same path and still the same interface behind it: /dev/hidrawN numbers
+/// are reassigned on replug, so a path alone may now lead to another one
+fn same_interface(a: &HidInterfaceInfo, b: &HidInterfaceInfo) -> bool {
+    a.path == b.path
+        && a.interface_number == b.interface_number
+        && a.usage_page == b.usage_page
+        && a.usage == b.usage
+}
+
+static INTERFACE_CACHE: Mutex<Vec<CachedInterface>> = Mutex::new(Vec::new());
+
+fn with_cache<T>(f: impl FnOnce(&mut Vec<CachedInterface>) -> T) -> T {
+    f(&mut INTERFACE_CACHE.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+fn cached_interface(vid: u16, pid: u16, interface: &HidInterfaceInfo) -> Option<String> {
+    with_cache(|cache| {
+        cache
+            .iter()
+            .find(|c| c.vid == vid && c.pid == pid && same_interface(&c.interface, interface))
+            .map(|c| c.reason.clone())
+    })
+}
+
+fn cache_interface(vid: u16, pid: u16, interface: &HidInterfaceInfo, reason: &str) {
+    with_cache(|cache| {
+        cache.retain(|c| c.interface.path != interface.path);
+        cache.push(CachedInterface {
+            vid,
+            pid,
+            interface: interface.clone(),
+            reason: reason.to_string(),
+        });
+    });
+}
+
+fn forget_interface(path: &str) {
+    with_cache(|cache| cache.retain(|c| c.interface.path != path));
+}
+
+/// Drop cached interfaces of a device (it was unplugged)
+pub fn forget_device(vid: u16, pid: u16) {
+    with_cache(|cache| cache.retain(|c| c.vid != vid || c.pid != pid));
+}
+
+/// Which interface open() picked, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterfaceSelection {
+    /// Index into `probes`
+    pub selected: usize,
+    pub reason: String,
+    /// Interfaces examined, in order (only the cached one on a cache hit)
+    pub probes: Vec<InterfaceProbe>,
+}
+
+/// Wrapper around an open HID interface for Akko keyboards
 pub struct AkkoHidDevice {
-    device: HidDevice,
+    device: Box<dyn HidTransport>,
+    policy: TransferPolicy,
+    /// VID/PID for the protocol inspector
+    ids: (u16, u16),
+    selection: InterfaceSelection,
 }
 
 impl AkkoHidDevice {
     /// Open an Akko HID device by VID/PID
-    /// Attempts to find the correct interface that supports Feature Reports
+    /// Picks the interface that takes the protocol's feature reports
     pub fn open(vid: u16, pid: u16) -> Result<Self, String> {
         info!("Opening Akko device VID: 0x{:04X}, PID: 0x{:04X}", vid, pid);
 
-        let api = HidApi::new().map_err(|e| format!("Failed to init HID API: {}", e))?;
+        let backend = transport::backend()?;
+        debug!("Using {:?} transport", backend.kind());
 
         // Find the correct interface - keyboards often have multiple HID interfaces
         // We need the one that supports Feature Reports
-        let device = Self::find_feature_report_interface(&api, vid, pid)?;
+        let (device, selection) = Self::find_feature_report_interface(&*backend, vid, pid)
+            .map_err(|e| udev::explain_open_error(vid, pid, e))?;
 
         info!("Device opened successfully");
-        Ok(Self { device })
+        Ok(Self {
+            device,
+            policy: transfer_policy(),
+            ids: (vid, pid),
+            selection,
+        })
     }
 
-    /// Find and open the HID interface that supports Feature Reports
+    /// Find and open the HID interface that takes the protocol's feature reports
+    ///
+    /// A path chosen before is reused. Otherwise every interface is opened
+    /// and ranked by the model's expected usage page/usage and by its report
+    /// descriptor declaring a 64-byte feature report (see choose_interface).
+    /// Only when neither identifies one is a test read used, as before.
     fn find_feature_report_interface(
-        api: &HidApi,
+        backend: &dyn HidBackend,
         vid: u16,
         pid: u16,
-    ) -> Result<HidDevice, String> {
-        let devices: Vec<&DeviceInfo> = api
-            .device_list()
-            .filter(|d| d.vendor_id() == vid && d.product_id() == pid)
-            .collect();
+    ) -> Result<(Box<dyn HidTransport>, InterfaceSelection), String> {
+        let devices = backend.interfaces(vid, pid)?;
 
         if devices.is_empty() {
             return Err(format!(
@@ -54,56 +402,257 @@ impl AkkoHidDevice {
 
         info!("Found {} interface(s) for device", devices.len());
 
-        // Try each interface until we find one that works with Feature Reports
+        if let Some(found) = Self::open_cached_interface(backend, vid, pid, &devices) {
+            return Ok(found);
+        }
+
+        let expected = AkkoModel::from_ids(vid, pid).map(|m| m.feature_usage());
+        let mut probes = Vec::new();
+        let mut opened = Vec::new();
 
         for (idx, dev_info) in devices.iter().enumerate() {
             info!(
                 "Trying interface {}: path={:?}, usage_page=0x{:04X}, usage=0x{:04X}",
-                idx,
-                dev_info.path().to_str().unwrap_or("unknown"),
-                dev_info.usage_page(),
-                dev_info.usage()
+                idx, dev_info.path, dev_info.usage_page, dev_info.usage
             );
 
-            match dev_info.open_device(api) {
+            let mut probe = InterfaceProbe::new(dev_info.clone());
+
+            match backend.open(dev_info) {
                 Ok(device) => {
-                    // Test if this interface supports Feature Reports
-                    let mut test_buf = [0u8; BUFFER_SIZE];
-                    test_buf[0] = REPORT_ID;
-
-                    // Try to get feature report - if it fails, try next interface
-                    if device.get_feature_report(&mut test_buf).is_ok() {
-                        info!("Interface {} supports Feature Reports", idx);
-                        return Ok(device);
-                    } else {
-                        warn!(
-                            "Interface {} does not support Feature Reports, trying next...",
-                            idx
-                        );
+                    probe.opened = true;
+                    match read_report_descriptor(&*device) {
+                        Ok(descriptor) => probe.descriptor = Some(descriptor),
+                        Err(e) => {
+                            warn!("Interface {}: {}", idx, e);
+                            probe.error = Some(e);
+                        }
                     }
+                    opened.push(Some(device));
                 }
                 Err(e) => {
                     let err_msg = format!("Failed to open interface {}: {}", idx, e);
                     warn!("{}", err_msg);
+                    probe.error = Some(err_msg);
+                    opened.push(None);
+                }
+            }
+
+            probe.usage_match = expected.is_some_and(|(page, usage)| probe.has_usage(page, usage));
+            probes.push(probe);
+        }
+
+        if let Some((idx, reason)) = choose_interface(&probes) {
+            info!("{}", reason);
+            let device = opened[idx].take().expect("chosen interface is open");
+            cache_interface(vid, pid, &probes[idx].interface, &reason);
+            let selection = InterfaceSelection {
+                selected: idx,
+                reason,
+                probes,
+            };
+            return Ok((device, selection));
+        }
+
+        // Neither usage nor descriptor identified the interface: fall back
+        // to the first one that answers a feature report read (not cached)
+        for (idx, device) in opened.iter_mut().enumerate() {
+            let Some(dev) = device.as_ref() else {
+                continue;
+            };
+            let mut test_buf = [0u8; BUFFER_SIZE];
+            test_buf[0] = REPORT_ID;
+
+            match dev.get_feature_report(&mut test_buf) {
+                Ok(_) => {
+                    info!("Interface {} supports Feature Reports", idx);
+                    probes[idx].feature_reports = true;
+                    let selection = InterfaceSelection {
+                        selected: idx,
+                        reason: format!(
+                            "No interface matched the expected usage or declared a 64-byte feature report; interface {} answered a feature report read",
+                            idx
+                        ),
+                        probes,
+                    };
+                    return Ok((device.take().expect("checked above"), selection));
+                }
+                Err(e) => {
+                    warn!(
+                        "Interface {} does not support Feature Reports, trying next...",
+                        idx
+                    );
+                    probes[idx].error = Some(e);
                 }
             }
         }
 
         // If no interface worked, try opening the first one anyway
-        devices
-            .first()
-            .ok_or_else(|| "No device found".to_string())?
-            .open_device(api)
-            .map_err(|e| format!("Failed to open device: {}", e))
+        let device = match opened[0].take() {
+            Some(device) => device,
+            None => backend
+                .open(&devices[0])
+                .map_err(|e| format!("Failed to open device: {}", e))?,
+        };
+        let selection = InterfaceSelection {
+            selected: 0,
+            reason: "No interface could be identified; fell back to the first".to_string(),
+            probes,
+        };
+        Ok((device, selection))
+    }
+
+    /// Open the interface cached for this device, if it is still present
+    fn open_cached_interface(
+        backend: &dyn HidBackend,
+        vid: u16,
+        pid: u16,
+        devices: &[HidInterfaceInfo],
+    ) -> Option<(Box<dyn HidTransport>, InterfaceSelection)> {
+        let (dev_info, reason) = devices
+            .iter()
+            .find_map(|d| cached_interface(vid, pid, d).map(|reason| (d, reason)))?;
+
+        let mut probe = InterfaceProbe::new(dev_info.clone());
+        match backend.open(dev_info) {
+            Ok(device) => {
+                info!("Using cached interface {}", probe.interface.path);
+                probe.opened = true;
+                let selection = InterfaceSelection {
+                    selected: 0,
+                    reason: format!("Cached: {}", reason),
+                    probes: vec![probe],
+                };
+                Some((device, selection))
+            }
+            Err(e) => {
+                warn!(
+                    "Cached interface {} failed to open ({}), probing again",
+                    probe.interface.path, e
+                );
+                forget_interface(&probe.interface.path);
+                None
+            }
+        }
+    }
+
+    /// Which interface open() picked, and why
+    pub fn interface_selection(&self) -> &InterfaceSelection {
+        &self.selection
+    }
+
+    /// Send a request and return the response that echoes its opcode
+    ///
+    /// Waits up to `timeout` for the answer, re-sends with backoff on failure
+    /// and re-handshakes first when a stale answer to another opcode shows up.
+    pub fn request(&self, packet: &AkkoPacket) -> Result<Vec<u8>, String> {
+        check_sendable(packet)?;
+        let data = packet.as_bytes();
+        let opcode = data[0];
+        let policy = self.policy;
+        let mut last_error = String::new();
+
+        for attempt in 0..=policy.retries {
+            if attempt > 0 {
+                thread::sleep(policy.backoff_delay(attempt));
+            }
+
+            match self.attempt(data) {
+                Ok(response) => return Ok(response),
+                Err(AttemptError::Transport(e)) => last_error = e,
+                Err(AttemptError::Timeout) => {
+                    last_error = format!(
+                        "no response to 0x{:02X} within {:?}",
+                        opcode, policy.timeout
+                    )
+                }
+                Err(AttemptError::Stale(other)) => {
+                    last_error = format!("0x{:02X} answered with 0x{:02X}", opcode, other);
+                    if opcode != u8::from(AkkoOpcode::Handshake) {
+                        self.resync();
+                    }
+                }
+            }
+            warn!(
+                "Request 0x{:02X} attempt {}/{} failed: {}",
+                opcode,
+                attempt + 1,
+                policy.retries + 1,
+                last_error
+            );
+        }
+
+        Err(format!(
+            "Request 0x{:02X} failed after {} attempts: {}",
+            opcode,
+            policy.retries + 1,
+            last_error
+        ))
+    }
+
+    /// One send plus polling for the matching response
+    ///
+    /// The deadline is checked after each read returns; a read that blocks
+    /// is not interrupted.
+    fn attempt(&self, data: &[u8; PAYLOAD_SIZE]) -> Result<Vec<u8>, AttemptError> {
+        let opcode = data[0];
+        let deadline = Instant::now() + self.policy.timeout;
+
+        self.send_feature_report_only(data)
+            .map_err(AttemptError::Transport)?;
+
+        let mut stale = None;
+        loop {
+            let response = self
+                .receive_feature_report()
+                .map_err(AttemptError::Transport)?;
+
+            match check_response(opcode, &response) {
+                ResponseCheck::Match => return Ok(response),
+                ResponseCheck::Empty => {}
+                ResponseCheck::Stale(other) => stale = Some(other),
+            }
+
+            if Instant::now() >= deadline {
+                return Err(stale.map_or(AttemptError::Timeout, AttemptError::Stale));
+            }
+            thread::sleep(self.policy.poll_interval);
+        }
+    }
+
+    /// Re-handshake to get the device back in step
+    fn resync(&self) {
+        info!("Resyncing with handshake");
+        let handshake = AkkoPacket::with_opcode(AkkoOpcode::Handshake);
+        if let Err(e) = self.attempt(handshake.as_bytes()) {
+            let reason = match e {
+                AttemptError::Transport(e) => e,
+                AttemptError::Timeout => "timeout".to_string(),
+                AttemptError::Stale(op) => format!("answered with 0x{:02X}", op),
+            };
+            warn!("Resync handshake failed: {}", reason);
+        }
     }
 
     /// Send a 64-byte feature report and receive response
+    /// Single exchange without response matching (raw packets, opcode
+    /// probing); use request() for protocol commands
     ///
     /// Windows HID API requires:
     /// - Buffer size = 65 bytes (report_id + 64 bytes payload)
     /// - byte[0] = report_id (0 if not used)
     /// - byte[1..65] = payload
-    pub fn send_feature_report(&self, data: &[u8; PAYLOAD_SIZE]) -> Result<Vec<u8>, String> {
+    pub fn send_feature_report(&self, packet: &AkkoPacket) -> Result<Vec<u8>, String> {
+        check_sendable(packet)?;
+        self.send_feature_report_only(packet.as_bytes())?;
+
+        // Receive response
+        let response = self.receive_feature_report()?;
+
+        Ok(response)
+    }
+
+    fn send_feature_report_only(&self, data: &[u8; PAYLOAD_SIZE]) -> Result<(), String> {
         // Prepare 65-byte buffer: [report_id | payload]
         let mut out_buf = [0u8; BUFFER_SIZE];
         out_buf[0] = REPORT_ID;
@@ -113,16 +662,11 @@ impl AkkoHidDevice {
         self.log_packet("SEND", data);
 
         // Send feature report (must be 65 bytes on Windows)
-        self.device
-            .send_feature_report(&out_buf)
-            .map_err(|e| format!("send_feature_report failed: {} (ensure 65-byte buffer)", e))?;
+        self.device.send_feature_report(&out_buf)?;
 
         info!("Feature report sent successfully");
-
-        // Receive response
-        let response = self.receive_feature_report()?;
-
-        Ok(response)
+        inspector::record(PacketDirection::Tx, self.ids, data);
+        Ok(())
     }
 
     /// Receive a feature report
@@ -132,10 +676,7 @@ impl AkkoHidDevice {
         let mut in_buf = [0u8; BUFFER_SIZE];
         in_buf[0] = REPORT_ID; // Must set report_id before calling get_feature_report
 
-        let bytes_read = self
-            .device
-            .get_feature_report(&mut in_buf)
-            .map_err(|e| format!("get_feature_report failed: {}", e))?;
+        let bytes_read = self.device.get_feature_report(&mut in_buf)?;
 
         info!("Received {} bytes (including report_id)", bytes_read);
 
@@ -149,6 +690,7 @@ impl AkkoHidDevice {
 
         // Log incoming data (payload only)
         self.log_packet("RECV", &payload);
+        inspector::record(PacketDirection::Rx, self.ids, &payload);
 
         Ok(payload)
     }
@@ -163,3 +705,185 @@ impl AkkoHidDevice {
         info!("[{}] Length: {} bytes", direction, data.len());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::super::hidraw::HidrawBackend;
+    use super::*;
+
+    #[test]
+    fn test_check_response() {
+        assert_eq!(check_response(0x87, &[0x87, 1, 5]), ResponseCheck::Match);
+        assert_eq!(check_response(0x87, &[0; 64]), ResponseCheck::Empty);
+        assert_eq!(
+            check_response(0x87, &[0xF0, 3, 0]),
+            ResponseCheck::Stale(0xF0)
+        );
+        assert_eq!(check_response(0x87, &[]), ResponseCheck::Empty);
+
+        // SET answers are not known to echo the opcode
+        assert_eq!(check_response(0x07, &[0x87, 1, 5]), ResponseCheck::Match);
+        assert_eq!(check_response(0x07, &[0; 64]), ResponseCheck::Empty);
+    }
+
+    fn probe(usage_page: u16, descriptor: Option<&[u8]>) -> InterfaceProbe {
+        let mut probe = InterfaceProbe::new(HidInterfaceInfo {
+            path: format!("/dev/hidraw-{:04x}", usage_page),
+            interface_number: 0,
+            usage_page,
+            usage: 0x02,
+            manufacturer: None,
+            product: None,
+            release_number: 0,
+        });
+        probe.opened = true;
+        probe.descriptor = descriptor.map(|d| descriptor::parse(d).unwrap());
+        probe.usage_match = probe.has_usage(0xFFFF, 0x02);
+        probe
+    }
+
+    #[test]
+    fn test_choose_interface_by_usage_and_descriptor() {
+        // 64 x 8-bit feature report, no report IDs
+        let feature: &[u8] = &[0x75, 0x08, 0x95, 0x40, 0xB1, 0x02];
+        // 32 x 8-bit feature report
+        let short: &[u8] = &[0x75, 0x08, 0x95, 0x20, 0xB1, 0x02];
+
+        let probes = [probe(0x0001, Some(feature)), probe(0xFFFF, Some(feature))];
+        assert_eq!(choose_interface(&probes).unwrap().0, 1);
+
+        // Right usage, wrong report size: the descriptor wins
+        let probes = [probe(0xFFFF, Some(short)), probe(0x000C, Some(feature))];
+        let (idx, reason) = choose_interface(&probes).unwrap();
+        assert_eq!(idx, 1);
+        assert!(reason.contains("not the expected"));
+
+        // Usage only counts when the descriptor could not be read
+        assert_eq!(choose_interface(&[probe(0xFFFF, None)]).unwrap().0, 0);
+        assert!(choose_interface(&[probe(0xFFFF, Some(short))]).is_none());
+    }
+
+    #[test]
+    fn test_interface_cache_per_path() {
+        let vendor = probe(0xFFFF, None).interface;
+        cache_interface(0x1234, 0x0001, &vendor, "first");
+        cache_interface(0x1234, 0x0001, &vendor, "second");
+        assert_eq!(
+            cached_interface(0x1234, 0x0001, &vendor).as_deref(),
+            Some("second")
+        );
+        assert!(cached_interface(0x1234, 0x0002, &vendor).is_none());
+
+        // Replugged: the same path is now the boot keyboard interface
+        let reassigned = HidInterfaceInfo {
+            usage_page: 0x0001,
+            usage: 0x06,
+            ..vendor.clone()
+        };
+        assert!(cached_interface(0x1234, 0x0001, &reassigned).is_none());
+
+        forget_interface(&vendor.path);
+        assert!(cached_interface(0x1234, 0x0001, &vendor).is_none());
+
+        cache_interface(0x1234, 0x0001, &vendor, "third");
+        forget_device(0x1234, 0x0001);
+        assert!(cached_interface(0x1234, 0x0001, &vendor).is_none());
+    }
+
+    /// hidraw discovery over the fake sysfs tree; opened interfaces serve
+    /// the report descriptor from sysfs and answer no feature reports
+    struct FakeBackend(HidrawBackend, PathBuf);
+
+    struct FakeTransport(Option<Vec<u8>>);
+
+    impl HidTransport for FakeTransport {
+        fn send_feature_report(&self, _data: &[u8]) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn get_feature_report(&self, _buf: &mut [u8]) -> Result<usize, String> {
+            Err("HIDIOCGFEATURE failed".to_string())
+        }
+
+        fn report_descriptor(&self) -> Result<Vec<u8>, String> {
+            self.0.clone().ok_or_else(|| "no descriptor".to_string())
+        }
+    }
+
+    impl FakeBackend {
+        fn new() -> Self {
+            let sys = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sysfs/sys");
+            let root = udev::SysfsRoot {
+                sys: sys.clone(),
+                dev: PathBuf::from("/dev"),
+            };
+            Self(HidrawBackend::new(root), sys)
+        }
+    }
+
+    impl HidBackend for FakeBackend {
+        fn kind(&self) -> transport::TransportKind {
+            self.0.kind()
+        }
+
+        fn interfaces(&self, vid: u16, pid: u16) -> Result<Vec<HidInterfaceInfo>, String> {
+            self.0.interfaces(vid, pid)
+        }
+
+        fn open(&self, interface: &HidInterfaceInfo) -> Result<Box<dyn HidTransport>, String> {
+            let name = Path::new(&interface.path).file_name().unwrap();
+            let descriptor = self
+                .1
+                .join("class/hidraw")
+                .join(name)
+                .join("device/report_descriptor");
+            Ok(Box::new(FakeTransport(std::fs::read(descriptor).ok())))
+        }
+    }
+
+    #[test]
+    fn test_selects_interface_through_backend() {
+        let backend = FakeBackend::new();
+        let (_, selection) =
+            AkkoHidDevice::find_feature_report_interface(&backend, 0x3151, 0x5009).unwrap();
+        assert_eq!(selection.selected, 1);
+        assert_eq!(selection.probes[1].interface.path, "/dev/hidraw1");
+        assert!(selection.probes[1].usage_match);
+
+        let (_, cached) =
+            AkkoHidDevice::find_feature_report_interface(&backend, 0x3151, 0x5009).unwrap();
+        assert!(cached.reason.starts_with("Cached:"));
+        forget_interface("/dev/hidraw1");
+
+        assert!(AkkoHidDevice::find_feature_report_interface(&backend, 0x3151, 0x4011).is_err());
+    }
+
+    #[test]
+    fn test_backoff_doubles() {
+        let policy = TransferPolicy {
+            backoff: Duration::from_millis(20),
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff_delay(1), Duration::from_millis(20));
+        assert_eq!(policy.backoff_delay(2), Duration::from_millis(40));
+        assert_eq!(policy.backoff_delay(3), Duration::from_millis(80));
+    }
+
+    #[test]
+    fn test_transfer_policy_setting() {
+        let json = serde_json::to_value(TransferPolicy::default()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"timeout_ms": 500, "poll_interval_ms": 10, "retries": 2, "backoff_ms": 20})
+        );
+
+        let too_fast = TransferPolicy {
+            poll_interval: Duration::from_millis(600),
+            ..Default::default()
+        };
+        assert!(set_transfer_policy(too_fast).is_err());
+        assert_eq!(transfer_policy(), TransferPolicy::default());
+    }
+}
@@ -0,0 +1,355 @@
+//! Linux hidraw transport
+//! Talks to /dev/hidraw* directly with the hidraw ioctls instead of going
+//! through hidapi, so errors name the ioctl, the node and the errno.
+//! Interfaces are discovered from sysfs (class/hidraw/*/device: uevent and
+//! report_descriptor) without libusb or hidapi's enumeration.
+//!
+//! Discovery only reads files and takes the sysfs root as a parameter, so it
+//! can be tested against a fake tree. The ioctls are Linux only, and only
+//! built for architectures with the generic _IOC layout (MIPS, PowerPC,
+//! SPARC and Alpha encode directions and sizes differently).
+
+use std::fs;
+use std::path::Path;
+
+use super::descriptor;
+use super::hid::HidInterfaceInfo;
+use super::transport::{HidBackend, HidTransport, TransportKind};
+use super::udev::SysfsRoot;
+
+/// Whether hidraw devices can be opened on this target
+pub const SUPPORTED: bool = cfg!(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+));
+
+/// Value of `key` in a uevent file ("KEY=value" lines)
+fn uevent_value<'a>(uevent: &'a str, key: &str) -> Option<&'a str> {
+    uevent
+        .lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+}
+
+/// USB interface number from HID_PHYS ("usb-0000:00:14.0-2/input1"), -1 if unknown
+fn interface_number(phys: &str) -> i32 {
+    phys.rsplit_once("/input")
+        .and_then(|(_, n)| n.parse().ok())
+        .unwrap_or(-1)
+}
+
+/// Interface info for hidraw node `name`, from sysfs only
+fn interface_info(root: &SysfsRoot, name: &str) -> HidInterfaceInfo {
+    let device = root.hidraw_device(name);
+    let uevent = fs::read_to_string(device.join("uevent")).unwrap_or_default();
+    // Like hidapi: the first top-level collection is the interface's usage
+    let (usage_page, usage) = fs::read(device.join("report_descriptor"))
+        .ok()
+        .and_then(|bytes| descriptor::parse(&bytes).ok())
+        .and_then(|d| d.collections.first().copied())
+        .unwrap_or((0, 0));
+
+    HidInterfaceInfo {
+        path: root.dev.join(name).display().to_string(),
+        interface_number: uevent_value(&uevent, "HID_PHYS").map_or(-1, interface_number),
+        usage_page,
+        usage,
+        manufacturer: None,
+        // The kernel joins manufacturer and product here
+        product: uevent_value(&uevent, "HID_NAME").map(str::to_string),
+        release_number: 0,
+    }
+}
+
+/// Finds interfaces under `root` and opens their /dev nodes
+#[derive(Debug, Clone, Default)]
+pub struct HidrawBackend {
+    root: SysfsRoot,
+}
+
+impl HidrawBackend {
+    pub fn new(root: SysfsRoot) -> Self {
+        Self { root }
+    }
+}
+
+impl HidBackend for HidrawBackend {
+    fn kind(&self) -> TransportKind {
+        TransportKind::Hidraw
+    }
+
+    fn interfaces(&self, vid: u16, pid: u16) -> Result<Vec<HidInterfaceInfo>, String> {
+        Ok(self
+            .root
+            .hidraw_nodes(vid, pid)
+            .iter()
+            .map(|node| interface_info(&self.root, &node.name))
+            .collect())
+    }
+
+    fn open(&self, interface: &HidInterfaceInfo) -> Result<Box<dyn HidTransport>, String> {
+        open_node(Path::new(&interface.path))
+    }
+}
+
+#[cfg(not(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+)))]
+fn open_node(path: &Path) -> Result<Box<dyn HidTransport>, String> {
+    Err(format!(
+        "Cannot open {}: hidraw is not supported on this platform",
+        path.display()
+    ))
+}
+
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+))]
+use linux::open_node;
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+))]
+pub use linux::HidrawDevice;
+
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+))]
+mod linux {
+    use std::fs::{File, OpenOptions};
+    use std::io;
+    use std::mem::size_of;
+    use std::os::unix::io::AsRawFd;
+    use std::path::{Path, PathBuf};
+
+    use super::super::transport::{HidTransport, MAX_DESCRIPTOR_SIZE};
+
+    /// struct hidraw_report_descriptor
+    #[repr(C)]
+    struct RawDescriptor {
+        size: u32,
+        value: [u8; MAX_DESCRIPTOR_SIZE],
+    }
+
+    /// struct hidraw_devinfo
+    #[repr(C)]
+    #[derive(Default)]
+    struct RawInfo {
+        bustype: u32,
+        vendor: i16,
+        product: i16,
+    }
+
+    const IOC_WRITE: u32 = 1;
+    const IOC_READ: u32 = 2;
+
+    /// _IOC(dir, 'H', nr, size) in the generic layout (x86, ARM, RISC-V)
+    const fn hid_ioc(dir: u32, nr: u32, size: usize) -> u32 {
+        (dir << 30) | ((size as u32) << 16) | ((b'H' as u32) << 8) | nr
+    }
+
+    const HIDIOCGRDESCSIZE: u32 = hid_ioc(IOC_READ, 0x01, size_of::<i32>());
+    const HIDIOCGRDESC: u32 = hid_ioc(IOC_READ, 0x02, size_of::<RawDescriptor>());
+    const HIDIOCGRAWINFO: u32 = hid_ioc(IOC_READ, 0x03, size_of::<RawInfo>());
+
+    const fn hidiocsfeature(len: usize) -> u32 {
+        hid_ioc(IOC_WRITE | IOC_READ, 0x06, len)
+    }
+
+    const fn hidiocgfeature(len: usize) -> u32 {
+        hid_ioc(IOC_WRITE | IOC_READ, 0x07, len)
+    }
+
+    pub fn open_node(path: &Path) -> Result<Box<dyn HidTransport>, String> {
+        let device = HidrawDevice::open(path)?;
+        let (bus, vid, pid) = device.raw_info()?;
+        log::debug!(
+            "Opened {} (bus 0x{:02X}, VID: 0x{:04X}, PID: 0x{:04X})",
+            path.display(),
+            bus,
+            vid,
+            pid
+        );
+        Ok(Box::new(device))
+    }
+
+    /// An open /dev/hidraw* node
+    pub struct HidrawDevice {
+        file: File,
+        path: PathBuf,
+    }
+
+    impl HidrawDevice {
+        pub fn open(path: &Path) -> Result<Self, String> {
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(path)
+                .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+            Ok(Self {
+                file,
+                path: path.to_path_buf(),
+            })
+        }
+
+        /// Run one ioctl; errors name the request, the node and the errno
+        fn ioctl<T>(&self, name: &str, request: u32, arg: *mut T) -> Result<usize, String> {
+            // SAFETY: the fd stays open while self lives and `arg` points to a
+            // buffer of the size encoded in `request`
+            let ret = unsafe { libc::ioctl(self.file.as_raw_fd(), request as _, arg) };
+            if ret < 0 {
+                return Err(format!(
+                    "{} on {} failed: {}",
+                    name,
+                    self.path.display(),
+                    io::Error::last_os_error()
+                ));
+            }
+            Ok(ret as usize)
+        }
+
+        /// Bus type, VID and PID as the kernel sees them
+        pub fn raw_info(&self) -> Result<(u32, u16, u16), String> {
+            let mut info = RawInfo::default();
+            self.ioctl("HIDIOCGRAWINFO", HIDIOCGRAWINFO, &mut info)?;
+            Ok((info.bustype, info.vendor as u16, info.product as u16))
+        }
+    }
+
+    impl HidTransport for HidrawDevice {
+        fn send_feature_report(&self, data: &[u8]) -> Result<(), String> {
+            let mut buf = data.to_vec();
+            self.ioctl(
+                "HIDIOCSFEATURE",
+                hidiocsfeature(buf.len()),
+                buf.as_mut_ptr(),
+            )?;
+            Ok(())
+        }
+
+        fn get_feature_report(&self, buf: &mut [u8]) -> Result<usize, String> {
+            self.ioctl(
+                "HIDIOCGFEATURE",
+                hidiocgfeature(buf.len()),
+                buf.as_mut_ptr(),
+            )
+        }
+
+        fn report_descriptor(&self) -> Result<Vec<u8>, String> {
+            let mut size: i32 = 0;
+            self.ioctl("HIDIOCGRDESCSIZE", HIDIOCGRDESCSIZE, &mut size)?;
+
+            let mut descriptor = RawDescriptor {
+                size: (size.max(0) as u32).min(MAX_DESCRIPTOR_SIZE as u32),
+                value: [0; MAX_DESCRIPTOR_SIZE],
+            };
+            self.ioctl("HIDIOCGRDESC", HIDIOCGRDESC, &mut descriptor)?;
+            Ok(descriptor.value[..descriptor.size as usize].to_vec())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_ioctl_numbers() {
+            assert_eq!(HIDIOCGRDESCSIZE, 0x8004_4801);
+            assert_eq!(HIDIOCGRDESC, 0x9004_4802);
+            assert_eq!(HIDIOCGRAWINFO, 0x8008_4803);
+            assert_eq!(hidiocsfeature(65), 0xC041_4806);
+            assert_eq!(hidiocgfeature(65), 0xC041_4807);
+        }
+
+        #[test]
+        fn test_ioctl_errors_name_request_and_node() {
+            // A regular file rejects hidraw ioctls with ENOTTY
+            let path =
+                std::env::temp_dir().join(format!("keyboard-hidraw-io-{}", std::process::id()));
+            std::fs::write(&path, b"").unwrap();
+
+            let device = HidrawDevice::open(&path).unwrap();
+            let mut buf = [0u8; 65];
+            let err = device.get_feature_report(&mut buf).unwrap_err();
+            assert!(err.starts_with(&format!("HIDIOCGFEATURE on {} failed:", path.display())));
+            assert!(device.raw_info().unwrap_err().contains("HIDIOCGRAWINFO"));
+            std::fs::remove_file(&path).unwrap();
+
+            assert!(matches!(
+                HidrawDevice::open(Path::new("/dev/hidraw-does-not-exist")),
+                Err(e) if e.contains("/dev/hidraw-does-not-exist")
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn fake_backend() -> HidrawBackend {
+        let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/sysfs");
+        HidrawBackend::new(SysfsRoot {
+            sys: Path::new(fixtures).join("sys"),
+            dev: PathBuf::from("/dev"),
+        })
+    }
+
+    #[test]
+    fn test_interfaces_from_sysfs() {
+        let interfaces = fake_backend().interfaces(0x3151, 0x5009).unwrap();
+        assert_eq!(interfaces.len(), 2);
+
+        let keyboard = &interfaces[0];
+        assert_eq!(keyboard.path, "/dev/hidraw0");
+        assert_eq!((keyboard.usage_page, keyboard.usage), (0x0001, 0x06));
+        assert_eq!(keyboard.interface_number, 0);
+
+        let vendor = &interfaces[1];
+        assert_eq!((vendor.usage_page, vendor.usage), (0xFFFF, 0x02));
+        assert_eq!(vendor.interface_number, 1);
+        assert_eq!(vendor.product.as_deref(), Some("Akko MOD007B"));
+
+        // No report_descriptor in sysfs: usage unknown, still listed
+        let receiver = fake_backend().interfaces(0x046D, 0xC52B).unwrap();
+        assert_eq!(
+            (receiver[0].usage_page, receiver[0].interface_number),
+            (0, 2)
+        );
+        assert_eq!(interface_number("usb-0000:00:14.0-2"), -1);
+    }
+}
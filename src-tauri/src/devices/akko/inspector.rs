@@ -0,0 +1,293 @@
+//! Live protocol inspector
+//! The HID layer reports every feature report it sends or receives. Packets
+//! are decoded, kept in a ring buffer and, unless paused, handed to a sink
+//! (the app forwards them to the frontend as events).
+//!
+//! The filter applies to the live stream and to snapshots; changing it shows
+//! buffered packets it previously hid. All-zero reads (polling while waiting
+//! for an answer) are only buffered while the filter includes them.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use super::protocol::{AkkoOpcode, AkkoPacket, DecodedField, PacketDirection};
+
+/// Default ring buffer size (packets)
+pub const DEFAULT_CAPACITY: usize = 500;
+
+/// Which packets to show
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InspectorFilter {
+    /// Only these opcodes (empty = all)
+    pub opcodes: Vec<u8>,
+    /// Only this direction (None = both)
+    pub direction: Option<PacketDirection>,
+    /// Include all-zero reads
+    pub include_empty: bool,
+    /// Only packets whose checksum is wrong
+    pub bad_checksum_only: bool,
+}
+
+impl InspectorFilter {
+    const fn new() -> Self {
+        Self {
+            opcodes: Vec::new(),
+            direction: None,
+            include_empty: false,
+            bad_checksum_only: false,
+        }
+    }
+
+    pub fn matches(&self, packet: &InspectorPacket) -> bool {
+        (self.opcodes.is_empty() || self.opcodes.contains(&packet.opcode))
+            && (self.direction.is_none() || self.direction == Some(packet.direction))
+            && (self.include_empty || !packet.empty)
+            && (!self.bad_checksum_only || !packet.checksum_valid)
+    }
+}
+
+/// Inspector settings (changed from the UI at runtime)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InspectorSettings {
+    /// Stop the live stream; packets are still buffered
+    pub paused: bool,
+    /// Ring buffer size (packets)
+    pub capacity: usize,
+    pub filter: InspectorFilter,
+}
+
+impl InspectorSettings {
+    const fn new() -> Self {
+        Self {
+            paused: false,
+            capacity: DEFAULT_CAPACITY,
+            filter: InspectorFilter::new(),
+        }
+    }
+}
+
+impl Default for InspectorSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One decoded feature report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InspectorPacket {
+    /// Increasing packet number (gaps = dropped from the buffer)
+    pub seq: u64,
+    /// Unix time in milliseconds
+    pub timestamp_ms: i64,
+    pub direction: PacketDirection,
+    pub vid: u16,
+    pub pid: u16,
+    pub opcode: u8,
+    pub opcode_name: String,
+    pub known: bool,
+    pub checksum_valid: bool,
+    /// All bytes zero (no answer yet)
+    pub empty: bool,
+    pub fields: Vec<DecodedField>,
+    pub hex: String,
+}
+
+impl InspectorPacket {
+    fn decode(
+        seq: u64,
+        timestamp_ms: i64,
+        direction: PacketDirection,
+        (vid, pid): (u16, u16),
+        data: &[u8],
+    ) -> Self {
+        let packet = AkkoPacket::from_bytes(data);
+        let opcode = packet.opcode();
+        Self {
+            seq,
+            timestamp_ms,
+            direction,
+            vid,
+            pid,
+            opcode: opcode.into(),
+            opcode_name: opcode.name().to_string(),
+            known: !matches!(opcode, AkkoOpcode::Unknown(_)),
+            checksum_valid: packet.is_checksum_valid_as(direction),
+            empty: data.iter().all(|&b| b == 0),
+            fields: opcode.decode_fields(packet.as_bytes()),
+            hex: packet.to_hex_string(),
+        }
+    }
+}
+
+type Sink = Arc<dyn Fn(&InspectorPacket) + Send + Sync>;
+
+struct Inspector {
+    settings: InspectorSettings,
+    buffer: VecDeque<InspectorPacket>,
+    next_seq: u64,
+    sink: Option<Sink>,
+}
+
+impl Inspector {
+    const fn new() -> Self {
+        Self {
+            settings: InspectorSettings::new(),
+            buffer: VecDeque::new(),
+            next_seq: 0,
+            sink: None,
+        }
+    }
+
+    /// Buffer a packet; returns the sink to call if it should be streamed
+    fn push(&mut self, packet: InspectorPacket) -> Option<(Sink, InspectorPacket)> {
+        if packet.empty && !self.settings.filter.include_empty {
+            return None;
+        }
+
+        self.buffer.push_back(packet.clone());
+        self.trim();
+
+        if self.settings.paused || !self.settings.filter.matches(&packet) {
+            return None;
+        }
+        self.sink.clone().map(|sink| (sink, packet))
+    }
+
+    fn trim(&mut self) {
+        while self.buffer.len() > self.settings.capacity {
+            self.buffer.pop_front();
+        }
+    }
+
+    fn snapshot(&self) -> Vec<InspectorPacket> {
+        self.buffer
+            .iter()
+            .filter(|p| self.settings.filter.matches(p))
+            .cloned()
+            .collect()
+    }
+}
+
+static INSPECTOR: Mutex<Inspector> = Mutex::new(Inspector::new());
+
+fn with_inspector<T>(f: impl FnOnce(&mut Inspector) -> T) -> T {
+    f(&mut INSPECTOR.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Record a packet sent to / received from `vid`/`pid`
+pub fn record(direction: PacketDirection, device: (u16, u16), data: &[u8]) {
+    let timestamp_ms = chrono::Utc::now().timestamp_millis();
+    let live = with_inspector(|inspector| {
+        let seq = inspector.next_seq;
+        inspector.next_seq += 1;
+        inspector.push(InspectorPacket::decode(
+            seq,
+            timestamp_ms,
+            direction,
+            device,
+            data,
+        ))
+    });
+
+    // Outside the lock: the sink may be slow (IPC)
+    if let Some((sink, packet)) = live {
+        sink(&packet);
+    }
+}
+
+/// Where live packets go (replaces any previous sink)
+pub fn set_sink(sink: impl Fn(&InspectorPacket) + Send + Sync + 'static) {
+    with_inspector(|inspector| inspector.sink = Some(Arc::new(sink)));
+}
+
+pub fn settings() -> InspectorSettings {
+    with_inspector(|inspector| inspector.settings.clone())
+}
+
+pub fn set_settings(settings: InspectorSettings) {
+    with_inspector(|inspector| {
+        inspector.settings = InspectorSettings {
+            capacity: settings.capacity.max(1),
+            ..settings
+        };
+        inspector.trim();
+    });
+}
+
+/// Buffered packets that match the current filter, oldest first
+pub fn snapshot() -> Vec<InspectorPacket> {
+    with_inspector(|inspector| inspector.snapshot())
+}
+
+pub fn clear() {
+    with_inspector(|inspector| inspector.buffer.clear());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(seq: u64, direction: PacketDirection, data: &[u8]) -> InspectorPacket {
+        InspectorPacket::decode(seq, 0, direction, (0x3151, 0x5009), data)
+    }
+
+    #[test]
+    fn test_decode_and_filter() {
+        let rx = packet(0, PacketDirection::Rx, &[0x97, 0, 10, 1]);
+        assert_eq!(rx.opcode_name, "GetSleepSettings");
+        assert_eq!(rx.fields[0].name, "timeout_min");
+        assert!(rx.checksum_valid);
+
+        let filter = InspectorFilter {
+            opcodes: vec![0x97],
+            direction: Some(PacketDirection::Tx),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&rx));
+        assert!(InspectorFilter {
+            direction: None,
+            ..filter
+        }
+        .matches(&rx));
+
+        let empty = packet(1, PacketDirection::Rx, &[0; 64]);
+        assert!(!InspectorFilter::default().matches(&empty));
+    }
+
+    #[test]
+    fn test_ring_buffer_and_pause() {
+        let streamed = Arc::new(Mutex::new(Vec::new()));
+        let mut inspector = Inspector::new();
+        let log = streamed.clone();
+        inspector.sink = Some(Arc::new(move |p: &InspectorPacket| {
+            log.lock().unwrap().push(p.seq)
+        }));
+        inspector.settings.capacity = 3;
+
+        let record = |inspector: &mut Inspector, seq| {
+            let handshake = AkkoPacket::with_opcode(AkkoOpcode::Handshake);
+            let pkt = packet(seq, PacketDirection::Tx, handshake.as_bytes());
+            if let Some((sink, pkt)) = inspector.push(pkt) {
+                sink(&pkt);
+            }
+        };
+
+        record(&mut inspector, 0);
+        inspector.settings.paused = true;
+        for seq in 1..5 {
+            record(&mut inspector, seq);
+        }
+        // Empty reads are dropped unless the filter asks for them
+        assert!(inspector
+            .push(packet(9, PacketDirection::Rx, &[0; 64]))
+            .is_none());
+
+        assert_eq!(*streamed.lock().unwrap(), vec![0]);
+        let seqs: Vec<u64> = inspector.snapshot().iter().map(|p| p.seq).collect();
+        assert_eq!(seqs, vec![2, 3, 4]);
+    }
+}
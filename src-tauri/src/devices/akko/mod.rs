@@ -1,10 +1,34 @@
 pub mod api;
+pub mod backup;
+pub mod capture;
+pub mod cloud_import;
 pub mod commands;
+pub mod compat;
+pub mod descriptor;
 pub mod detector;
+pub mod diagnose;
 pub mod hid;
+pub mod hidraw;
+pub mod inspector;
 pub mod models;
 pub mod protocol;
+pub mod queue;
+pub mod state;
+pub mod transfer;
+pub mod transport;
+pub mod udev;
+pub mod verify;
 
-pub use api::{akko_handshake, akko_send_packet};
+pub use api::{akko_handshake, akko_send_packet, DeviceInfo};
+pub use backup::{ConfigDocument, RestorePreview, RestoreReport};
+pub use cloud_import::CloudImport;
 pub use commands::{CommandResult, ProbeResult};
 pub use detector::AkkoModel;
+pub use diagnose::DiagnoseReport;
+pub use hid::TransferPolicy;
+pub use protocol::{
+    BatteryStatus, ColorMode, ConnectionMode, FirmwareVersion, FnLockSettings, IndicatorBehaviour,
+    IndicatorLedSettings, ResetScope, SleepSettings,
+};
+pub use state::{ConfigSection, FieldDiff, KeyboardState};
+pub use transport::TransportKind;
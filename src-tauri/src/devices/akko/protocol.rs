@@ -7,6 +7,18 @@
 //! - Byte 3-6: Reserved
 //! - Byte 7: Checksum = 0xFF - Opcode
 //! - Byte 8-63: Payload
+//!
+//! SET commands use opcode = GET opcode - 0x80 (e.g. 0x07 / 0x87) and carry
+//! their fields right after the opcode, followed by a sum checksum. Only the
+//! 0x07 / 0x87 pair has been captured; the other SETs apply that rule to their
+//! GET and are marked unverified in the table below.
+//! Each opcode knows its ChecksumScheme; AkkoPacket fills it in automatically.
+//!
+//! Opcodes are declared once in the akko_opcodes! table below; conversions,
+//! names, field layouts and the docs reference table are generated from it, and
+//! the typed from_response decoders read their bytes by field name from it.
+
+use serde::{Deserialize, Serialize};
 
 /// Packet size for all Akko commands
 pub const PACKET_SIZE: usize = 64;
@@ -17,133 +29,374 @@ pub fn calc_checksum(opcode: u8) -> u8 {
     0xFF_u8.wrapping_sub(opcode)
 }
 
-/// Known Akko opcodes (from real Akko Cloud capture)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[repr(u8)]
-pub enum AkkoOpcode {
-    /// Handshake / Init (0x8F) - Response: [8F, fw_major, fw_minor, ...]
-    Handshake = 0x8F,
+/// Calculate sum checksum for SET packets (0xFF - sum of preceding bytes)
+#[inline]
+pub fn calc_sum_checksum(data: &[u8]) -> u8 {
+    let sum: u16 = data.iter().map(|&b| b as u16).sum();
+    (0xFF_u16.wrapping_sub(sum % 256)) as u8
+}
 
-    /// Get profile count (0xF0) - Response: [F0, count, active_profile, ...]
-    GetProfileCount = 0xF0,
+/// Where and how a packet's checksum is computed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChecksumScheme {
+    /// 0xFF - opcode at byte 7 (GET commands)
+    Opcode,
+    /// 0xFF - sum of all bytes before `position` (SET commands)
+    Sum { position: usize },
+    /// No checksum (GET responses carry data in byte 7)
+    None,
+}
+
+impl ChecksumScheme {
+    /// Byte holding the checksum
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            ChecksumScheme::Opcode => Some(7),
+            ChecksumScheme::Sum { position } => Some(*position),
+            ChecksumScheme::None => None,
+        }
+    }
+
+    /// Expected checksum byte for `data`
+    pub fn compute(&self, data: &[u8; PACKET_SIZE]) -> Option<u8> {
+        match self {
+            ChecksumScheme::Opcode => Some(calc_checksum(data[0])),
+            ChecksumScheme::Sum { position } => Some(calc_sum_checksum(&data[..*position])),
+            ChecksumScheme::None => None,
+        }
+    }
+
+    /// Whether `data` carries the right checksum (always true for None)
+    pub fn is_valid(&self, data: &[u8; PACKET_SIZE]) -> bool {
+        match (self.position(), self.compute(data)) {
+            (Some(position), Some(expected)) => data[position] == expected,
+            _ => true,
+        }
+    }
+}
 
-    /// Get device info (0x80) - Response: [80, type, status, ...]
-    GetDeviceInfo = 0x80,
+/// Host to keyboard (TX) or keyboard to host (RX)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PacketDirection {
+    Tx,
+    Rx,
+}
 
-    /// Set RGB settings (0x07) - Command: [07, 04, brightness, speed, 07, R, G, B]
-    SetRgbSettings = 0x07,
+/// Whether an opcode reads or writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpcodeDirection {
+    Get,
+    Set,
+}
 
-    /// Get RGB settings (0x87) - Response: [87, mode, speed, dir, 0, R, G, B]
-    GetRgbSettings = 0x87,
+/// A named byte in a response (or, for SETs, the echoed request)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ResponseField {
+    pub name: &'static str,
+    pub byte: usize,
+}
 
-    /// Get RGB mode (0x88) - Response: [88, mode, p1, p2, brightness, R, G, B]
-    GetRgbMode = 0x88,
+/// Raw value of a ResponseField in a received packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DecodedField {
+    pub name: &'static str,
+    pub value: u8,
+}
 
-    /// Get performance settings (0x92) - Response: [92, debounce_dn, 0, debounce_up, ...]
-    GetPerformance = 0x92,
+/// Generates AkkoOpcode, its byte conversions, names, directions, checksum
+/// schemes and response layouts from one table
+///
+/// Entry: `/// description` `Variant = byte, Get|Set, checksum, [field @ byte, ...];`
+/// where checksum is a ChecksumScheme variant. Opcodes not seen in a capture
+/// end with `, unverified` before the semicolon.
+macro_rules! akko_opcodes {
+    (@verified) => { true };
+    (@verified unverified) => { false };
+    ($(
+        $(#[doc = $doc:literal])*
+        $variant:ident = $byte:literal, $direction:ident, $checksum:expr,
+            [$($field:ident @ $pos:literal),* $(,)?] $(, $unverified:ident)?;
+    )*) => {
+        /// Known Akko opcodes (from real Akko Cloud capture, except those
+        /// marked unverified in the schema; see `is_verified`)
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum AkkoOpcode {
+            $(
+                $(#[doc = $doc])*
+                $variant,
+            )*
+            /// Unknown opcode for probing
+            Unknown(u8),
+        }
 
-    /// Get FN lock status (0x84) - Response: [84, 0, enabled, ...]
-    GetFnLockStatus = 0x84,
+        impl From<u8> for AkkoOpcode {
+            fn from(value: u8) -> Self {
+                match value {
+                    $($byte => AkkoOpcode::$variant,)*
+                    other => AkkoOpcode::Unknown(other),
+                }
+            }
+        }
 
-    /// Get indicator LED settings (0x91) - Response: [91, 0, enabled, ...]
-    GetIndicatorLed = 0x91,
+        impl From<AkkoOpcode> for u8 {
+            fn from(opcode: AkkoOpcode) -> Self {
+                match opcode {
+                    $(AkkoOpcode::$variant => $byte,)*
+                    AkkoOpcode::Unknown(v) => v,
+                }
+            }
+        }
 
-    /// Get sleep settings (0x97)
-    GetSleepSettings = 0x97,
+        impl AkkoOpcode {
+            /// Every known opcode, in schema order
+            pub const ALL: &'static [AkkoOpcode] = &[$(AkkoOpcode::$variant),*];
 
-    /// Get custom RGB (0x86)
-    GetCustomRgb = 0x86,
+            /// Get command name for logging
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(AkkoOpcode::$variant => stringify!($variant),)*
+                    AkkoOpcode::Unknown(_) => "Unknown",
+                }
+            }
 
-    /// Get macro status (0xAE)
-    GetMacroStatus = 0xAE,
+            /// Description from the schema (empty for unknown opcodes)
+            pub fn description(&self) -> String {
+                let lines: &[&str] = match self {
+                    $(AkkoOpcode::$variant => &[$($doc),*],)*
+                    AkkoOpcode::Unknown(_) => &[],
+                };
+                lines.iter().map(|l| l.trim()).collect::<Vec<_>>().join(" ")
+            }
 
-    /// Get macro data (0xAD)
-    GetMacroData = 0xAD,
+            /// Whether the opcode and its layout come from a capture
+            /// Unverified ones are guesses: never send them without the user asking
+            pub fn is_verified(&self) -> bool {
+                match self {
+                    $(AkkoOpcode::$variant => akko_opcodes!(@verified $($unverified)?),)*
+                    AkkoOpcode::Unknown(_) => false,
+                }
+            }
 
-    /// Get layout info (0x85)
-    GetLayoutInfo = 0x85,
+            /// Read or write; unknown opcodes follow the GET = SET + 0x80 rule
+            pub fn direction(&self) -> OpcodeDirection {
+                match self {
+                    $(AkkoOpcode::$variant => OpcodeDirection::$direction,)*
+                    AkkoOpcode::Unknown(v) if *v >= 0x80 => OpcodeDirection::Get,
+                    AkkoOpcode::Unknown(_) => OpcodeDirection::Set,
+                }
+            }
 
-    /// Get battery status (0x9D)
-    GetBatteryStatus = 0x9D,
+            /// Checksum of packets sent with this opcode
+            /// Unknown opcodes use the GET scheme, like the probes that find them
+            pub fn checksum_scheme(&self) -> ChecksumScheme {
+                use ChecksumScheme::*;
+                match self {
+                    $(AkkoOpcode::$variant => $checksum,)*
+                    AkkoOpcode::Unknown(_) => Opcode,
+                }
+            }
 
-    /// Unknown opcode for probing
-    Unknown(u8),
+            /// Named bytes of the response
+            pub fn response_fields(&self) -> &'static [ResponseField] {
+                match self {
+                    $(AkkoOpcode::$variant => &[$(ResponseField {
+                        name: stringify!($field),
+                        byte: $pos,
+                    }),*],)*
+                    AkkoOpcode::Unknown(_) => &[],
+                }
+            }
+        }
+    };
 }
 
-impl From<u8> for AkkoOpcode {
-    fn from(value: u8) -> Self {
-        match value {
-            0x07 => AkkoOpcode::SetRgbSettings,
-            0x8F => AkkoOpcode::Handshake,
-            0xF0 => AkkoOpcode::GetProfileCount,
-            0x80 => AkkoOpcode::GetDeviceInfo,
-            0x87 => AkkoOpcode::GetRgbSettings,
-            0x88 => AkkoOpcode::GetRgbMode,
-            0x92 => AkkoOpcode::GetPerformance,
-            0x84 => AkkoOpcode::GetFnLockStatus,
-            0x91 => AkkoOpcode::GetIndicatorLed,
-            0x97 => AkkoOpcode::GetSleepSettings,
-            0x86 => AkkoOpcode::GetCustomRgb,
-            0xAE => AkkoOpcode::GetMacroStatus,
-            0xAD => AkkoOpcode::GetMacroData,
-            0x85 => AkkoOpcode::GetLayoutInfo,
-            0x9D => AkkoOpcode::GetBatteryStatus,
-            other => AkkoOpcode::Unknown(other),
+akko_opcodes! {
+    /// Handshake / Init
+    Handshake = 0x8F, Get, Opcode, [fw_major @ 1, fw_minor @ 2];
+
+    /// Get profile count
+    GetProfileCount = 0xF0, Get, Opcode, [count @ 1, active @ 2];
+
+    /// Get device info
+    GetDeviceInfo = 0x80, Get, Opcode, [device_type @ 1, status @ 2];
+
+    /// Reset to factory defaults (target: 0 = factory, 1 = lighting, 2 = keymap, 3 = macros)
+    /// Guessed: no capture shows a reset yet
+    Reset = 0x01, Set, Sum { position: 7 }, [target @ 1, profile @ 2], unverified;
+
+    /// Set RGB settings (speed inverted: 5 - UI)
+    SetRgbSettings = 0x07, Set, Sum { position: 8 },
+        [direction @ 1, speed @ 2, brightness @ 3, mode @ 4, red @ 5, green @ 6, blue @ 7];
+
+    /// Set FN lock
+    /// Guessed from its GET (opcode - 0x80, same field bytes)
+    SetFnLock = 0x04, Set, Sum { position: 7 }, [enabled @ 2], unverified;
+
+    /// Set indicator LED
+    /// Guessed from its GET (opcode - 0x80, same field bytes)
+    SetIndicatorLed = 0x11, Set, Sum { position: 7 }, [enabled @ 2, behaviour @ 3], unverified;
+
+    /// Set sleep settings
+    /// Guessed from its GET (opcode - 0x80, same field bytes)
+    SetSleepSettings = 0x17, Set, Sum { position: 7 }, [timeout_min @ 2, lights_off @ 3], unverified;
+
+    /// Set performance
+    /// Guessed from its GET (opcode - 0x80, same field bytes)
+    SetPerformance = 0x12, Set, Sum { position: 7 }, [debounce_down @ 1, debounce_up @ 3], unverified;
+
+    /// Set active profile
+    /// Guessed from its GET (opcode - 0x80, same field bytes)
+    SetActiveProfile = 0x70, Set, Sum { position: 7 }, [profile @ 2], unverified;
+
+    /// Get RGB settings (speed inverted: 5 - UI, byte 4 reserved)
+    GetRgbSettings = 0x87, Get, Opcode,
+        [mode @ 1, speed @ 2, brightness @ 3, red @ 5, green @ 6, blue @ 7];
+
+    /// Get RGB mode
+    GetRgbMode = 0x88, Get, Opcode,
+        [mode @ 1, p1 @ 2, p2 @ 3, brightness @ 4, red @ 5, green @ 6, blue @ 7];
+
+    /// Get performance settings
+    GetPerformance = 0x92, Get, Opcode, [debounce_down @ 1, debounce_up @ 3];
+
+    /// Get FN lock status
+    GetFnLockStatus = 0x84, Get, Opcode, [enabled @ 2];
+
+    /// Get indicator LED settings
+    GetIndicatorLed = 0x91, Get, Opcode, [enabled @ 2, behaviour @ 3];
+
+    /// Get sleep settings
+    GetSleepSettings = 0x97, Get, Opcode, [timeout_min @ 2, lights_off @ 3];
+
+    /// Get custom RGB
+    GetCustomRgb = 0x86, Get, Opcode, [];
+
+    /// Get macro status
+    GetMacroStatus = 0xAE, Get, Opcode, [enabled @ 2];
+
+    /// Get macro data
+    GetMacroData = 0xAD, Get, Opcode, [];
+
+    /// Get layout info
+    GetLayoutInfo = 0x85, Get, Opcode, [];
+
+    /// Get battery status
+    GetBatteryStatus = 0x9D, Get, Opcode, [percent @ 1, charging @ 2, connection @ 3];
+}
+
+impl AkkoOpcode {
+    /// Checksum of the keyboard's answer: SETs are echoed with their
+    /// checksum, GET responses use byte 7 for data
+    pub fn response_checksum_scheme(&self) -> ChecksumScheme {
+        match self.checksum_scheme() {
+            ChecksumScheme::Opcode => ChecksumScheme::None,
+            scheme => scheme,
         }
     }
-}
 
-impl From<AkkoOpcode> for u8 {
-    fn from(opcode: AkkoOpcode) -> Self {
-        match opcode {
-            AkkoOpcode::SetRgbSettings => 0x07,
-            AkkoOpcode::Handshake => 0x8F,
-            AkkoOpcode::GetProfileCount => 0xF0,
-            AkkoOpcode::GetDeviceInfo => 0x80,
-            AkkoOpcode::GetRgbSettings => 0x87,
-            AkkoOpcode::GetRgbMode => 0x88,
-            AkkoOpcode::GetPerformance => 0x92,
-            AkkoOpcode::GetFnLockStatus => 0x84,
-            AkkoOpcode::GetIndicatorLed => 0x91,
-            AkkoOpcode::GetSleepSettings => 0x97,
-            AkkoOpcode::GetCustomRgb => 0x86,
-            AkkoOpcode::GetMacroStatus => 0xAE,
-            AkkoOpcode::GetMacroData => 0xAD,
-            AkkoOpcode::GetLayoutInfo => 0x85,
-            AkkoOpcode::GetBatteryStatus => 0x9D,
-            AkkoOpcode::Unknown(v) => v,
+    /// Raw values of the named response bytes present in `data`
+    pub fn decode_fields(&self, data: &[u8]) -> Vec<DecodedField> {
+        self.response_fields()
+            .iter()
+            .filter_map(|field| {
+                data.get(field.byte).map(|&value| DecodedField {
+                    name: field.name,
+                    value,
+                })
+            })
+            .collect()
+    }
+
+    /// `data` read as a response to this opcode: byte 0 must echo the opcode
+    /// and every named byte must be present
+    pub fn parse_response<'a>(&self, data: &'a [u8]) -> Option<Response<'a>> {
+        let fields = self.response_fields();
+        let len = fields.iter().map(|f| f.byte + 1).max().unwrap_or(1);
+        if data.len() < len || data[0] != u8::from(*self) {
+            return None;
         }
+        Some(Response { fields, data })
     }
 }
 
-impl AkkoOpcode {
-    /// Get command name for logging
-    pub fn name(&self) -> &'static str {
-        match self {
-            AkkoOpcode::SetRgbSettings => "SetRgbSettings",
-            AkkoOpcode::Handshake => "Handshake",
-            AkkoOpcode::GetProfileCount => "GetProfileCount",
-            AkkoOpcode::GetDeviceInfo => "GetDeviceInfo",
-            AkkoOpcode::GetRgbSettings => "GetRgbSettings",
-            AkkoOpcode::GetRgbMode => "GetRgbMode",
-            AkkoOpcode::GetPerformance => "GetPerformance",
-            AkkoOpcode::GetFnLockStatus => "GetFnLockStatus",
-            AkkoOpcode::GetIndicatorLed => "GetIndicatorLed",
-            AkkoOpcode::GetSleepSettings => "GetSleepSettings",
-            AkkoOpcode::GetCustomRgb => "GetCustomRgb",
-            AkkoOpcode::GetMacroStatus => "GetMacroStatus",
-            AkkoOpcode::GetMacroData => "GetMacroData",
-            AkkoOpcode::GetLayoutInfo => "GetLayoutInfo",
-            AkkoOpcode::GetBatteryStatus => "GetBatteryStatus",
-            AkkoOpcode::Unknown(_) => "Unknown",
-        }
+/// A received packet, read through its opcode's response_fields
+pub struct Response<'a> {
+    fields: &'static [ResponseField],
+    data: &'a [u8],
+}
+
+impl Response<'_> {
+    /// Value of a named byte (None if the schema has no such field)
+    pub fn field(&self, name: &str) -> Option<u8> {
+        let field = self.fields.iter().find(|f| f.name == name)?;
+        self.data.get(field.byte).copied()
+    }
+}
+
+/// Command reference table for docs/akko/akko_protocol.md
+pub fn opcode_reference_markdown() -> String {
+    let mut out = String::from(
+        "| Opcode | Direction | Checksum | Name | Response | Verified | Description |\n\
+         |--------|-----------|----------|------|----------|----------|-------------|\n",
+    );
+
+    for opcode in AkkoOpcode::ALL {
+        let byte = u8::from(*opcode);
+        let checksum = match opcode.checksum_scheme() {
+            ChecksumScheme::Opcode => format!("`0x{:02X}`", calc_checksum(byte)),
+            ChecksumScheme::Sum { position } => format!("Sum @{}", position),
+            ChecksumScheme::None => "-".to_string(),
+        };
+        let direction = match opcode.direction() {
+            OpcodeDirection::Get => "GET",
+            OpcodeDirection::Set => "SET",
+        };
+
+        let fields = opcode.response_fields();
+        let response = match fields.iter().map(|f| f.byte).max() {
+            Some(last) => {
+                let mut bytes = vec!["_".to_string(); last + 1];
+                bytes[0] = format!("{:02X}", byte);
+                for field in fields {
+                    bytes[field.byte] = field.name.to_string();
+                }
+                format!("`[{}]`", bytes.join(", "))
+            }
+            None => "-".to_string(),
+        };
+
+        let verified = if opcode.is_verified() {
+            "yes"
+        } else {
+            "**no**"
+        };
+
+        out.push_str(&format!(
+            "| `0x{:02X}` | {} | {} | {} | {} | {} | {} |\n",
+            byte,
+            direction,
+            checksum,
+            opcode.name(),
+            response,
+            verified,
+            opcode.description()
+        ));
     }
+
+    out
 }
 
 /// Akko HID Packet builder and parser
+///
+/// Builders always write the opcode's checksum. The HID layer refuses packets
+/// whose checksum is wrong unless `with_checksum_override` was used (probing).
 #[derive(Debug, Clone)]
 pub struct AkkoPacket {
     data: [u8; PACKET_SIZE],
+    checksum_override: bool,
 }
 
 impl AkkoPacket {
@@ -151,27 +404,35 @@ impl AkkoPacket {
     pub fn new() -> Self {
         Self {
             data: [0u8; PACKET_SIZE],
+            checksum_override: false,
         }
     }
 
     /// Create packet with opcode and auto-calculated checksum
     pub fn with_opcode(opcode: AkkoOpcode) -> Self {
         let mut packet = Self::new();
-        let op_byte: u8 = opcode.into();
-        packet.data[0] = op_byte;
-        packet.data[7] = calc_checksum(op_byte);
+        packet.data[0] = opcode.into();
+        packet.seal();
         packet
     }
 
     /// Create packet with opcode and parameters
     pub fn with_opcode_params(opcode: AkkoOpcode, param1: u8, param2: u8) -> Self {
+        Self::with_fields(opcode, &[param1, param2])
+    }
+
+    /// Create packet with field bytes from byte 1 up to the checksum
+    /// (extra fields are dropped)
+    pub fn with_fields(opcode: AkkoOpcode, fields: &[u8]) -> Self {
         let mut packet = Self::with_opcode(opcode);
-        packet.data[1] = param1;
-        packet.data[2] = param2;
+        let end = opcode.checksum_scheme().position().unwrap_or(PACKET_SIZE);
+        let len = fields.len().min(end - 1);
+        packet.data[1..1 + len].copy_from_slice(&fields[..len]);
+        packet.seal();
         packet
     }
 
-    /// Create packet from raw bytes
+    /// Create packet from raw bytes (checksum kept as is)
     pub fn from_bytes(data: &[u8]) -> Self {
         let mut packet = Self::new();
         let len = data.len().min(PACKET_SIZE);
@@ -179,6 +440,26 @@ impl AkkoPacket {
         packet
     }
 
+    /// Allow sending this packet even if its checksum is wrong
+    /// Only for probing unknown opcodes and raw packets typed by the user
+    pub fn with_checksum_override(mut self) -> Self {
+        self.checksum_override = true;
+        self
+    }
+
+    /// Whether the HID layer may send this packet
+    pub fn is_sendable(&self) -> bool {
+        self.checksum_override || self.is_checksum_valid()
+    }
+
+    /// Recompute the checksum after a change
+    fn seal(&mut self) {
+        let scheme = self.checksum_scheme();
+        if let (Some(position), Some(checksum)) = (scheme.position(), scheme.compute(&self.data)) {
+            self.data[position] = checksum;
+        }
+    }
+
     /// Get opcode from packet
     pub fn opcode(&self) -> AkkoOpcode {
         self.data[0].into()
@@ -189,25 +470,51 @@ impl AkkoPacket {
         (self.data[1], self.data[2])
     }
 
+    /// Checksum scheme of this packet's opcode
+    pub fn checksum_scheme(&self) -> ChecksumScheme {
+        self.opcode().checksum_scheme()
+    }
+
     /// Get checksum byte
-    pub fn checksum(&self) -> u8 {
-        self.data[7]
+    pub fn checksum(&self) -> Option<u8> {
+        self.checksum_scheme().position().map(|p| self.data[p])
     }
 
-    /// Verify checksum is valid
+    /// Verify checksum is valid (as an outgoing packet)
     pub fn is_checksum_valid(&self) -> bool {
-        self.data[7] == calc_checksum(self.data[0])
+        self.checksum_scheme().is_valid(&self.data)
+    }
+
+    /// Verify checksum is valid as the keyboard's answer to this opcode
+    pub fn is_response_checksum_valid(&self) -> bool {
+        self.opcode()
+            .response_checksum_scheme()
+            .is_valid(&self.data)
+    }
+
+    /// Checksum check for a packet travelling in `direction`
+    pub fn is_checksum_valid_as(&self, direction: PacketDirection) -> bool {
+        match direction {
+            PacketDirection::Tx => self.is_checksum_valid(),
+            PacketDirection::Rx => self.is_response_checksum_valid(),
+        }
     }
 
-    /// Get payload slice (bytes 8-63)
+    /// Get payload slice (bytes after the checksum)
     pub fn payload(&self) -> &[u8] {
-        &self.data[8..]
+        &self.data[self.payload_start()..]
     }
 
     /// Set payload data
     pub fn set_payload(&mut self, payload: &[u8]) {
-        let len = payload.len().min(PACKET_SIZE - 8);
-        self.data[8..8 + len].copy_from_slice(&payload[..len]);
+        let start = self.payload_start();
+        let len = payload.len().min(PACKET_SIZE - start);
+        self.data[start..start + len].copy_from_slice(&payload[..len]);
+        self.seal();
+    }
+
+    fn payload_start(&self) -> usize {
+        self.checksum_scheme().position().map_or(8, |p| p + 1)
     }
 
     /// Get raw bytes
@@ -215,11 +522,6 @@ impl AkkoPacket {
         &self.data
     }
 
-    /// Get mutable raw bytes
-    pub fn as_bytes_mut(&mut self) -> &mut [u8; PACKET_SIZE] {
-        &mut self.data
-    }
-
     /// Check if response has data (non-zero after opcode)
     pub fn has_data(&self) -> bool {
         self.data[1] != 0
@@ -255,31 +557,189 @@ impl Default for AkkoPacket {
     }
 }
 
-/// Parsed RGB settings from GetRgbSettings response
-#[derive(Debug, Clone)]
+/// What a Reset (0x01) command restores to factory state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "target", rename_all = "snake_case")]
+pub enum ResetScope {
+    /// Whole keyboard (all profiles and system settings)
+    Factory,
+    /// Lighting of a single profile
+    Lighting { profile: u8 },
+    /// Keymap of a single profile
+    Keymap { profile: u8 },
+    /// Macros of a single profile
+    Macros { profile: u8 },
+}
+
+impl ResetScope {
+    /// Target byte (byte 1)
+    pub fn target(&self) -> u8 {
+        match self {
+            ResetScope::Factory => 0,
+            ResetScope::Lighting { .. } => 1,
+            ResetScope::Keymap { .. } => 2,
+            ResetScope::Macros { .. } => 3,
+        }
+    }
+
+    /// Profile index for per-profile resets
+    pub fn profile(&self) -> Option<u8> {
+        match self {
+            ResetScope::Factory => None,
+            ResetScope::Lighting { profile }
+            | ResetScope::Keymap { profile }
+            | ResetScope::Macros { profile } => Some(*profile),
+        }
+    }
+
+    /// Human readable description for confirmation prompts
+    pub fn describe(&self) -> String {
+        match self {
+            ResetScope::Factory => "factory reset (all profiles and settings)".to_string(),
+            ResetScope::Lighting { profile } => format!("reset lighting of profile {}", profile),
+            ResetScope::Keymap { profile } => format!("reset keymap of profile {}", profile),
+            ResetScope::Macros { profile } => format!("reset macros of profile {}", profile),
+        }
+    }
+
+    /// Field bytes for Reset (bytes 1-6)
+    pub fn to_fields(&self) -> [u8; 6] {
+        [self.target(), self.profile().unwrap_or(0), 0, 0, 0, 0]
+    }
+}
+
+/// Firmware version from Handshake response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct FirmwareVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl FirmwareVersion {
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    pub fn from_response(data: &[u8]) -> Option<Self> {
+        let r = AkkoOpcode::Handshake.parse_response(data)?;
+        Some(Self {
+            major: r.field("fw_major")?,
+            minor: r.field("fw_minor")?,
+        })
+    }
+}
+
+impl std::fmt::Display for FirmwareVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}.{}", self.major, self.minor)
+    }
+}
+
+/// Parsed device info from GetDeviceInfo response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceInfoResponse {
+    pub device_type: u8,
+    pub status: u8,
+}
+
+impl DeviceInfoResponse {
+    pub fn from_response(data: &[u8]) -> Option<Self> {
+        let r = AkkoOpcode::GetDeviceInfo.parse_response(data)?;
+        Some(Self {
+            device_type: r.field("device_type")?,
+            status: r.field("status")?,
+        })
+    }
+}
+
+/// Whether an effect uses the custom color or the rainbow gradient
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorMode {
+    /// Rainbow gradient, RGB bytes ignored
+    Dazzle,
+    /// Custom RGB color
+    Color,
+}
+
+/// Build the SetRgbSettings mode byte (byte 4)
+/// 0x07 (Dazzle) / 0x08 (Color) + 0x10 * direction (0-3)
+pub fn rgb_mode_byte(color_mode: ColorMode, direction: u8) -> u8 {
+    let base = match color_mode {
+        ColorMode::Dazzle => 0x07,
+        ColorMode::Color => 0x08,
+    };
+    base + 0x10 * direction.min(3)
+}
+
+/// Known effect IDs (byte 1 of SetRgbSettings), see docs/akko/rgb-protocol.md
+pub const EFFECT_IDS: &[u8] = &[4, 5, 6, 7, 8, 9, 10, 11, 12, 14, 15, 16, 17, 18, 19, 20, 21];
+
+/// Effect after `current` in EFFECT_IDS (wraps; unknown IDs start over)
+pub fn next_effect(current: u8) -> u8 {
+    EFFECT_IDS
+        .iter()
+        .position(|&id| id == current)
+        .map(|i| EFFECT_IDS[(i + 1) % EFFECT_IDS.len()])
+        .unwrap_or(EFFECT_IDS[0])
+}
+
+/// Lighting sent with SetRgbSettings (layout from web capture):
+/// [07, effect, speed, brightness, mode, R, G, B]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RgbSettings {
+    /// Effect ID (byte 1, historically called "direction")
+    pub direction: u8,
+    /// UI speed 0-4 (protocol value is inverted)
+    pub speed: u8,
+    /// Brightness 0-4 (0 = off)
+    pub brightness: u8,
+    /// Color mode / sub-mode (0x07 = Dazzle, 0x08 = Color, ...)
     pub mode: u8,
+    pub color: (u8, u8, u8),
+}
+
+/// Parsed lighting from GetRgbSettings response:
+/// [87, mode, speed, brightness, reserved, R, G, B]
+///
+/// How `mode` relates to the effect and color mode of SetRgbSettings is not
+/// known, so a read cannot rebuild RgbSettings on its own; see `apply_to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RgbReadback {
+    /// Mode ID (byte 1)
+    pub mode: u8,
+    /// UI speed 0-4 (protocol value is inverted)
     pub speed: u8,
-    pub direction: u8,
+    /// Brightness 0-4 (0 = off)
+    pub brightness: u8,
     pub color: (u8, u8, u8),
 }
 
-impl RgbSettings {
+impl RgbReadback {
     pub fn from_response(data: &[u8]) -> Option<Self> {
-        if data.len() < 8 || data[0] != 0x87 {
-            return None;
-        }
+        let r = AkkoOpcode::GetRgbSettings.parse_response(data)?;
         Some(Self {
-            mode: data[1],
-            speed: data[2],
-            direction: data[3],
-            color: (data[5], data[6], data[7]),
+            mode: r.field("mode")?,
+            speed: 5_u8.saturating_sub(r.field("speed")?).min(4),
+            brightness: r.field("brightness")?,
+            color: (r.field("red")?, r.field("green")?, r.field("blue")?),
         })
     }
+
+    /// `known` lighting with the speed, brightness and color read here
+    /// Effect and color mode are kept from `known` (what was last sent)
+    pub fn apply_to(&self, known: RgbSettings) -> RgbSettings {
+        RgbSettings {
+            speed: self.speed,
+            brightness: self.brightness,
+            color: self.color,
+            ..known
+        }
+    }
 }
 
 /// Parsed performance settings from GetPerformance response
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PerformanceSettings {
     pub debounce_down: u8,
     pub debounce_up: u8,
@@ -287,18 +747,21 @@ pub struct PerformanceSettings {
 
 impl PerformanceSettings {
     pub fn from_response(data: &[u8]) -> Option<Self> {
-        if data.len() < 4 || data[0] != 0x92 {
-            return None;
-        }
+        let r = AkkoOpcode::GetPerformance.parse_response(data)?;
         Some(Self {
-            debounce_down: data[1],
-            debounce_up: data[3],
+            debounce_down: r.field("debounce_down")?,
+            debounce_up: r.field("debounce_up")?,
         })
     }
+
+    /// Field bytes for SetPerformance (bytes 1-6)
+    pub fn to_fields(&self) -> [u8; 6] {
+        [self.debounce_down, 0, self.debounce_up, 0, 0, 0]
+    }
 }
 
 /// Parsed profile info from GetProfileCount response
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ProfileInfo {
     pub count: u8,
     pub active: u8,
@@ -306,12 +769,176 @@ pub struct ProfileInfo {
 
 impl ProfileInfo {
     pub fn from_response(data: &[u8]) -> Option<Self> {
-        if data.len() < 3 || data[0] != 0xF0 {
-            return None;
+        let r = AkkoOpcode::GetProfileCount.parse_response(data)?;
+        Some(Self {
+            count: r.field("count")?,
+            active: r.field("active")?,
+        })
+    }
+
+    /// Field bytes for SetActiveProfile (bytes 1-6)
+    pub fn to_fields(&self) -> [u8; 6] {
+        [0, self.active, 0, 0, 0, 0]
+    }
+}
+
+/// FN lock state from GetFnLockStatus response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FnLockSettings {
+    pub enabled: bool,
+}
+
+impl FnLockSettings {
+    pub fn from_response(data: &[u8]) -> Option<Self> {
+        let r = AkkoOpcode::GetFnLockStatus.parse_response(data)?;
+        Some(Self {
+            enabled: r.field("enabled")? != 0,
+        })
+    }
+
+    /// Field bytes for SetFnLock (bytes 1-6)
+    pub fn to_fields(&self) -> [u8; 6] {
+        [0, self.enabled as u8, 0, 0, 0, 0]
+    }
+}
+
+/// Indicator LED behaviour (byte 3 of GetIndicatorLed)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndicatorBehaviour {
+    /// Indicator always lit while enabled
+    AlwaysOn = 0,
+    /// Indicator follows the backlight effect
+    FollowBacklight = 1,
+    /// Indicator only shows lock states (Caps/Win lock)
+    StatusOnly = 2,
+}
+
+impl IndicatorBehaviour {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(IndicatorBehaviour::AlwaysOn),
+            1 => Some(IndicatorBehaviour::FollowBacklight),
+            2 => Some(IndicatorBehaviour::StatusOnly),
+            _ => None,
+        }
+    }
+}
+
+/// Indicator LED settings from GetIndicatorLed response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndicatorLedSettings {
+    pub enabled: bool,
+    pub behaviour: IndicatorBehaviour,
+}
+
+impl IndicatorLedSettings {
+    pub fn from_response(data: &[u8]) -> Option<Self> {
+        let r = AkkoOpcode::GetIndicatorLed.parse_response(data)?;
+        Some(Self {
+            enabled: r.field("enabled")? != 0,
+            // Older firmware leaves byte 3 at 0
+            behaviour: IndicatorBehaviour::from_u8(r.field("behaviour")?)
+                .unwrap_or(IndicatorBehaviour::AlwaysOn),
+        })
+    }
+
+    /// Field bytes for SetIndicatorLed (bytes 1-6)
+    pub fn to_fields(&self) -> [u8; 6] {
+        [0, self.enabled as u8, self.behaviour as u8, 0, 0, 0]
+    }
+}
+
+/// Maximum sleep timeout accepted by the firmware (minutes)
+pub const MAX_SLEEP_TIMEOUT_MINUTES: u8 = 60;
+
+/// Sleep settings from GetSleepSettings response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SleepSettings {
+    /// Minutes of inactivity before sleep (0 = never)
+    pub timeout_minutes: u8,
+    /// Turn lighting off when the keyboard sleeps
+    pub lights_off_on_sleep: bool,
+}
+
+impl SleepSettings {
+    pub fn from_response(data: &[u8]) -> Option<Self> {
+        let r = AkkoOpcode::GetSleepSettings.parse_response(data)?;
+        Some(Self {
+            timeout_minutes: r.field("timeout_min")?,
+            lights_off_on_sleep: r.field("lights_off")? != 0,
+        })
+    }
+
+    /// Validate values before sending to the keyboard
+    pub fn validate(&self) -> Result<(), String> {
+        if self.timeout_minutes > MAX_SLEEP_TIMEOUT_MINUTES {
+            return Err(format!(
+                "Sleep timeout must be 0-{} minutes, got {}",
+                MAX_SLEEP_TIMEOUT_MINUTES, self.timeout_minutes
+            ));
+        }
+        Ok(())
+    }
+
+    /// Field bytes for SetSleepSettings (bytes 1-6)
+    pub fn to_fields(&self) -> [u8; 6] {
+        [
+            0,
+            self.timeout_minutes,
+            self.lights_off_on_sleep as u8,
+            0,
+            0,
+            0,
+        ]
+    }
+}
+
+/// How a wireless keyboard is currently connected (byte 3 of GetBatteryStatus)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionMode {
+    Wired,
+    Wireless24G,
+    Bluetooth,
+}
+
+impl ConnectionMode {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(ConnectionMode::Wired),
+            1 => Some(ConnectionMode::Wireless24G),
+            2 => Some(ConnectionMode::Bluetooth),
+            _ => None,
         }
+    }
+
+    /// Short label for UI / tray
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConnectionMode::Wired => "Wired",
+            ConnectionMode::Wireless24G => "2.4G",
+            ConnectionMode::Bluetooth => "BT",
+        }
+    }
+}
+
+/// Battery status from GetBatteryStatus response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatteryStatus {
+    /// Charge level 0-100
+    pub percent: u8,
+    pub charging: bool,
+    pub connection: ConnectionMode,
+}
+
+impl BatteryStatus {
+    pub fn from_response(data: &[u8]) -> Option<Self> {
+        let r = AkkoOpcode::GetBatteryStatus.parse_response(data)?;
         Some(Self {
-            count: data[1],
-            active: data[2],
+            percent: r.field("percent")?.min(100),
+            charging: r.field("charging")? != 0,
+            connection: ConnectionMode::from_u8(r.field("connection")?)?,
         })
     }
 }
@@ -320,6 +947,128 @@ impl ProfileInfo {
 mod tests {
     use super::*;
 
+    const PROTOCOL_DOC: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../docs/akko/akko_protocol.md");
+    const TABLE_START: &str = "<!-- opcode-table:start -->\n";
+    const TABLE_END: &str = "<!-- opcode-table:end -->";
+
+    #[test]
+    fn test_opcode_schema_roundtrip() {
+        for opcode in AkkoOpcode::ALL {
+            assert_eq!(AkkoOpcode::from(u8::from(*opcode)), *opcode);
+            assert_ne!(opcode.name(), "Unknown");
+        }
+        assert_eq!(AkkoOpcode::from(0x42), AkkoOpcode::Unknown(0x42));
+        assert_eq!(AkkoOpcode::Unknown(0x42).direction(), OpcodeDirection::Set);
+
+        let fields = AkkoOpcode::GetSleepSettings.decode_fields(&[0x97, 0, 10, 1]);
+        assert_eq!(
+            fields,
+            vec![
+                DecodedField {
+                    name: "timeout_min",
+                    value: 10
+                },
+                DecodedField {
+                    name: "lights_off",
+                    value: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decoders_follow_schema() {
+        type Decoder = fn(&[u8]) -> bool;
+        let decoders: &[(AkkoOpcode, Decoder)] = &[
+            (AkkoOpcode::Handshake, |d| {
+                FirmwareVersion::from_response(d).is_some()
+            }),
+            (AkkoOpcode::GetDeviceInfo, |d| {
+                DeviceInfoResponse::from_response(d).is_some()
+            }),
+            (AkkoOpcode::GetRgbSettings, |d| {
+                RgbReadback::from_response(d).is_some()
+            }),
+            (AkkoOpcode::GetPerformance, |d| {
+                PerformanceSettings::from_response(d).is_some()
+            }),
+            (AkkoOpcode::GetProfileCount, |d| {
+                ProfileInfo::from_response(d).is_some()
+            }),
+            (AkkoOpcode::GetFnLockStatus, |d| {
+                FnLockSettings::from_response(d).is_some()
+            }),
+            (AkkoOpcode::GetIndicatorLed, |d| {
+                IndicatorLedSettings::from_response(d).is_some()
+            }),
+            (AkkoOpcode::GetSleepSettings, |d| {
+                SleepSettings::from_response(d).is_some()
+            }),
+            (AkkoOpcode::GetBatteryStatus, |d| {
+                BatteryStatus::from_response(d).is_some()
+            }),
+        ];
+
+        for (opcode, decode) in decoders {
+            // Exactly the bytes the schema names: every field a decoder reads must exist
+            let len = opcode
+                .response_fields()
+                .iter()
+                .map(|f| f.byte + 1)
+                .max()
+                .unwrap();
+            let mut data = vec![1u8; len];
+            data[0] = u8::from(*opcode);
+            assert!(
+                decode(&data),
+                "{} decoder disagrees with the schema",
+                opcode.name()
+            );
+            assert!(
+                !decode(&data[..len - 1]),
+                "{} accepts a short packet",
+                opcode.name()
+            );
+
+            data[0] ^= 0x01;
+            assert!(!decode(&data), "{} accepts another opcode", opcode.name());
+        }
+    }
+
+    /// Regenerate with UPDATE_DOCS=1 cargo test
+    #[test]
+    fn test_docs_opcode_table_is_current() {
+        let doc = std::fs::read_to_string(PROTOCOL_DOC).unwrap();
+        let start = doc.find(TABLE_START).expect("missing table start marker") + TABLE_START.len();
+        let end = doc.find(TABLE_END).expect("missing table end marker");
+        let table = opcode_reference_markdown();
+
+        if std::env::var_os("UPDATE_DOCS").is_some() {
+            let updated = format!("{}{}{}", &doc[..start], table, &doc[end..]);
+            std::fs::write(PROTOCOL_DOC, updated).unwrap();
+        } else {
+            assert_eq!(
+                doc[start..end],
+                table,
+                "opcode table is stale, run with UPDATE_DOCS=1"
+            );
+        }
+    }
+
+    #[test]
+    fn test_guessed_opcodes_are_unverified() {
+        assert!(!AkkoOpcode::Reset.is_verified());
+        assert!(!AkkoOpcode::SetFnLock.is_verified());
+        assert!(!AkkoOpcode::SetIndicatorLed.is_verified());
+        assert!(!AkkoOpcode::SetSleepSettings.is_verified());
+        assert!(!AkkoOpcode::SetPerformance.is_verified());
+        assert!(!AkkoOpcode::SetActiveProfile.is_verified());
+        assert!(!AkkoOpcode::Unknown(0x42).is_verified());
+        assert!(AkkoOpcode::SetRgbSettings.is_verified());
+        assert!(AkkoOpcode::GetRgbSettings.is_verified());
+    }
+
     #[test]
     fn test_checksum() {
         assert_eq!(calc_checksum(0x8F), 0x70);
@@ -335,4 +1084,124 @@ mod tests {
         assert_eq!(packet.as_bytes()[7], 0x70);
         assert!(packet.is_checksum_valid());
     }
+
+    #[test]
+    fn test_checksum_scheme_per_opcode() {
+        // [7, 1, 5, 4, 7, 255, 0, 0, 232] from web capture
+        let rgb = AkkoPacket::with_fields(AkkoOpcode::SetRgbSettings, &[1, 5, 4, 7, 255, 0, 0]);
+        assert_eq!(&rgb.as_bytes()[..9], &[7, 1, 5, 4, 7, 255, 0, 0, 232]);
+        assert_eq!(rgb.checksum(), Some(232));
+
+        let fn_lock = AkkoPacket::with_fields(AkkoOpcode::SetFnLock, &[0, 1, 0, 0, 0, 0]);
+        assert_eq!(
+            fn_lock.checksum(),
+            Some(calc_sum_checksum(&[4, 0, 1, 0, 0, 0, 0]))
+        );
+        assert!(fn_lock.is_checksum_valid());
+
+        // Params do not change the GET checksum
+        let get = AkkoPacket::with_opcode_params(AkkoOpcode::GetRgbSettings, 1, 2);
+        assert_eq!(get.checksum(), Some(0x78));
+    }
+
+    #[test]
+    fn test_wrong_checksum_needs_override() {
+        let mut bytes = *AkkoPacket::with_opcode(AkkoOpcode::SetFnLock).as_bytes();
+        bytes[2] = 1;
+        let tampered = AkkoPacket::from_bytes(&bytes);
+        assert!(!tampered.is_sendable());
+        assert!(tampered.with_checksum_override().is_sendable());
+
+        // GET responses carry data where the request had its checksum
+        let response = AkkoPacket::from_bytes(&[0x87, 1, 5, 0, 0, 255, 0, 9]);
+        assert!(!response.is_checksum_valid());
+        assert!(response.is_response_checksum_valid());
+    }
+
+    #[test]
+    fn test_sum_checksum() {
+        // [7, 1, 5, 4, 7, 255, 0, 0, 232] from web capture
+        assert_eq!(calc_sum_checksum(&[7, 1, 5, 4, 7, 255, 0, 0]), 232);
+        // GET packets are a special case of the sum checksum
+        assert_eq!(calc_sum_checksum(&[0x8F, 0, 0, 0, 0, 0, 0]), 0x70);
+    }
+
+    #[test]
+    fn test_system_settings_roundtrip() {
+        let sleep = SleepSettings::from_response(&[0x97, 0, 10, 1]).unwrap();
+        assert_eq!(sleep.timeout_minutes, 10);
+        assert!(sleep.lights_off_on_sleep);
+        assert_eq!(sleep.to_fields(), [0, 10, 1, 0, 0, 0]);
+
+        let too_long = SleepSettings {
+            timeout_minutes: 90,
+            lights_off_on_sleep: false,
+        };
+        assert!(too_long.validate().is_err());
+
+        let led = IndicatorLedSettings::from_response(&[0x91, 0, 1, 2]).unwrap();
+        assert_eq!(led.behaviour, IndicatorBehaviour::StatusOnly);
+        assert!(
+            FnLockSettings::from_response(&[0x84, 0, 1])
+                .unwrap()
+                .enabled
+        );
+    }
+
+    #[test]
+    fn test_rgb_mode_byte() {
+        assert_eq!(rgb_mode_byte(ColorMode::Dazzle, 0), 0x07);
+        assert_eq!(rgb_mode_byte(ColorMode::Color, 1), 0x18);
+        assert_eq!(rgb_mode_byte(ColorMode::Color, 3), 0x38);
+    }
+
+    #[test]
+    fn test_next_effect() {
+        assert_eq!(next_effect(4), 5);
+        assert_eq!(next_effect(12), 14);
+        assert_eq!(next_effect(21), 4);
+        assert_eq!(next_effect(1), 4);
+    }
+
+    #[test]
+    fn test_rgb_readback() {
+        // Layout from the GET 0x87 section of akko_protocol.md; byte 4 is reserved
+        let rgb = RgbReadback::from_response(&[135, 1, 4, 3, 0x55, 255, 0, 0]).unwrap();
+        assert_eq!(rgb.mode, 1);
+        assert_eq!(rgb.speed, 1);
+        assert_eq!(rgb.brightness, 3);
+        assert_eq!(rgb.color, (255, 0, 0));
+
+        let known = RgbSettings {
+            direction: 4,
+            speed: 2,
+            brightness: 1,
+            mode: 0x17,
+            color: (0, 0, 255),
+        };
+        let current = rgb.apply_to(known);
+        assert_eq!((current.direction, current.mode), (4, 0x17));
+        assert_eq!((current.speed, current.brightness), (1, 3));
+        assert_eq!(current.color, (255, 0, 0));
+    }
+
+    #[test]
+    fn test_firmware_version() {
+        let fw = FirmwareVersion::from_response(&[0x8F, 1, 4, 0]).unwrap();
+        assert_eq!(fw, FirmwareVersion::new(1, 4));
+        assert_eq!(fw.to_string(), "v1.4");
+        assert!(fw < FirmwareVersion::new(2, 0));
+        assert!(FirmwareVersion::from_response(&[0xF0, 1, 4]).is_none());
+    }
+
+    #[test]
+    fn test_battery_status() {
+        let status = BatteryStatus::from_response(&[0x9D, 87, 1, 1]).unwrap();
+        assert_eq!(status.percent, 87);
+        assert!(status.charging);
+        assert_eq!(status.connection, ConnectionMode::Wireless24G);
+
+        assert!(BatteryStatus::from_response(&[0x9D, 50, 0, 9]).is_none());
+        assert!(BatteryStatus::from_response(&[0x87, 50, 0, 0]).is_none());
+    }
 }
@@ -0,0 +1,306 @@
+//! Per-device access control and write coalescing
+//!
+//! DeviceLease serializes every open of a keyboard so concurrent commands
+//! never interleave feature reports. Coalescer collapses rapid writes (slider
+//! drags) to the latest value; run_worker sends them no faster than the
+//! firmware tolerates.
+
+use std::ops::Deref;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::detector::AkkoModel;
+use super::hid::AkkoHidDevice;
+
+/// Minimum gap between lighting writes; faster bursts are coalesced
+pub const MIN_WRITE_INTERVAL: Duration = Duration::from_millis(50);
+
+static BUSY: Mutex<Vec<AkkoModel>> = Mutex::new(Vec::new());
+static FREED: Condvar = Condvar::new();
+
+/// Exclusive use of one keyboard until dropped
+pub struct DeviceLease {
+    model: AkkoModel,
+}
+
+impl DeviceLease {
+    /// Block until no one else is talking to `model`
+    pub fn acquire(model: AkkoModel) -> Self {
+        let mut busy = BUSY.lock().unwrap_or_else(|e| e.into_inner());
+        while busy.contains(&model) {
+            busy = FREED.wait(busy).unwrap_or_else(|e| e.into_inner());
+        }
+        busy.push(model);
+        Self { model }
+    }
+}
+
+impl Drop for DeviceLease {
+    fn drop(&mut self) {
+        let mut busy = BUSY.lock().unwrap_or_else(|e| e.into_inner());
+        busy.retain(|m| *m != self.model);
+        FREED.notify_all();
+    }
+}
+
+/// An open device plus the lease that guards it
+/// (fields drop in order: the handle is closed before the lease is released)
+pub struct LockedDevice {
+    device: AkkoHidDevice,
+    _lease: DeviceLease,
+}
+
+impl LockedDevice {
+    pub fn open(model: AkkoModel) -> Result<Self, String> {
+        let lease = DeviceLease::acquire(model);
+        let device = AkkoHidDevice::open(model.vid(), model.pid())?;
+        Ok(Self {
+            device,
+            _lease: lease,
+        })
+    }
+}
+
+impl Deref for LockedDevice {
+    type Target = AkkoHidDevice;
+
+    fn deref(&self) -> &AkkoHidDevice {
+        &self.device
+    }
+}
+
+/// Completion handle for one submitted value
+pub struct Ticket<R> {
+    result: Mutex<Option<Result<R, String>>>,
+    done: Condvar,
+}
+
+impl<R: Clone> Ticket<R> {
+    fn new() -> Self {
+        Self {
+            result: Mutex::new(None),
+            done: Condvar::new(),
+        }
+    }
+
+    /// Wait for the write that carried this value (or a newer one that replaced it)
+    pub fn wait(&self) -> Result<R, String> {
+        let mut result = self.result.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if let Some(result) = result.as_ref() {
+                return result.clone();
+            }
+            result = self.done.wait(result).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    fn complete(&self, result: Result<R, String>) {
+        *self.result.lock().unwrap_or_else(|e| e.into_inner()) = Some(result);
+        self.done.notify_all();
+    }
+}
+
+struct Slot<T, R> {
+    pending: Option<T>,
+    waiters: Vec<Arc<Ticket<R>>>,
+}
+
+/// Single-slot queue: a newer value replaces one not yet sent
+pub struct Coalescer<T, R> {
+    slot: Mutex<Slot<T, R>>,
+    ready: Condvar,
+}
+
+/// Values taken from the queue in one go
+pub struct Batch<T, R> {
+    /// Latest submitted value
+    pub value: T,
+    /// Number of submissions this write covers
+    pub submissions: usize,
+    waiters: Vec<Arc<Ticket<R>>>,
+}
+
+impl<T, R: Clone> Batch<T, R> {
+    /// Wake everyone waiting on this write
+    pub fn finish(&self, result: Result<R, String>) {
+        for ticket in &self.waiters {
+            ticket.complete(result.clone());
+        }
+    }
+}
+
+impl<T, R: Clone> Default for Coalescer<T, R> {
+    fn default() -> Self {
+        Self {
+            slot: Mutex::new(Slot {
+                pending: None,
+                waiters: Vec::new(),
+            }),
+            ready: Condvar::new(),
+        }
+    }
+}
+
+impl<T, R: Clone> Coalescer<T, R> {
+    /// Queue a value, replacing any value not yet taken
+    pub fn submit(&self, value: T) -> Arc<Ticket<R>> {
+        self.submit_merged(value, |_, newer| newer)
+    }
+
+    /// Queue a value, combining it with any value not yet taken
+    /// (`merge(pending, value)`), e.g. to keep parts the new value leaves alone
+    pub fn submit_merged(&self, value: T, merge: impl FnOnce(T, T) -> T) -> Arc<Ticket<R>> {
+        let ticket = Arc::new(Ticket::new());
+
+        let mut slot = self.slot.lock().unwrap_or_else(|e| e.into_inner());
+        slot.pending = Some(match slot.pending.take() {
+            Some(pending) => merge(pending, value),
+            None => value,
+        });
+        slot.waiters.push(ticket.clone());
+        self.ready.notify_one();
+
+        ticket
+    }
+
+    /// Take the pending value without blocking
+    pub fn try_take(&self) -> Option<Batch<T, R>> {
+        let mut slot = self.slot.lock().unwrap_or_else(|e| e.into_inner());
+        Self::take_from(&mut slot)
+    }
+
+    /// Block until a value is pending and take it
+    pub fn take(&self) -> Batch<T, R> {
+        let mut slot = self.slot.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if let Some(batch) = Self::take_from(&mut slot) {
+                return batch;
+            }
+            slot = self.ready.wait(slot).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    fn take_from(slot: &mut Slot<T, R>) -> Option<Batch<T, R>> {
+        let value = slot.pending.take()?;
+        let waiters = std::mem::take(&mut slot.waiters);
+        Some(Batch {
+            value,
+            submissions: waiters.len(),
+            waiters,
+        })
+    }
+}
+
+/// Start a thread that sends queued values one at a time
+///
+/// `write` performs the transfer; `done` is called after each write with the
+/// value, the number of submissions it covered and the result. Waiters are woken
+/// before `done` runs, so `done` may hop to a thread a waiter is blocking.
+/// Writes are at least `min_interval` apart.
+pub fn run_worker<T, R, W, D>(
+    queue: Arc<Coalescer<T, R>>,
+    min_interval: Duration,
+    mut write: W,
+    mut done: D,
+) where
+    T: Send + 'static,
+    R: Clone + Send + 'static,
+    W: FnMut(&T) -> Result<R, String> + Send + 'static,
+    D: FnMut(&T, usize, &Result<R, String>) + Send + 'static,
+{
+    thread::spawn(move || loop {
+        let batch = queue.take();
+        let result = write(&batch.value);
+        batch.finish(result.clone());
+        done(&batch.value, batch.submissions, &result);
+
+        thread::sleep(min_interval);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_newer_value_replaces_pending() {
+        let queue: Coalescer<u8, u8> = Coalescer::default();
+        let first = queue.submit(1);
+        let second = queue.submit(2);
+        let third = queue.submit(3);
+
+        let batch = queue.try_take().unwrap();
+        assert_eq!(batch.value, 3);
+        assert_eq!(batch.submissions, 3);
+        assert!(queue.try_take().is_none());
+
+        batch.finish(Ok(30));
+        for ticket in [first, second, third] {
+            assert_eq!(ticket.wait(), Ok(30));
+        }
+    }
+
+    #[test]
+    fn test_merge_with_pending() {
+        let queue: Coalescer<(u8, u8), u8> = Coalescer::default();
+        let first = queue.submit((1, 1));
+        let second = queue.submit_merged((0, 2), |pending, newer| (pending.0, newer.1));
+
+        let batch = queue.try_take().unwrap();
+        assert_eq!(batch.value, (1, 2));
+        assert_eq!(batch.submissions, 2);
+
+        // Nothing pending: taken as is
+        queue.submit_merged((0, 3), |pending, newer| (pending.0, newer.1));
+        assert_eq!(queue.try_take().unwrap().value, (0, 3));
+
+        batch.finish(Ok(12));
+        assert_eq!((first.wait(), second.wait()), (Ok(12), Ok(12)));
+    }
+
+    #[test]
+    fn test_worker_coalesces_bursts() {
+        let queue: Arc<Coalescer<u8, u8>> = Arc::new(Coalescer::default());
+        let written = Arc::new(Mutex::new(Vec::new()));
+
+        let log = written.clone();
+        run_worker(
+            queue.clone(),
+            Duration::from_millis(100),
+            move |v: &u8| {
+                log.lock().unwrap().push(*v);
+                Ok(*v)
+            },
+            |_, _, _| {},
+        );
+
+        // First write starts immediately; the rest pile up during the gap
+        let first = queue.submit(1);
+        assert_eq!(first.wait(), Ok(1));
+        let tickets: Vec<_> = (2..=10).map(|v| queue.submit(v)).collect();
+        for ticket in &tickets {
+            assert_eq!(ticket.wait(), Ok(10));
+        }
+
+        assert_eq!(*written.lock().unwrap(), vec![1, 10]);
+    }
+
+    #[test]
+    fn test_lease_is_exclusive_per_model() {
+        let lease = DeviceLease::acquire(AkkoModel::Mod007b);
+        // Another model is independent
+        drop(DeviceLease::acquire(AkkoModel::Akko24GWireless));
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let waiter = thread::spawn(move || {
+            let _lease = DeviceLease::acquire(AkkoModel::Mod007b);
+            tx.send(()).unwrap();
+        });
+
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+        drop(lease);
+        rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        waiter.join().unwrap();
+    }
+}
@@ -0,0 +1,279 @@
+//! Typed snapshot of a keyboard's readable configuration
+//! Shared by backup/restore and anything that needs to save and reapply state
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::commands::{self, CommandResult};
+use super::hid::AkkoHidDevice;
+use super::protocol::{
+    AkkoOpcode, FnLockSettings, IndicatorLedSettings, PerformanceSettings, ProfileInfo,
+    RgbSettings, SleepSettings,
+};
+use super::verify::{self, RetryPolicy};
+
+/// Sections of a keyboard configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSection {
+    Profiles,
+    Lighting,
+    Performance,
+    FnLock,
+    Indicator,
+    Sleep,
+    PerKeyColors,
+    Keymaps,
+    Macros,
+}
+
+impl ConfigSection {
+    /// All sections, in restore order (profile switch first)
+    pub const ALL: [ConfigSection; 9] = [
+        ConfigSection::Profiles,
+        ConfigSection::Lighting,
+        ConfigSection::Performance,
+        ConfigSection::FnLock,
+        ConfigSection::Indicator,
+        ConfigSection::Sleep,
+        ConfigSection::PerKeyColors,
+        ConfigSection::Keymaps,
+        ConfigSection::Macros,
+    ];
+
+    /// Large tables that span multiple packets
+    pub fn is_table(&self) -> bool {
+        matches!(
+            self,
+            ConfigSection::PerKeyColors | ConfigSection::Keymaps | ConfigSection::Macros
+        )
+    }
+
+    /// Sections tied to the physical key layout of a model
+    pub fn is_layout_specific(&self) -> bool {
+        self.is_table()
+    }
+
+    /// SET opcode used to restore the section (None for tables)
+    pub fn set_opcode(&self) -> Option<AkkoOpcode> {
+        match self {
+            ConfigSection::Profiles => Some(AkkoOpcode::SetActiveProfile),
+            ConfigSection::Lighting => Some(AkkoOpcode::SetRgbSettings),
+            ConfigSection::Performance => Some(AkkoOpcode::SetPerformance),
+            ConfigSection::FnLock => Some(AkkoOpcode::SetFnLock),
+            ConfigSection::Indicator => Some(AkkoOpcode::SetIndicatorLed),
+            ConfigSection::Sleep => Some(AkkoOpcode::SetSleepSettings),
+            _ => None,
+        }
+    }
+
+    /// The SET opcode of a section no capture confirms yet
+    /// Restore and reapply never send those on their own
+    pub fn unverified_opcode(&self) -> Option<AkkoOpcode> {
+        self.set_opcode().filter(|opcode| !opcode.is_verified())
+    }
+}
+
+/// One field that differs between two states
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldDiff {
+    pub section: ConfigSection,
+    pub field: String,
+    pub current: Value,
+    pub target: Value,
+}
+
+/// Typed keyboard state; None = section could not be read
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyboardState {
+    pub profiles: Option<ProfileInfo>,
+    /// Lighting of the active profile
+    pub lighting: Option<RgbSettings>,
+    pub performance: Option<PerformanceSettings>,
+    pub fn_lock: Option<FnLockSettings>,
+    pub indicator: Option<IndicatorLedSettings>,
+    pub sleep: Option<SleepSettings>,
+}
+
+impl KeyboardState {
+    /// Read all sections (handshake must already be done)
+    ///
+    /// The keyboard does not report its effect or color mode, so lighting is
+    /// the speed, brightness and color read on top of `known_lighting` (what
+    /// was last sent), and None when nothing was sent yet.
+    pub fn read(device: &AkkoHidDevice, known_lighting: Option<RgbSettings>) -> Self {
+        let lighting = known_lighting.and_then(|known| {
+            log_err("lighting", commands::cmd_read_rgb_settings(device))
+                .map(|read| read.apply_to(known))
+        });
+
+        Self {
+            profiles: log_err("profiles", commands::cmd_read_profile_info(device)),
+            lighting,
+            performance: log_err("performance", commands::cmd_read_performance(device)),
+            fn_lock: log_err("fn_lock", commands::cmd_read_fn_lock(device)),
+            indicator: log_err("indicator", commands::cmd_read_indicator_led(device)),
+            sleep: log_err("sleep", commands::cmd_read_sleep_settings(device)),
+        }
+    }
+
+    /// Whether a section is present in this state
+    pub fn has_section(&self, section: ConfigSection) -> bool {
+        !self.section_value(section).is_null()
+    }
+
+    /// Field-by-field differences from `self` (current) to `target`
+    /// Sections missing from `target` are ignored
+    pub fn diff(&self, target: &KeyboardState) -> Vec<FieldDiff> {
+        let mut diffs = Vec::new();
+
+        for section in ConfigSection::ALL {
+            let current = self.section_value(section);
+            let wanted = target.section_value(section);
+
+            let Value::Object(wanted) = wanted else {
+                continue;
+            };
+
+            for (field, target_value) in wanted {
+                let current_value = current.get(&field).cloned().unwrap_or(Value::Null);
+                if current_value != target_value {
+                    diffs.push(FieldDiff {
+                        section,
+                        field,
+                        current: current_value,
+                        target: target_value,
+                    });
+                }
+            }
+        }
+
+        diffs
+    }
+
+    /// Write one section to the keyboard and read it back
+    /// Returns None when the section is absent from this state
+    pub fn write_section(
+        &self,
+        device: &AkkoHidDevice,
+        section: ConfigSection,
+    ) -> Result<Option<CommandResult>, String> {
+        let policy = RetryPolicy::default();
+
+        let result = match section {
+            ConfigSection::Profiles => self.profiles.map(|p| {
+                // Only the active profile is writable
+                verify::write_verified(
+                    &policy,
+                    &p.active,
+                    || commands::cmd_set_active_profile(device, p.active),
+                    || commands::cmd_read_profile_info(device).map(|i| i.active),
+                )
+            }),
+            // Not retried: the GET 0x87 layout is unconfirmed (see api::write_lighting)
+            ConfigSection::Lighting => self.lighting.map(|l| {
+                verify::write_verified(
+                    &RetryPolicy::once(),
+                    &l,
+                    || commands::cmd_apply_rgb_settings(device, l),
+                    || commands::cmd_read_rgb_settings(device).map(|read| read.apply_to(l)),
+                )
+            }),
+            ConfigSection::Performance => self.performance.map(|p| {
+                verify::write_verified(
+                    &policy,
+                    &p,
+                    || commands::cmd_set_performance(device, p),
+                    || commands::cmd_read_performance(device),
+                )
+            }),
+            ConfigSection::FnLock => self.fn_lock.map(|f| {
+                verify::write_verified(
+                    &policy,
+                    &f,
+                    || commands::cmd_set_fn_lock(device, f),
+                    || commands::cmd_read_fn_lock(device),
+                )
+            }),
+            ConfigSection::Indicator => self.indicator.map(|i| {
+                verify::write_verified(
+                    &policy,
+                    &i,
+                    || commands::cmd_set_indicator_led(device, i),
+                    || commands::cmd_read_indicator_led(device),
+                )
+            }),
+            ConfigSection::Sleep => self.sleep.map(|s| {
+                verify::write_verified(
+                    &policy,
+                    &s,
+                    || commands::cmd_set_sleep_settings(device, s),
+                    || commands::cmd_read_sleep_settings(device),
+                )
+            }),
+            table => {
+                return Err(format!(
+                    "{:?} is not part of KeyboardState (multi-packet table)",
+                    table
+                ))
+            }
+        };
+
+        result.transpose()
+    }
+
+    fn section_value(&self, section: ConfigSection) -> Value {
+        let value = match section {
+            ConfigSection::Profiles => serde_json::to_value(self.profiles),
+            ConfigSection::Lighting => serde_json::to_value(self.lighting),
+            ConfigSection::Performance => serde_json::to_value(self.performance),
+            ConfigSection::FnLock => serde_json::to_value(self.fn_lock),
+            ConfigSection::Indicator => serde_json::to_value(self.indicator),
+            ConfigSection::Sleep => serde_json::to_value(self.sleep),
+            _ => Ok(Value::Null),
+        };
+        value.unwrap_or(Value::Null)
+    }
+}
+
+fn log_err<T>(section: &str, result: Result<T, String>) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(e) => {
+            warn!("Failed to read {}: {}", section, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_reports_changed_fields_only() {
+        let current = KeyboardState {
+            fn_lock: Some(FnLockSettings { enabled: false }),
+            sleep: Some(SleepSettings {
+                timeout_minutes: 10,
+                lights_off_on_sleep: true,
+            }),
+            ..Default::default()
+        };
+        let target = KeyboardState {
+            fn_lock: Some(FnLockSettings { enabled: true }),
+            sleep: Some(SleepSettings {
+                timeout_minutes: 10,
+                lights_off_on_sleep: false,
+            }),
+            ..Default::default()
+        };
+
+        let diffs = current.diff(&target);
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].section, ConfigSection::FnLock);
+        assert_eq!(diffs[1].field, "lights_off_on_sleep");
+        assert!(target.diff(&target).is_empty());
+    }
+}
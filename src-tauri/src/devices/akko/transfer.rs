@@ -0,0 +1,361 @@
+//! Multi-packet transfers
+//! HYPOTHETICAL: the chunk format below has not been seen in a capture or
+//! checked against a keyboard, and no command uses these transfers yet.
+//! It is a guess kept for when per-key colors, keymaps and macros get
+//! captured; verify it (and fix this module) before wiring it to a command.
+//!
+//! Assumed format: tables larger than one packet are read and written in
+//! chunks, each carrying a header the keyboard echoes as its acknowledgement:
+//! - Byte 1: Sequence number (chunk index, wraps at 256)
+//! - Bytes 2-3: Offset into the table (little endian)
+//! - Byte 4: Data length of this chunk
+//! - Checksum per opcode, chunk data right after it
+//!
+//! A failed transfer reports how many bytes completed; passing that back in
+//! resumes where it stopped instead of starting over.
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use super::hid::AkkoHidDevice;
+use super::protocol::{AkkoOpcode, AkkoPacket};
+
+/// Largest table addressable with a 16-bit offset
+pub const MAX_TABLE_SIZE: usize = u16::MAX as usize + 1;
+
+/// Sends one packet and returns the keyboard's answer
+pub trait PacketExchange {
+    fn exchange(&self, packet: &AkkoPacket) -> Result<Vec<u8>, String>;
+}
+
+impl PacketExchange for AkkoHidDevice {
+    fn exchange(&self, packet: &AkkoPacket) -> Result<Vec<u8>, String> {
+        self.request(packet)
+    }
+}
+
+/// Chunk header (bytes 1-4, assumed layout)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkHeader {
+    pub seq: u8,
+    pub offset: u16,
+    pub length: u8,
+}
+
+impl ChunkHeader {
+    pub fn from_response(data: &[u8]) -> Option<Self> {
+        if data.len() < 5 {
+            return None;
+        }
+        Some(Self {
+            seq: data[1],
+            offset: u16::from_le_bytes([data[2], data[3]]),
+            length: data[4],
+        })
+    }
+
+    /// Field bytes for the packet builder (bytes 1-4)
+    pub fn to_fields(&self) -> [u8; 4] {
+        let [lo, hi] = self.offset.to_le_bytes();
+        [self.seq, lo, hi, self.length]
+    }
+}
+
+/// Chunk size and per-chunk retries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkPolicy {
+    /// Data bytes per chunk (capped at what fits after the checksum)
+    pub chunk_size: usize,
+    /// Re-sends of a chunk that failed or was acknowledged wrongly
+    pub retries: u32,
+}
+
+impl Default for ChunkPolicy {
+    fn default() -> Self {
+        Self {
+            chunk_size: 48,
+            retries: 2,
+        }
+    }
+}
+
+impl ChunkPolicy {
+    /// Chunk size that fits in a packet for `opcode`
+    pub fn chunk_size_for(&self, opcode: AkkoOpcode) -> usize {
+        let capacity = AkkoPacket::with_opcode(opcode).payload().len();
+        self.chunk_size.clamp(1, capacity.min(u8::MAX as usize))
+    }
+}
+
+/// A transfer that stopped part way
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferError {
+    /// Bytes transferred successfully (resume offset)
+    pub completed: usize,
+    pub error: String,
+}
+
+impl std::fmt::Display for TransferError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (after {} bytes)", self.error, self.completed)
+    }
+}
+
+impl From<TransferError> for String {
+    fn from(e: TransferError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Header of the chunk starting at `offset`
+fn header_at(offset: usize, length: usize, chunk_size: usize) -> ChunkHeader {
+    ChunkHeader {
+        seq: (offset / chunk_size) as u8,
+        offset: offset as u16,
+        length: length as u8,
+    }
+}
+
+/// Send a chunk until the keyboard echoes its header
+fn exchange_chunk<E: PacketExchange>(
+    device: &E,
+    packet: &AkkoPacket,
+    header: ChunkHeader,
+    retries: u32,
+) -> Result<Vec<u8>, String> {
+    let mut last_error = String::new();
+
+    for attempt in 0..=retries {
+        match device.exchange(packet) {
+            Ok(response) => match ChunkHeader::from_response(&response) {
+                Some(ack) if ack == header => return Ok(response),
+                ack => last_error = format!("chunk {} acknowledged as {:?}", header.seq, ack),
+            },
+            Err(e) => last_error = e,
+        }
+        warn!(
+            "Chunk {} at offset {} attempt {}/{} failed: {}",
+            header.seq,
+            header.offset,
+            attempt + 1,
+            retries + 1,
+            last_error
+        );
+    }
+
+    Err(last_error)
+}
+
+fn check_size(total: usize) -> Result<(), TransferError> {
+    if total > MAX_TABLE_SIZE {
+        return Err(TransferError {
+            completed: 0,
+            error: format!("Table of {} bytes exceeds {}", total, MAX_TABLE_SIZE),
+        });
+    }
+    Ok(())
+}
+
+/// Read `total` bytes into `buf`, starting at `buf.len()`
+///
+/// Pass the same (partly filled) buffer again to resume a failed read.
+pub fn read_chunked<E: PacketExchange>(
+    device: &E,
+    opcode: AkkoOpcode,
+    total: usize,
+    buf: &mut Vec<u8>,
+    policy: &ChunkPolicy,
+) -> Result<(), TransferError> {
+    check_size(total)?;
+    let chunk_size = policy.chunk_size_for(opcode);
+
+    while buf.len() < total {
+        let offset = buf.len();
+        let header = header_at(offset, chunk_size.min(total - offset), chunk_size);
+        let packet = AkkoPacket::with_fields(opcode, &header.to_fields());
+
+        debug!("Reading {} chunk {:?}", opcode.name(), header);
+        let response =
+            exchange_chunk(device, &packet, header, policy.retries).map_err(|error| {
+                TransferError {
+                    completed: offset,
+                    error,
+                }
+            })?;
+
+        let start = packet.as_bytes().len() - packet.payload().len();
+        let data = response
+            .get(start..start + header.length as usize)
+            .ok_or_else(|| TransferError {
+                completed: offset,
+                error: format!("chunk {} response too short", header.seq),
+            })?;
+        buf.extend_from_slice(data);
+    }
+
+    Ok(())
+}
+
+/// Read a whole table in one go
+pub fn read_table<E: PacketExchange>(
+    device: &E,
+    opcode: AkkoOpcode,
+    total: usize,
+    policy: &ChunkPolicy,
+) -> Result<Vec<u8>, TransferError> {
+    let mut buf = Vec::with_capacity(total);
+    read_chunked(device, opcode, total, &mut buf, policy)?;
+    Ok(buf)
+}
+
+/// Write `data`, starting at byte `start`
+///
+/// Pass `TransferError::completed` as `start` to resume a failed write.
+pub fn write_chunked<E: PacketExchange>(
+    device: &E,
+    opcode: AkkoOpcode,
+    data: &[u8],
+    start: usize,
+    policy: &ChunkPolicy,
+) -> Result<(), TransferError> {
+    check_size(data.len())?;
+    let chunk_size = policy.chunk_size_for(opcode);
+
+    let mut offset = start.min(data.len());
+    while offset < data.len() {
+        let chunk = &data[offset..data.len().min(offset + chunk_size)];
+        let header = header_at(offset, chunk.len(), chunk_size);
+        let mut packet = AkkoPacket::with_fields(opcode, &header.to_fields());
+        packet.set_payload(chunk);
+
+        debug!("Writing {} chunk {:?}", opcode.name(), header);
+        exchange_chunk(device, &packet, header, policy.retries).map_err(|error| TransferError {
+            completed: offset,
+            error,
+        })?;
+
+        offset += chunk.len();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    const READ: AkkoOpcode = AkkoOpcode::GetCustomRgb;
+    const WRITE: AkkoOpcode = AkkoOpcode::Unknown(0x06);
+
+    /// Keyboard-side table; fails every exchange once `fail_after` reaches 0
+    struct FakeTable {
+        table: RefCell<Vec<u8>>,
+        fail_after: Cell<Option<usize>>,
+        sent: Cell<usize>,
+    }
+
+    impl FakeTable {
+        fn new(len: usize) -> Self {
+            Self {
+                table: RefCell::new(vec![0; len]),
+                fail_after: Cell::new(None),
+                sent: Cell::new(0),
+            }
+        }
+    }
+
+    impl PacketExchange for FakeTable {
+        fn exchange(&self, packet: &AkkoPacket) -> Result<Vec<u8>, String> {
+            if let Some(n) = self.fail_after.get() {
+                if n == 0 {
+                    return Err("unplugged".to_string());
+                }
+                self.fail_after.set(Some(n - 1));
+            }
+            self.sent.set(self.sent.get() + 1);
+
+            let header = ChunkHeader::from_response(packet.as_bytes()).unwrap();
+            let range = header.offset as usize..header.offset as usize + header.length as usize;
+            let mut response = packet.as_bytes().to_vec();
+            let start = response.len() - packet.payload().len();
+
+            if packet.opcode() == READ {
+                response[start..start + range.len()].copy_from_slice(&self.table.borrow()[range]);
+            } else {
+                let data = &packet.payload()[..range.len()];
+                self.table.borrow_mut()[range].copy_from_slice(data);
+            }
+            Ok(response)
+        }
+    }
+
+    fn policy() -> ChunkPolicy {
+        ChunkPolicy {
+            chunk_size: 16,
+            retries: 0,
+        }
+    }
+
+    #[test]
+    fn test_header_roundtrip() {
+        let header = ChunkHeader {
+            seq: 3,
+            offset: 0x0130,
+            length: 16,
+        };
+        let mut response = vec![0x86];
+        response.extend_from_slice(&header.to_fields());
+        assert_eq!(ChunkHeader::from_response(&response), Some(header));
+    }
+
+    #[test]
+    fn test_write_then_read_across_chunks() {
+        let device = FakeTable::new(100);
+        let data: Vec<u8> = (0..100).collect();
+
+        write_chunked(&device, WRITE, &data, 0, &policy()).unwrap();
+        assert_eq!(*device.table.borrow(), data);
+        assert_eq!(device.sent.get(), 7);
+
+        assert_eq!(read_table(&device, READ, 100, &policy()).unwrap(), data);
+    }
+
+    #[test]
+    fn test_failed_transfer_resumes() {
+        let device = FakeTable::new(64);
+        let data = vec![0xAB; 64];
+
+        device.fail_after.set(Some(2));
+        let err = write_chunked(&device, WRITE, &data, 0, &policy()).unwrap_err();
+        assert_eq!(err.completed, 32);
+
+        device.fail_after.set(None);
+        device.sent.set(0);
+        write_chunked(&device, WRITE, &data, err.completed, &policy()).unwrap();
+        assert_eq!(device.sent.get(), 2);
+
+        device.fail_after.set(Some(1));
+        let mut buf = Vec::new();
+        let err = read_chunked(&device, READ, 64, &mut buf, &policy()).unwrap_err();
+        assert_eq!((err.completed, buf.len()), (16, 16));
+
+        device.fail_after.set(None);
+        read_chunked(&device, READ, 64, &mut buf, &policy()).unwrap();
+        assert_eq!(buf, data);
+    }
+
+    #[test]
+    fn test_wrong_ack_is_rejected() {
+        struct Deaf;
+        impl PacketExchange for Deaf {
+            fn exchange(&self, packet: &AkkoPacket) -> Result<Vec<u8>, String> {
+                Ok(vec![packet.as_bytes()[0]; 64])
+            }
+        }
+
+        let err = read_table(&Deaf, READ, 20, &policy()).unwrap_err();
+        assert_eq!(err.completed, 0);
+        assert!(err.error.contains("acknowledged"));
+    }
+}
@@ -0,0 +1,150 @@
+//! HID transports
+//! AkkoHidDevice talks to one open interface through HidTransport; a
+//! HidBackend enumerates interfaces and opens them. Two backends exist:
+//! - hidapi: the default, on every platform
+//! - hidraw: /dev/hidraw* ioctls with sysfs discovery (Linux on x86, ARM and
+//!   RISC-V only, hidraw.rs)
+//!
+//! The backend is chosen at runtime; every open() uses the current choice.
+
+use std::ffi::CString;
+use std::sync::Mutex;
+
+use hidapi::{HidApi, HidDevice};
+use serde::{Deserialize, Serialize};
+
+use super::hid::HidInterfaceInfo;
+use super::hidraw::HidrawBackend;
+
+/// Largest report descriptor (HID_MAX_DESCRIPTOR_SIZE)
+pub const MAX_DESCRIPTOR_SIZE: usize = 4096;
+
+/// One open HID interface
+pub trait HidTransport: Send {
+    /// Send a feature report; `data[0]` is the report ID
+    fn send_feature_report(&self, data: &[u8]) -> Result<(), String>;
+
+    /// Read a feature report into `buf` (`buf[0]` = report ID); returns the
+    /// number of bytes read including the report ID
+    fn get_feature_report(&self, buf: &mut [u8]) -> Result<usize, String>;
+
+    /// Raw report descriptor
+    fn report_descriptor(&self) -> Result<Vec<u8>, String>;
+}
+
+/// Finds and opens HID interfaces
+pub trait HidBackend {
+    fn kind(&self) -> TransportKind;
+
+    /// Interfaces with `vid`/`pid`, without opening them
+    fn interfaces(&self, vid: u16, pid: u16) -> Result<Vec<HidInterfaceInfo>, String>;
+
+    fn open(&self, interface: &HidInterfaceInfo) -> Result<Box<dyn HidTransport>, String>;
+}
+
+/// Which backend open() uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransportKind {
+    Hidapi,
+    Hidraw,
+}
+
+impl TransportKind {
+    pub fn is_available(&self) -> bool {
+        match self {
+            TransportKind::Hidapi => true,
+            TransportKind::Hidraw => super::hidraw::SUPPORTED,
+        }
+    }
+
+    pub fn backend(&self) -> Result<Box<dyn HidBackend>, String> {
+        match self {
+            TransportKind::Hidapi => Ok(Box::new(HidapiBackend::new()?)),
+            TransportKind::Hidraw if self.is_available() => Ok(Box::new(HidrawBackend::default())),
+            TransportKind::Hidraw => Err(
+                "The hidraw transport is only available on Linux (x86, ARM, RISC-V)".to_string(),
+            ),
+        }
+    }
+}
+
+static SELECTED: Mutex<TransportKind> = Mutex::new(TransportKind::Hidapi);
+
+pub fn selected() -> TransportKind {
+    *SELECTED.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Switch backends (applies to the next open)
+pub fn select(kind: TransportKind) -> Result<(), String> {
+    if !kind.is_available() {
+        return Err(format!(
+            "{:?} transport is not available on this platform",
+            kind
+        ));
+    }
+    *SELECTED.lock().unwrap_or_else(|e| e.into_inner()) = kind;
+    Ok(())
+}
+
+/// Backend for the current choice
+pub fn backend() -> Result<Box<dyn HidBackend>, String> {
+    selected().backend()
+}
+
+/// hidapi (libusb/hidraw on Linux, HID.dll on Windows, IOHIDManager on macOS)
+pub struct HidapiBackend {
+    api: HidApi,
+}
+
+impl HidapiBackend {
+    pub fn new() -> Result<Self, String> {
+        let api = HidApi::new().map_err(|e| format!("Failed to init HID API: {}", e))?;
+        Ok(Self { api })
+    }
+}
+
+impl HidBackend for HidapiBackend {
+    fn kind(&self) -> TransportKind {
+        TransportKind::Hidapi
+    }
+
+    fn interfaces(&self, vid: u16, pid: u16) -> Result<Vec<HidInterfaceInfo>, String> {
+        Ok(self
+            .api
+            .device_list()
+            .filter(|d| d.vendor_id() == vid && d.product_id() == pid)
+            .map(HidInterfaceInfo::from_device_info)
+            .collect())
+    }
+
+    fn open(&self, interface: &HidInterfaceInfo) -> Result<Box<dyn HidTransport>, String> {
+        let path = CString::new(interface.path.as_str())
+            .map_err(|e| format!("Invalid device path {:?}: {}", interface.path, e))?;
+        let device = self
+            .api
+            .open_path(&path)
+            .map_err(|e| format!("Failed to open {}: {}", interface.path, e))?;
+        Ok(Box::new(device))
+    }
+}
+
+impl HidTransport for HidDevice {
+    fn send_feature_report(&self, data: &[u8]) -> Result<(), String> {
+        HidDevice::send_feature_report(self, data)
+            .map_err(|e| format!("send_feature_report failed: {} (ensure 65-byte buffer)", e))
+    }
+
+    fn get_feature_report(&self, buf: &mut [u8]) -> Result<usize, String> {
+        HidDevice::get_feature_report(self, buf)
+            .map_err(|e| format!("get_feature_report failed: {}", e))
+    }
+
+    fn report_descriptor(&self) -> Result<Vec<u8>, String> {
+        let mut buf = [0u8; MAX_DESCRIPTOR_SIZE];
+        let len = self
+            .get_report_descriptor(&mut buf)
+            .map_err(|e| format!("get_report_descriptor failed: {}", e))?;
+        Ok(buf[..len].to_vec())
+    }
+}
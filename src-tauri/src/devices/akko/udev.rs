@@ -0,0 +1,411 @@
+//! Linux hidraw permissions
+//! Without a udev rule, /dev/hidraw* nodes belong to root and opening the
+//! keyboard fails with a bare "Failed to open device". This module finds the
+//! keyboard's hidraw nodes through sysfs, tells a permission failure apart
+//! from other open errors, and generates/installs a rule that grants the
+//! logged-in user access (TAG+="uaccess").
+//!
+//! Everything takes the sysfs/dev/rules paths as parameters so it can be
+//! tested against a fake tree.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use super::detector::AkkoModel;
+use super::queue::LockedDevice;
+
+/// Installed rule (numbered below 73 so uaccess is applied)
+pub const RULES_PATH: &str = "/etc/udev/rules.d/70-akko-keyboard.rules";
+
+/// Opens after installing the rule (udev applies it asynchronously)
+const RETRIES: u32 = 5;
+const RETRY_DELAY: Duration = Duration::from_millis(300);
+
+/// Where sysfs and device nodes live (`/sys` and `/dev` on a real system)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SysfsRoot {
+    pub sys: PathBuf,
+    pub dev: PathBuf,
+}
+
+impl Default for SysfsRoot {
+    fn default() -> Self {
+        Self {
+            sys: PathBuf::from("/sys"),
+            dev: PathBuf::from("/dev"),
+        }
+    }
+}
+
+/// A hidraw node and the HID device behind it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HidrawNode {
+    /// e.g. "hidraw3"
+    pub name: String,
+    pub dev_path: PathBuf,
+    pub vid: u16,
+    pub pid: u16,
+}
+
+/// VID/PID from a HID uevent ("HID_ID=0003:00003151:00005009")
+pub fn parse_hid_id(uevent: &str) -> Option<(u16, u16)> {
+    let id = uevent
+        .lines()
+        .find_map(|line| line.strip_prefix("HID_ID="))?;
+    let mut parts = id.trim().split(':').skip(1);
+    let vid = u32::from_str_radix(parts.next()?, 16).ok()?;
+    let pid = u32::from_str_radix(parts.next()?, 16).ok()?;
+    Some((u16::try_from(vid).ok()?, u16::try_from(pid).ok()?))
+}
+
+impl SysfsRoot {
+    /// sysfs directory of the HID device behind hidraw node `name`
+    pub fn hidraw_device(&self, name: &str) -> PathBuf {
+        self.sys.join("class/hidraw").join(name).join("device")
+    }
+
+    /// VID/PID of the HID device behind hidraw node `name`
+    pub fn hidraw_ids(&self, name: &str) -> Option<(u16, u16)> {
+        let uevent = fs::read_to_string(self.hidraw_device(name).join("uevent")).ok()?;
+        parse_hid_id(&uevent)
+    }
+
+    /// hidraw nodes whose device matches `vid`/`pid`, sorted by name
+    pub fn hidraw_nodes(&self, vid: u16, pid: u16) -> Vec<HidrawNode> {
+        let class = self.sys.join("class/hidraw");
+        let entries = match fs::read_dir(&class) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut nodes: Vec<HidrawNode> = entries
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name().into_string().ok()?;
+                let (node_vid, node_pid) = self.hidraw_ids(&name)?;
+                (node_vid == vid && node_pid == pid).then(|| HidrawNode {
+                    dev_path: self.dev.join(&name),
+                    name,
+                    vid,
+                    pid,
+                })
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.name.cmp(&b.name));
+        nodes
+    }
+}
+
+/// The rule for every registered model (one line per distinct VID/PID)
+pub fn rule_text(models: &[AkkoModel]) -> String {
+    let mut ids: Vec<(u16, u16)> = models.iter().map(|m| (m.vid(), m.pid())).collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    let mut rule = String::from(
+        "# Akko keyboards: let the logged-in user send HID feature reports\n\
+         # Generated by the keyboard app; reinstall it after adding models\n",
+    );
+    for (vid, pid) in ids {
+        rule.push_str(&format!(
+            "SUBSYSTEM==\"hidraw\", ATTRS{{idVendor}}==\"{:04x}\", ATTRS{{idProduct}}==\"{:04x}\", MODE=\"0660\", TAG+=\"uaccess\"\n",
+            vid, pid
+        ));
+    }
+    rule
+}
+
+/// State of the rule file at `path`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleState {
+    Missing,
+    /// Installed, but for a different set of models
+    Outdated,
+    Current,
+}
+
+pub fn rule_state(path: &Path, models: &[AkkoModel]) -> RuleState {
+    match fs::read_to_string(path) {
+        Ok(installed) if installed == rule_text(models) => RuleState::Current,
+        Ok(_) => RuleState::Outdated,
+        Err(_) => RuleState::Missing,
+    }
+}
+
+/// hidraw nodes of a keyboard that this user may not open
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermissionProblem {
+    pub vid: u16,
+    pub pid: u16,
+    pub denied: Vec<PathBuf>,
+    pub rule: RuleState,
+}
+
+impl PermissionProblem {
+    /// What went wrong and what to do about it
+    pub fn explain(&self) -> String {
+        let nodes: Vec<String> = self
+            .denied
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        let advice = match self.rule {
+            RuleState::Current => {
+                "The udev rule is installed but not applied yet: unplug and replug the keyboard"
+                    .to_string()
+            }
+            RuleState::Missing | RuleState::Outdated => format!(
+                "Install the udev rule (from the app or by copying it to {}), then replug the keyboard",
+                RULES_PATH
+            ),
+        };
+        format!(
+            "No permission to open {} (VID: 0x{:04X}, PID: 0x{:04X}). {}",
+            nodes.join(", "),
+            self.vid,
+            self.pid,
+            advice
+        )
+    }
+}
+
+/// Check every hidraw node of `vid`/`pid` with `open` (read/write)
+///
+/// Only PermissionDenied counts; None if no node was denied.
+pub fn detect_permission_problem(
+    root: &SysfsRoot,
+    vid: u16,
+    pid: u16,
+    rule: RuleState,
+    open: impl Fn(&Path) -> io::Result<()>,
+) -> Option<PermissionProblem> {
+    let denied: Vec<PathBuf> = root
+        .hidraw_nodes(vid, pid)
+        .into_iter()
+        .filter(|node| {
+            matches!(open(&node.dev_path), Err(e) if e.kind() == io::ErrorKind::PermissionDenied)
+        })
+        .map(|node| node.dev_path)
+        .collect();
+
+    (!denied.is_empty()).then_some(PermissionProblem {
+        vid,
+        pid,
+        denied,
+        rule,
+    })
+}
+
+fn open_read_write(path: &Path) -> io::Result<()> {
+    fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map(|_| ())
+}
+
+/// Permission problem on this system, if any (always None off Linux)
+pub fn check_permissions(vid: u16, pid: u16) -> Option<PermissionProblem> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let rule = rule_state(Path::new(RULES_PATH), AkkoModel::ALL.as_slice());
+    detect_permission_problem(&SysfsRoot::default(), vid, pid, rule, open_read_write)
+}
+
+/// Everything the UI needs to offer the rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UdevStatus {
+    /// False off Linux (nothing to install)
+    pub supported: bool,
+    pub path: String,
+    pub rule: String,
+    pub state: RuleState,
+    pub problem: Option<PermissionProblem>,
+}
+
+pub fn status(model: AkkoModel) -> UdevStatus {
+    let models = AkkoModel::ALL.as_slice();
+    UdevStatus {
+        supported: cfg!(target_os = "linux"),
+        path: RULES_PATH.to_string(),
+        rule: rule_text(models),
+        state: rule_state(Path::new(RULES_PATH), models),
+        problem: check_permissions(model.vid(), model.pid()),
+    }
+}
+
+/// Replace an open error with an explanation when it was a permission problem
+pub fn explain_open_error(vid: u16, pid: u16, error: String) -> String {
+    match check_permissions(vid, pid) {
+        Some(problem) => {
+            warn!("{}", problem.explain());
+            format!("{} ({})", problem.explain(), error)
+        }
+        None => error,
+    }
+}
+
+/// pkexec invocation that installs the rule read from stdin as `dest`,
+/// reloads the rules and re-triggers hidraw devices (one password prompt)
+///
+/// The rule never touches a file other users could create or swap before
+/// root installs it.
+pub fn install_command(dest: &Path) -> (String, Vec<String>) {
+    let script = "install -m 0644 /dev/stdin \"$1\" && udevadm control --reload-rules \
+                  && udevadm trigger --subsystem-match=hidraw --action=add";
+    (
+        "pkexec".to_string(),
+        vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            script.to_string(),
+            "sh".to_string(),
+            dest.display().to_string(),
+        ],
+    )
+}
+
+/// Install the rule for every registered model through pkexec, then retry
+/// opening `model` until udev has applied it
+pub fn install_rule(model: AkkoModel) -> Result<(), String> {
+    if !cfg!(target_os = "linux") {
+        return Err("udev rules only apply on Linux".to_string());
+    }
+
+    let (program, args) = install_command(Path::new(RULES_PATH));
+    info!("Installing udev rule: {} {:?}", program, args);
+    let mut child = Command::new(&program)
+        .args(&args)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    // Dropping stdin after the write closes it, so install sees EOF
+    let written = child
+        .stdin
+        .take()
+        .map(|mut stdin| stdin.write_all(rule_text(AkkoModel::ALL.as_slice()).as_bytes()));
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for {}: {}", program, e))?;
+
+    // pkexec: 126 = dismissed, 127 = not authorized
+    if !status.success() {
+        return Err(match status.code() {
+            Some(126) | Some(127) => "Installing the udev rule was cancelled".to_string(),
+            _ => format!("Installing the udev rule failed ({})", status),
+        });
+    }
+    if let Some(Err(e)) = written {
+        return Err(format!(
+            "Failed to pass the udev rule to {}: {}",
+            program, e
+        ));
+    }
+
+    let mut last_error = String::new();
+    for attempt in 1..=RETRIES {
+        let _ = Command::new("udevadm").arg("settle").status();
+        match LockedDevice::open(model) {
+            Ok(_) => {
+                info!("{} opened after installing the udev rule", model.name());
+                return Ok(());
+            }
+            Err(e) => last_error = e,
+        }
+        warn!(
+            "Open attempt {}/{} failed: {}",
+            attempt, RETRIES, last_error
+        );
+        thread::sleep(RETRY_DELAY);
+    }
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_root() -> SysfsRoot {
+        let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/sysfs");
+        SysfsRoot {
+            sys: Path::new(fixtures).join("sys"),
+            dev: PathBuf::from("/dev"),
+        }
+    }
+
+    #[test]
+    fn test_hidraw_nodes_from_sysfs() {
+        assert_eq!(
+            parse_hid_id("DRIVER=hid-generic\nHID_ID=0003:00003151:00005009\n"),
+            Some((0x3151, 0x5009))
+        );
+        assert_eq!(parse_hid_id("HID_ID=0003:00013151:00005009"), None);
+
+        let nodes = fake_root().hidraw_nodes(0x3151, 0x5009);
+        let names: Vec<&str> = nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, ["hidraw0", "hidraw1"]);
+        assert_eq!(nodes[1].dev_path, Path::new("/dev/hidraw1"));
+        assert!(fake_root().hidraw_nodes(0x3151, 0x4011).is_empty());
+    }
+
+    #[test]
+    fn test_rule_covers_every_model_once() {
+        let rule = rule_text(&[
+            AkkoModel::Mod007b,
+            AkkoModel::Akko24GWireless,
+            AkkoModel::Mod007b,
+        ]);
+        let lines: Vec<&str> = rule.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "SUBSYSTEM==\"hidraw\", ATTRS{idVendor}==\"3151\", ATTRS{idProduct}==\"4011\", MODE=\"0660\", TAG+=\"uaccess\""
+        );
+        assert!(lines[1].contains("ATTRS{idProduct}==\"5009\""));
+    }
+
+    #[test]
+    fn test_install_reads_rule_from_stdin() {
+        let (program, args) = install_command(Path::new(RULES_PATH));
+        assert_eq!(program, "pkexec");
+        assert!(args[2].starts_with("install -m 0644 /dev/stdin \"$1\""));
+        assert_eq!(args[4..], [RULES_PATH.to_string()]);
+    }
+
+    #[test]
+    fn test_detects_only_permission_denied() {
+        let root = fake_root();
+        let denied = |path: &Path| {
+            if path.ends_with("hidraw1") {
+                Err(io::Error::from(io::ErrorKind::PermissionDenied))
+            } else {
+                Err(io::Error::from(io::ErrorKind::NotFound))
+            }
+        };
+
+        let problem =
+            detect_permission_problem(&root, 0x3151, 0x5009, RuleState::Missing, denied).unwrap();
+        assert_eq!(problem.denied, [PathBuf::from("/dev/hidraw1")]);
+        assert!(problem.explain().contains(RULES_PATH));
+
+        let installed = PermissionProblem {
+            rule: RuleState::Current,
+            ..problem
+        };
+        assert!(installed.explain().contains("replug"));
+
+        assert!(
+            detect_permission_problem(&root, 0x3151, 0x5009, RuleState::Missing, |_| Ok(()))
+                .is_none()
+        );
+    }
+}
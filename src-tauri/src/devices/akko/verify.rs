@@ -0,0 +1,239 @@
+//! Read-after-write verification
+//! A SET is only trusted once the matching GET decodes to the values written;
+//! mismatches are retried according to a RetryPolicy
+
+use std::thread;
+use std::time::Duration;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::commands::CommandResult;
+
+/// A field that did not read back as written
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldMismatch {
+    pub field: String,
+    pub expected: Value,
+    pub actual: Value,
+}
+
+/// Outcome of reading a write back
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Verification {
+    pub verified: bool,
+    /// Writes sent (1 = no retry needed)
+    pub attempts: u32,
+    /// Mismatches from the last read-back (empty when verified)
+    pub mismatches: Vec<FieldMismatch>,
+}
+
+impl Verification {
+    /// One-line summary for errors and logs
+    pub fn describe(&self) -> String {
+        if self.verified {
+            return format!("verified after {} attempt(s)", self.attempts);
+        }
+        let fields: Vec<String> = self
+            .mismatches
+            .iter()
+            .map(|m| format!("{} (wrote {}, read {})", m.field, m.expected, m.actual))
+            .collect();
+        format!(
+            "not verified after {} attempt(s): {}",
+            self.attempts,
+            fields.join(", ")
+        )
+    }
+}
+
+/// How often to re-send a write that did not read back correctly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total writes, including the first (minimum 1)
+    pub max_attempts: u32,
+    /// Pause before re-sending
+    pub delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            delay: Duration::from_millis(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Verify but never re-send
+    pub fn once() -> Self {
+        Self {
+            max_attempts: 1,
+            delay: Duration::ZERO,
+        }
+    }
+}
+
+/// Turn a write that did not read back correctly into an error
+/// Every setter in api.rs goes through this except lighting (see
+/// warn_unverified), so Ok always means verified
+pub fn ensure_verified(result: CommandResult) -> Result<CommandResult, String> {
+    match &result.verification {
+        Some(v) if !v.verified => Err(format!("{} {}", result.opcode_name, v.describe())),
+        _ => Ok(result),
+    }
+}
+
+/// Log a write that did not read back correctly instead of failing it
+/// For reads whose layout no capture confirms (GET 0x87), where a mismatch
+/// may be a decoding error rather than a lost write. The mismatch stays in
+/// `verification` for the caller to show.
+pub fn warn_unverified(result: CommandResult) -> CommandResult {
+    if let Some(v) = result.verification.as_ref().filter(|v| !v.verified) {
+        warn!("{} {}", result.opcode_name, v.describe());
+    }
+    result
+}
+
+/// Compare two decoded values field by field
+/// Non-struct values are compared as a whole under the field name "value"
+pub fn compare<T: Serialize>(expected: &T, actual: &T) -> Vec<FieldMismatch> {
+    let expected = serde_json::to_value(expected).unwrap_or(Value::Null);
+    let actual = serde_json::to_value(actual).unwrap_or(Value::Null);
+
+    match (expected, actual) {
+        (Value::Object(expected), actual) => expected
+            .into_iter()
+            .filter_map(|(field, wanted)| {
+                let got = actual.get(&field).cloned().unwrap_or(Value::Null);
+                (got != wanted).then_some(FieldMismatch {
+                    field,
+                    expected: wanted,
+                    actual: got,
+                })
+            })
+            .collect(),
+        (expected, actual) if expected != actual => vec![FieldMismatch {
+            field: "value".to_string(),
+            expected,
+            actual,
+        }],
+        _ => Vec::new(),
+    }
+}
+
+/// Send a write, read it back and retry on mismatch
+///
+/// Transport errors from `write` or `read` abort immediately. The returned
+/// CommandResult is the last write's, with `verification` filled in.
+pub fn write_verified<T, W, R>(
+    policy: &RetryPolicy,
+    expected: &T,
+    mut write: W,
+    mut read: R,
+) -> Result<CommandResult, String>
+where
+    T: Serialize,
+    W: FnMut() -> Result<CommandResult, String>,
+    R: FnMut() -> Result<T, String>,
+{
+    let max_attempts = policy.max_attempts.max(1);
+    let mut attempts = 0;
+
+    loop {
+        attempts += 1;
+        let mut result = write()?;
+        let mismatches = compare(expected, &read()?);
+
+        if mismatches.is_empty() || attempts >= max_attempts {
+            result.verification = Some(Verification {
+                verified: mismatches.is_empty(),
+                attempts,
+                mismatches,
+            });
+            return Ok(result);
+        }
+
+        warn!(
+            "{} read back differently (attempt {}/{}), retrying",
+            result.opcode_name, attempts, max_attempts
+        );
+        thread::sleep(policy.delay);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::akko::protocol::{AkkoOpcode, FnLockSettings};
+
+    fn ack() -> Result<CommandResult, String> {
+        Ok(CommandResult::from_response(
+            AkkoOpcode::SetFnLock,
+            vec![0x04, 0, 1],
+        ))
+    }
+
+    #[test]
+    fn test_compare_lists_changed_fields() {
+        let on = FnLockSettings { enabled: true };
+        let off = FnLockSettings { enabled: false };
+
+        assert!(compare(&on, &on).is_empty());
+        let mismatches = compare(&on, &off);
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].field, "enabled");
+        assert_eq!(mismatches[0].expected, Value::Bool(true));
+
+        assert_eq!(compare(&3u8, &4u8)[0].field, "value");
+    }
+
+    #[test]
+    fn test_write_verified_retries_until_match() {
+        let expected = FnLockSettings { enabled: true };
+        let mut reads = vec![expected, FnLockSettings { enabled: false }];
+        let mut writes = 0;
+
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            delay: Duration::ZERO,
+        };
+        let result = write_verified(
+            &policy,
+            &expected,
+            || {
+                writes += 1;
+                ack()
+            },
+            || Ok(reads.pop().unwrap()),
+        )
+        .unwrap();
+
+        let verification = result.verification.unwrap();
+        assert!(verification.verified);
+        assert_eq!(verification.attempts, 2);
+        assert_eq!(writes, 2);
+    }
+
+    #[test]
+    fn test_write_verified_reports_mismatch_when_exhausted() {
+        let expected = FnLockSettings { enabled: true };
+
+        let result = write_verified(&RetryPolicy::once(), &expected, ack, || {
+            Ok(FnLockSettings { enabled: false })
+        })
+        .unwrap();
+
+        let verification = result.verification.clone().unwrap();
+        assert!(!verification.verified);
+        assert_eq!(verification.attempts, 1);
+        assert_eq!(verification.mismatches[0].field, "enabled");
+        assert!(verification.describe().contains("enabled"));
+
+        assert!(ensure_verified(result.clone()).is_err());
+        let kept = warn_unverified(result);
+        assert!(!kept.verification.unwrap().verified);
+    }
+}
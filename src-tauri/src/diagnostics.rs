@@ -0,0 +1,136 @@
+//! Diagnostics bundle for bug reports
+//! A zip with the log files, a support report for every connected keyboard
+//! (see devices::akko::diagnose) and basic system information:
+//! - system.json
+//! - devices.json
+//! - logs/keyboard.log, logs/keyboard.1.log, ...
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::devices::akko::detector::detect_akko_devices;
+use crate::devices::akko::diagnose::diagnose;
+use crate::devices::akko::DiagnoseReport;
+
+/// App and platform the bundle was collected on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemInfo {
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub log_level: String,
+    /// RFC 3339 local time
+    pub collected_at: String,
+}
+
+impl SystemInfo {
+    pub fn current(app_version: &str, log_level: &str) -> Self {
+        Self {
+            app_version: app_version.to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            log_level: log_level.to_string(),
+            collected_at: chrono::Local::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Support report for every connected keyboard
+pub fn collect_devices() -> Vec<DiagnoseReport> {
+    detect_akko_devices().into_iter().map(diagnose).collect()
+}
+
+/// Write the bundle to `path`
+/// Log files that disappeared since they were listed are skipped
+pub fn write_bundle(
+    path: &Path,
+    system: &SystemInfo,
+    devices: &[DiagnoseReport],
+    logs: &[PathBuf],
+) -> Result<(), String> {
+    let zip_err = |e: zip::result::ZipError| format!("Failed to write {}: {}", path.display(), e);
+    let io_err = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+
+    let file = File::create(path).map_err(io_err)?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut add = |name: &str, contents: &[u8]| -> Result<(), String> {
+        zip.start_file(name, options).map_err(zip_err)?;
+        zip.write_all(contents).map_err(io_err)
+    };
+
+    add("system.json", to_json(system)?.as_bytes())?;
+    add("devices.json", to_json(&devices)?.as_bytes())?;
+
+    for log in logs {
+        let Some(name) = log.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        match fs::read(log) {
+            Ok(contents) => add(&format!("logs/{}", name), &contents)?,
+            // Rotated away between listing and reading
+            Err(e) => warn!("Skipping {}: {}", log.display(), e),
+        }
+    }
+
+    zip.finish().map_err(zip_err)?;
+    info!("Diagnostics written to {}", path.display());
+    Ok(())
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_bundle_contains_logs_and_reports() {
+        let dir = std::env::temp_dir().join(format!("keyboard-diag-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("keyboard.log");
+        fs::write(
+            &log,
+            "2024-05-01 12:00:00.000 INFO  keyboard_lib: started\n",
+        )
+        .unwrap();
+
+        let devices = vec![DiagnoseReport {
+            model: "MOD007B".to_string(),
+            vid: 0x3151,
+            pid: 0x5009,
+            errors: vec!["No device found".to_string()],
+            ..Default::default()
+        }];
+        let bundle = dir.join("diagnostics.zip");
+        let system = SystemInfo::current("0.1.0", "debug");
+        let missing = dir.join("keyboard.1.log");
+        write_bundle(&bundle, &system, &devices, &[log, missing]).unwrap();
+
+        let mut zip = zip::ZipArchive::new(File::open(&bundle).unwrap()).unwrap();
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort();
+        assert_eq!(names, ["devices.json", "logs/keyboard.log", "system.json"]);
+
+        let mut json = String::new();
+        zip.by_name("devices.json")
+            .unwrap()
+            .read_to_string(&mut json)
+            .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed[0]["pid"], 0x5009);
+        assert_eq!(parsed[0]["errors"][0], "No device found");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,226 @@
+//! Global hotkey bindings
+//! Accelerator validation and conflict checks plus the persisted binding list;
+//! registration with the OS lives in services::hotkeys
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::devices::akko::AkkoModel;
+use crate::storage;
+
+/// File name in the app config directory
+pub const HOTKEYS_FILE: &str = "hotkeys.json";
+
+/// What a hotkey does
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HotkeyAction {
+    BrightnessUp,
+    BrightnessDown,
+    /// Switch to the next known lighting effect
+    CycleEffect,
+    /// LEDs off, or back to the last brightness
+    ToggleLights,
+    /// Next profile (wraps)
+    NextProfile,
+    /// Apply the Nth preset of the library (1-based)
+    ApplyPreset {
+        slot: usize,
+    },
+}
+
+/// One accelerator bound to an action
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HotkeyBinding {
+    /// e.g. "Ctrl+Alt+Up"
+    pub accelerator: String,
+    pub action: HotkeyAction,
+    /// Only for this model; None = all connected keyboards
+    #[serde(default)]
+    pub model: Option<AkkoModel>,
+}
+
+impl HotkeyBinding {
+    pub fn applies_to(&self, model: AkkoModel) -> bool {
+        self.model.is_none_or(|m| m == model)
+    }
+}
+
+/// Persisted hotkey configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HotkeySettings {
+    #[serde(default)]
+    pub bindings: Vec<HotkeyBinding>,
+}
+
+impl HotkeySettings {
+    /// Check every accelerator and reject bindings that share one
+    pub fn validate(&self) -> Result<(), String> {
+        let mut seen: Vec<(String, &HotkeyBinding)> = Vec::new();
+
+        for binding in &self.bindings {
+            let normalized = normalize_accelerator(&binding.accelerator)?;
+            if let HotkeyAction::ApplyPreset { slot: 0 } = binding.action {
+                return Err("Preset slots start at 1".to_string());
+            }
+
+            if let Some((_, other)) = seen.iter().find(|(acc, _)| *acc == normalized) {
+                return Err(format!(
+                    "{} is bound to both {:?} and {:?}",
+                    normalized, other.action, binding.action
+                ));
+            }
+            seen.push((normalized, binding));
+        }
+        Ok(())
+    }
+}
+
+/// Canonical form of an accelerator ("alt+ctrl+l" -> "Ctrl+Alt+L")
+///
+/// At least one modifier is required so a hotkey cannot swallow normal typing.
+/// CmdOrCtrl is resolved for the current platform so it conflicts with the
+/// key it stands for.
+pub fn normalize_accelerator(accelerator: &str) -> Result<String, String> {
+    const ORDER: [&str; 4] = ["Ctrl", "Alt", "Shift", "Super"];
+
+    let mut modifiers: Vec<&str> = Vec::new();
+    let mut key: Option<String> = None;
+
+    for part in accelerator.split('+').map(str::trim) {
+        let modifier = match part.to_lowercase().as_str() {
+            "ctrl" | "control" => Some("Ctrl"),
+            "alt" | "option" => Some("Alt"),
+            "shift" => Some("Shift"),
+            "super" | "cmd" | "command" | "meta" => Some("Super"),
+            "cmdorctrl" | "cmdorcontrol" | "commandorctrl" | "commandorcontrol" => {
+                Some(if cfg!(target_os = "macos") {
+                    "Super"
+                } else {
+                    "Ctrl"
+                })
+            }
+            _ => None,
+        };
+
+        match modifier {
+            Some(m) if modifiers.contains(&m) => {
+                return Err(format!("Duplicate modifier in {:?}", accelerator));
+            }
+            Some(m) => modifiers.push(m),
+            None if part.is_empty() => {
+                return Err(format!("Invalid accelerator {:?}", accelerator));
+            }
+            None if key.is_some() => {
+                return Err(format!("Only one key allowed in {:?}", accelerator));
+            }
+            None => key = Some(normalize_key(part)),
+        }
+    }
+
+    let key = key.ok_or_else(|| format!("No key in {:?}", accelerator))?;
+    if modifiers.is_empty() {
+        return Err(format!("{:?} needs at least one modifier", accelerator));
+    }
+
+    let mut parts: Vec<String> = ORDER
+        .iter()
+        .filter(|m| modifiers.contains(m))
+        .map(|m| m.to_string())
+        .collect();
+    parts.push(key);
+    Ok(parts.join("+"))
+}
+
+/// "a" -> "A", "pageup" -> "Pageup"; keeps matching case-insensitive
+fn normalize_key(key: &str) -> String {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// Persisted hotkeys (Tauri managed state)
+pub struct HotkeyStore {
+    path: PathBuf,
+    settings: Mutex<HotkeySettings>,
+}
+
+impl HotkeyStore {
+    /// Load from disk (no bindings if the file does not exist yet)
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let settings = storage::read_json(&path)?.unwrap_or_default();
+        Ok(Self {
+            path,
+            settings: Mutex::new(settings),
+        })
+    }
+
+    pub fn get(&self) -> HotkeySettings {
+        self.settings
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn set(&self, settings: HotkeySettings) -> Result<(), String> {
+        settings.validate()?;
+        storage::write_json(&self.path, &settings)?;
+        *self.settings.lock().unwrap_or_else(|e| e.into_inner()) = settings;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(accelerator: &str, action: HotkeyAction) -> HotkeyBinding {
+        HotkeyBinding {
+            accelerator: accelerator.to_string(),
+            action,
+            model: None,
+        }
+    }
+
+    #[test]
+    fn test_normalize_accelerator() {
+        assert_eq!(normalize_accelerator("alt+ctrl+l").unwrap(), "Ctrl+Alt+L");
+        assert_eq!(
+            normalize_accelerator("Shift + Control + PageUp").unwrap(),
+            "Ctrl+Shift+Pageup"
+        );
+        assert!(normalize_accelerator("L").is_err());
+        assert!(normalize_accelerator("Ctrl+A+B").is_err());
+        assert!(normalize_accelerator("Ctrl+Ctrl+A").is_err());
+        assert!(normalize_accelerator("Ctrl+").is_err());
+    }
+
+    #[test]
+    fn test_conflicting_bindings_rejected() {
+        let mut settings = HotkeySettings {
+            bindings: vec![
+                binding("Ctrl+Alt+Up", HotkeyAction::BrightnessUp),
+                binding("Ctrl+Alt+Down", HotkeyAction::BrightnessDown),
+            ],
+        };
+        assert!(settings.validate().is_ok());
+
+        settings
+            .bindings
+            .push(binding("alt+control+up", HotkeyAction::NextProfile));
+        let err = settings.validate().unwrap_err();
+        assert!(err.contains("Ctrl+Alt+Up"), "{}", err);
+
+        let slot_zero = HotkeySettings {
+            bindings: vec![binding("Ctrl+1", HotkeyAction::ApplyPreset { slot: 0 })],
+        };
+        assert!(slot_zero.validate().is_err());
+    }
+}
@@ -0,0 +1,338 @@
+//! System-wide idle detection
+//! Platform probes for input idle time / screen lock, plus the idle settings
+//!
+//! - Windows: GetLastInputInfo (lock is not reported, idle time covers it)
+//! - Linux: logind session IdleHint / LockedHint via `loginctl`
+//! - macOS: IOHIDSystem HIDIdleTime via `ioreg`
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::schedules::ScheduleAction;
+use crate::storage;
+
+/// File name in the app config directory
+pub const IDLE_FILE: &str = "idle.json";
+
+/// Current idle state of the system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IdleStatus {
+    /// Time since last keyboard/mouse input
+    pub idle_for: Duration,
+    pub locked: bool,
+}
+
+/// User settings for idle lighting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdleSettings {
+    pub enabled: bool,
+    /// Seconds without input before applying the idle action
+    pub timeout_secs: u64,
+    /// Treat a locked screen as idle immediately
+    pub idle_when_locked: bool,
+    /// Lighting applied while idle
+    pub action: ScheduleAction,
+}
+
+impl Default for IdleSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_secs: 300,
+            idle_when_locked: true,
+            action: ScheduleAction::Brightness { level: 0 },
+        }
+    }
+}
+
+impl IdleSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.timeout_secs < 10 {
+            return Err("Idle timeout must be at least 10 seconds".to_string());
+        }
+        if let ScheduleAction::Brightness { level } = self.action {
+            if level > 4 {
+                return Err(format!("Brightness must be 0-4, got {}", level));
+            }
+        }
+        Ok(())
+    }
+
+    fn is_idle(&self, status: IdleStatus) -> bool {
+        self.enabled
+            && (status.idle_for >= Duration::from_secs(self.timeout_secs)
+                || (self.idle_when_locked && status.locked))
+    }
+}
+
+/// Edge reported by IdleTracker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleTransition {
+    None,
+    /// Went idle: snapshot state, then apply the idle action
+    EnterIdle,
+    /// Input again (or feature disabled): restore the snapshot
+    Resume,
+}
+
+/// Tracks idle/active edges
+#[derive(Debug, Default)]
+pub struct IdleTracker {
+    idle: bool,
+}
+
+impl IdleTracker {
+    pub fn update(&mut self, settings: &IdleSettings, status: IdleStatus) -> IdleTransition {
+        let idle = settings.is_idle(status);
+
+        let transition = match (self.idle, idle) {
+            (false, true) => IdleTransition::EnterIdle,
+            (true, false) => IdleTransition::Resume,
+            _ => IdleTransition::None,
+        };
+        self.idle = idle;
+        transition
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+}
+
+/// Persisted idle settings (Tauri managed state)
+pub struct IdleStore {
+    path: PathBuf,
+    settings: Mutex<IdleSettings>,
+}
+
+impl IdleStore {
+    /// Load from disk (defaults if the file does not exist yet)
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let settings = storage::read_json(&path)?.unwrap_or_default();
+        Ok(Self {
+            path,
+            settings: Mutex::new(settings),
+        })
+    }
+
+    pub fn get(&self) -> IdleSettings {
+        self.settings
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn set(&self, settings: IdleSettings) -> Result<(), String> {
+        settings.validate()?;
+        storage::write_json(&self.path, &settings)?;
+        *self.settings.lock().unwrap_or_else(|e| e.into_inner()) = settings;
+        Ok(())
+    }
+}
+
+/// Query the system idle state
+#[cfg(target_os = "windows")]
+pub fn system_idle() -> Result<IdleStatus, String> {
+    #[repr(C)]
+    struct LastInputInfo {
+        cb_size: u32,
+        dw_time: u32,
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetLastInputInfo(plii: *mut LastInputInfo) -> i32;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetTickCount() -> u32;
+    }
+
+    let mut info = LastInputInfo {
+        cb_size: std::mem::size_of::<LastInputInfo>() as u32,
+        dw_time: 0,
+    };
+
+    // SAFETY: info is a valid, correctly sized LASTINPUTINFO
+    let ok = unsafe { GetLastInputInfo(&mut info) };
+    if ok == 0 {
+        return Err("GetLastInputInfo failed".to_string());
+    }
+
+    // SAFETY: no arguments, always succeeds
+    let now = unsafe { GetTickCount() };
+
+    Ok(IdleStatus {
+        idle_for: Duration::from_millis(now.wrapping_sub(info.dw_time) as u64),
+        locked: false,
+    })
+}
+
+/// Query the system idle state
+#[cfg(target_os = "linux")]
+pub fn system_idle() -> Result<IdleStatus, String> {
+    let output = std::process::Command::new("loginctl")
+        .args([
+            "show-session",
+            "auto",
+            "-p",
+            "IdleHint",
+            "-p",
+            "IdleSinceHint",
+            "-p",
+            "LockedHint",
+        ])
+        .output()
+        .map_err(|e| format!("Failed to run loginctl: {}", e))?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    let now_us = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0);
+
+    Ok(parse_loginctl(
+        &String::from_utf8_lossy(&output.stdout),
+        now_us,
+    ))
+}
+
+/// Query the system idle state
+#[cfg(target_os = "macos")]
+pub fn system_idle() -> Result<IdleStatus, String> {
+    let output = std::process::Command::new("ioreg")
+        .args(["-c", "IOHIDSystem", "-d", "4"])
+        .output()
+        .map_err(|e| format!("Failed to run ioreg: {}", e))?;
+
+    parse_ioreg(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| "HIDIdleTime not found in ioreg output".to_string())
+}
+
+/// Query the system idle state
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+pub fn system_idle() -> Result<IdleStatus, String> {
+    Err("Idle detection is not supported on this platform".to_string())
+}
+
+/// Parse `loginctl show-session -p IdleHint -p IdleSinceHint -p LockedHint`
+/// IdleSinceHint is a realtime timestamp in microseconds
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_loginctl(output: &str, now_us: u64) -> IdleStatus {
+    let mut idle_hint = false;
+    let mut idle_since = 0_u64;
+    let mut locked = false;
+
+    for line in output.lines() {
+        match line.split_once('=') {
+            Some(("IdleHint", value)) => idle_hint = value.trim() == "yes",
+            Some(("IdleSinceHint", value)) => idle_since = value.trim().parse().unwrap_or(0),
+            Some(("LockedHint", value)) => locked = value.trim() == "yes",
+            _ => {}
+        }
+    }
+
+    let idle_for = if idle_hint && idle_since > 0 {
+        Duration::from_micros(now_us.saturating_sub(idle_since))
+    } else {
+        Duration::ZERO
+    };
+
+    IdleStatus { idle_for, locked }
+}
+
+/// Parse `"HIDIdleTime" = <nanoseconds>` from ioreg output
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_ioreg(output: &str) -> Option<IdleStatus> {
+    let line = output.lines().find(|l| l.contains("\"HIDIdleTime\""))?;
+    let nanos: u64 = line.rsplit('=').next()?.trim().parse().ok()?;
+
+    Some(IdleStatus {
+        idle_for: Duration::from_nanos(nanos),
+        locked: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> IdleSettings {
+        IdleSettings {
+            enabled: true,
+            timeout_secs: 60,
+            ..Default::default()
+        }
+    }
+
+    fn idle(secs: u64, locked: bool) -> IdleStatus {
+        IdleStatus {
+            idle_for: Duration::from_secs(secs),
+            locked,
+        }
+    }
+
+    #[test]
+    fn test_tracker_edges() {
+        let mut tracker = IdleTracker::default();
+        let settings = settings();
+
+        assert_eq!(
+            tracker.update(&settings, idle(10, false)),
+            IdleTransition::None
+        );
+        assert_eq!(
+            tracker.update(&settings, idle(61, false)),
+            IdleTransition::EnterIdle
+        );
+        assert_eq!(
+            tracker.update(&settings, idle(90, false)),
+            IdleTransition::None
+        );
+        assert_eq!(
+            tracker.update(&settings, idle(0, false)),
+            IdleTransition::Resume
+        );
+        // Lock counts as idle right away
+        assert_eq!(
+            tracker.update(&settings, idle(0, true)),
+            IdleTransition::EnterIdle
+        );
+
+        // Disabling while idle restores
+        let disabled = IdleSettings {
+            enabled: false,
+            ..settings
+        };
+        assert_eq!(
+            tracker.update(&disabled, idle(0, true)),
+            IdleTransition::Resume
+        );
+    }
+
+    #[test]
+    fn test_parse_loginctl() {
+        let out = "IdleHint=yes\nIdleSinceHint=1000000000\nLockedHint=no\n";
+        let status = parse_loginctl(out, 1_120_000_000);
+        assert_eq!(status.idle_for, Duration::from_secs(120));
+        assert!(!status.locked);
+
+        let active = parse_loginctl("IdleHint=no\nIdleSinceHint=5\nLockedHint=yes\n", 10);
+        assert_eq!(active.idle_for, Duration::ZERO);
+        assert!(active.locked);
+    }
+
+    #[test]
+    fn test_parse_ioreg() {
+        let out = "    |   \"HIDIdleTime\" = 5000000000\n";
+        assert_eq!(parse_ioreg(out).unwrap().idle_for, Duration::from_secs(5));
+        assert!(parse_ioreg("nothing").is_none());
+    }
+}
@@ -0,0 +1,117 @@
+//! Last configuration applied from the app, per keyboard
+//! Used to put a keyboard back the way the user left it after unplug/suspend
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use log::warn;
+
+use crate::devices::akko::protocol::{ProfileInfo, RgbSettings};
+use crate::devices::akko::{AkkoModel, ConfigSection, FieldDiff, KeyboardState};
+use crate::storage;
+
+/// File name in the app data directory
+pub const LAST_APPLIED_FILE: &str = "last_applied.json";
+
+/// Persisted last-applied state (Tauri managed state)
+pub struct LastApplied {
+    path: PathBuf,
+    states: Mutex<HashMap<AkkoModel, KeyboardState>>,
+}
+
+impl LastApplied {
+    /// Load from disk (empty if the file does not exist yet)
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let states = storage::read_json(&path)?.unwrap_or_default();
+        Ok(Self {
+            path,
+            states: Mutex::new(states),
+        })
+    }
+
+    pub fn get(&self, model: AkkoModel) -> Option<KeyboardState> {
+        self.lock().get(&model).cloned()
+    }
+
+    /// Lighting last sent to the active profile
+    pub fn lighting(&self, model: AkkoModel) -> Option<RgbSettings> {
+        self.lock().get(&model).and_then(|state| state.lighting)
+    }
+
+    /// Remember lighting sent by the user
+    pub fn record_lighting(&self, model: AkkoModel, lighting: RgbSettings) {
+        self.record(model, |state| state.lighting = Some(lighting));
+    }
+
+    /// Remember the active profile (as read back from the keyboard)
+    pub fn record_profile(&self, model: AkkoModel, profiles: ProfileInfo) {
+        self.record(model, |state| {
+            state.profiles = Some(profiles);
+            // Lighting belongs to the previous profile
+            state.lighting = None;
+        });
+    }
+
+    fn record(&self, model: AkkoModel, f: impl FnOnce(&mut KeyboardState)) {
+        let mut states = self.lock();
+        f(states.entry(model).or_default());
+
+        // Losing this file only disables reapply, so don't fail the setter
+        if let Err(e) = storage::write_json(&self.path, &*states) {
+            warn!("Failed to save last applied state: {}", e);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<AkkoModel, KeyboardState>> {
+        self.states.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Sections that differ between a fresh read and what was last applied,
+/// in restore order; sections with an unverified setter are left alone
+pub fn sections_to_reapply(diffs: &[FieldDiff]) -> Vec<ConfigSection> {
+    ConfigSection::ALL
+        .into_iter()
+        .filter(|section| section.unverified_opcode().is_none())
+        .filter(|section| diffs.iter().any(|d| d.section == *section))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections_to_reapply() {
+        let remembered = KeyboardState {
+            profiles: Some(ProfileInfo {
+                count: 3,
+                active: 1,
+            }),
+            lighting: Some(RgbSettings {
+                direction: 4,
+                speed: 2,
+                brightness: 3,
+                mode: 0x08,
+                color: (255, 0, 0),
+            }),
+            ..Default::default()
+        };
+
+        // Came back on profile 0 with dimmer lighting
+        let mut fresh = remembered.clone();
+        fresh.profiles = Some(ProfileInfo {
+            count: 3,
+            active: 0,
+        });
+        fresh.lighting.as_mut().unwrap().brightness = 1;
+
+        // The profile switch is unverified, so only lighting is re-sent
+        assert_eq!(
+            sections_to_reapply(&fresh.diff(&remembered)),
+            vec![ConfigSection::Lighting]
+        );
+        assert!(sections_to_reapply(&remembered.diff(&remembered)).is_empty());
+    }
+}
@@ -1,8 +1,41 @@
+mod actions;
 mod devices;
+mod diagnostics;
+mod hotkeys;
+mod idle;
+mod last_applied;
+mod logging;
+mod presets;
+mod reset;
+mod schedules;
+mod services;
+mod storage;
+mod tray;
 
 use active_win_pos_rs::get_active_window;
-use devices::akko::{self, AkkoModel, CommandResult, ProbeResult};
-use log::{error, info};
+use devices::akko::capture::{self, CaptureTranscript, UsbAddress};
+use devices::akko::diagnose;
+use devices::akko::inspector::{self, InspectorPacket, InspectorSettings};
+use devices::akko::protocol::{ProfileInfo, RgbSettings};
+use devices::akko::udev::{self, UdevStatus};
+use devices::akko::{
+    self, AkkoModel, BatteryStatus, CloudImport, CommandResult, ConfigDocument, ConfigSection,
+    DeviceInfo, DiagnoseReport, FirmwareVersion, FnLockSettings, IndicatorBehaviour,
+    IndicatorLedSettings, KeyboardState, ProbeResult, ResetScope, RestorePreview, RestoreReport,
+    SleepSettings, TransferPolicy, TransportKind,
+};
+use hotkeys::{HotkeySettings, HotkeyStore};
+use idle::{IdleSettings, IdleStore};
+use last_applied::LastApplied;
+use log::{error, info, warn};
+use presets::{LightingPreset, PresetStore};
+use reset::{ResetOutcome, ResetRequest, ResetTokens};
+use schedules::{Schedule, ScheduleStore};
+use services::lighting::{LightingChange, LightingQueues};
+use std::path::Path;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State, WindowEvent};
+use tray::TrayState;
 
 /// Tauri command: Get the active application name/process
 #[tauri::command]
@@ -30,6 +63,25 @@ fn akko_handshake(model: String) -> Result<Vec<u8>, String> {
     akko::akko_handshake(akko_model)
 }
 
+/// Tauri command: Get firmware version decoded from the handshake
+#[tauri::command]
+fn akko_get_firmware_version(model: String) -> Result<FirmwareVersion, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    akko::api::akko_get_firmware_version(akko_model)
+}
+
+/// Tauri command: Get device info (model, VID/PID, firmware)
+#[tauri::command]
+fn akko_get_device_info(model: String) -> Result<DeviceInfo, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    info!("Tauri command: akko_get_device_info({})", model);
+    akko::api::akko_get_device_info(akko_model)
+}
+
 /// Tauri command: Detect connected Akko devices
 #[tauri::command]
 fn detect_akko_devices() -> Vec<String> {
@@ -113,8 +165,13 @@ fn akko_get_performance(model: String) -> Result<CommandResult, String> {
 }
 
 /// Tauri command: Send raw packet
+/// `override_checksum` sends the packet even if its checksum is wrong (probing)
 #[tauri::command]
-fn akko_send_raw(model: String, packet: Vec<u8>) -> Result<Vec<u8>, String> {
+fn akko_send_raw(
+    model: String,
+    packet: Vec<u8>,
+    override_checksum: Option<bool>,
+) -> Result<Vec<u8>, String> {
     let akko_model =
         AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
 
@@ -130,12 +187,14 @@ fn akko_send_raw(model: String, packet: Vec<u8>) -> Result<Vec<u8>, String> {
         model,
         packet.len()
     );
-    akko::akko_send_packet(akko_model, arr)
+    akko::akko_send_packet(akko_model, arr, override_checksum.unwrap_or(false))
 }
 
 /// Tauri command: Set RGB settings
+/// Queued per keyboard; resolves when the write carrying this value completes
 #[tauri::command]
-fn akko_set_rgb_settings(
+async fn akko_set_rgb_settings(
+    app: AppHandle,
     model: String,
     brightness: u8,
     speed: u8,
@@ -149,13 +208,22 @@ fn akko_set_rgb_settings(
 
     info!("Tauri command: akko_set_rgb_settings({}, brightness={}, speed={}, direction={}, color=({},{},{}))", 
           model, brightness, speed, direction, r, g, b);
-    akko::api::akko_set_rgb_settings(akko_model, brightness, speed, direction, (r, g, b))
+    let lighting = RgbSettings {
+        direction,
+        speed: speed.min(4),
+        brightness: brightness.min(4),
+        mode: 0x07,
+        color: (r, g, b),
+    };
+    queue_lighting(app, akko_model, lighting).await
 }
 
 /// Tauri command: Set RGB settings with specific mode
 /// mode: 7 = Dazzle, 8 = Static Color
+/// Queued like akko_set_rgb_settings
 #[tauri::command]
-fn akko_set_rgb_with_mode(
+async fn akko_set_rgb_with_mode(
+    app: AppHandle,
     model: String,
     brightness: u8,
     speed: u8,
@@ -170,28 +238,719 @@ fn akko_set_rgb_with_mode(
 
     info!("Tauri command: akko_set_rgb_with_mode({}, brightness={}, speed={}, mode=0x{:02X}, color=({},{},{}))", 
           model, brightness, speed, mode, r, g, b);
-    akko::api::akko_set_rgb_settings_with_mode(
-        akko_model,
-        brightness,
-        speed,
+    let lighting = RgbSettings {
         direction,
-        (r, g, b),
+        speed: speed.min(4),
+        brightness: brightness.min(4),
         mode,
+        color: (r, g, b),
+    };
+    queue_lighting(app, akko_model, lighting).await
+}
+
+/// Queue lighting and wait (off the async runtime) for the write that covers it
+/// Rapid calls are coalesced, so earlier callers get the result of a later write
+async fn queue_lighting(
+    app: AppHandle,
+    model: AkkoModel,
+    lighting: RgbSettings,
+) -> Result<CommandResult, String> {
+    let ticket = app
+        .state::<LightingQueues>()
+        .submit(&app, model, LightingChange::Set(lighting));
+
+    tauri::async_runtime::spawn_blocking(move || ticket.wait().map(|(_, result)| result))
+        .await
+        .map_err(|e| format!("Lighting write task failed: {}", e))?
+}
+
+/// Tauri command: Get FN lock state (0x84)
+#[tauri::command]
+fn akko_get_fn_lock(model: String) -> Result<FnLockSettings, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    akko::api::akko_get_fn_lock(akko_model)
+}
+
+/// Tauri command: Set FN lock on/off
+/// Experimental: the opcode is unverified; the read-back reports whether it took
+#[tauri::command]
+fn akko_set_fn_lock(model: String, enabled: bool) -> Result<CommandResult, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    info!(
+        "Tauri command: akko_set_fn_lock({}, enabled={})",
+        model, enabled
+    );
+    akko::api::akko_set_fn_lock(akko_model, FnLockSettings { enabled })
+}
+
+/// Tauri command: Get indicator LED settings (0x91)
+#[tauri::command]
+fn akko_get_indicator_led(model: String) -> Result<IndicatorLedSettings, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    akko::api::akko_get_indicator_led(akko_model)
+}
+
+/// Tauri command: Set indicator LED on/off and behaviour
+/// Experimental: the opcode is unverified; the read-back reports whether it took
+/// behaviour: "always_on" | "follow_backlight" | "status_only"
+#[tauri::command]
+fn akko_set_indicator_led(
+    model: String,
+    enabled: bool,
+    behaviour: IndicatorBehaviour,
+) -> Result<CommandResult, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    info!(
+        "Tauri command: akko_set_indicator_led({}, enabled={}, behaviour={:?})",
+        model, enabled, behaviour
+    );
+    akko::api::akko_set_indicator_led(akko_model, IndicatorLedSettings { enabled, behaviour })
+}
+
+/// Tauri command: Get sleep settings (0x97)
+#[tauri::command]
+fn akko_get_sleep_settings(model: String) -> Result<SleepSettings, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    akko::api::akko_get_sleep_settings(akko_model)
+}
+
+/// Tauri command: Set sleep timeout (minutes, 0 = never) and lights-off-on-sleep
+/// Experimental: the opcode is unverified; the read-back reports whether it took
+#[tauri::command]
+fn akko_set_sleep_settings(
+    model: String,
+    timeout_minutes: u8,
+    lights_off_on_sleep: bool,
+) -> Result<CommandResult, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    info!(
+        "Tauri command: akko_set_sleep_settings({}, timeout={}min, lights_off={})",
+        model, timeout_minutes, lights_off_on_sleep
+    );
+    akko::api::akko_set_sleep_settings(
+        akko_model,
+        SleepSettings {
+            timeout_minutes,
+            lights_off_on_sleep,
+        },
     )
 }
 
+/// Tauri command: Get battery percent, charging state and connection mode (0x9D)
+#[tauri::command]
+fn akko_get_battery_status(model: String) -> Result<BatteryStatus, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    akko::api::akko_get_battery_status(akko_model)
+}
+
+/// Tauri command: Request a reset; returns a confirmation token
+/// scope: { target: "factory" } | { target: "lighting" | "keymap" | "macros", profile }
+#[tauri::command]
+fn akko_request_reset(
+    tokens: State<'_, ResetTokens>,
+    model: String,
+    scope: ResetScope,
+) -> Result<ResetRequest, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    info!("Tauri command: akko_request_reset({}, {:?})", model, scope);
+    akko::backup::check_reset_supported(scope)?;
+    Ok(ResetRequest {
+        token: tokens.issue(akko_model, scope),
+        description: format!("{} on {}", scope.describe(), akko_model.name()),
+        expires_in_secs: reset::TOKEN_TTL.as_secs(),
+    })
+}
+
+/// Tauri command: Confirm a reset with its token
+/// Backs up the current configuration first; aborts if the backup fails or
+/// cannot undo the reset
+#[tauri::command]
+async fn akko_confirm_reset(
+    app: AppHandle,
+    model: String,
+    scope: ResetScope,
+    token: String,
+) -> Result<ResetOutcome, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    info!("Tauri command: akko_confirm_reset({}, {:?})", model, scope);
+    tauri::async_runtime::spawn_blocking(move || confirm_reset(&app, akko_model, scope, &token))
+        .await
+        .map_err(|e| format!("Reset task failed: {}", e))?
+}
+
+/// Back up, redeem the token and reset (blocking)
+fn confirm_reset(
+    app: &AppHandle,
+    model: AkkoModel,
+    scope: ResetScope,
+    token: &str,
+) -> Result<ResetOutcome, String> {
+    akko::backup::check_reset_supported(scope)?;
+
+    // The token is only used up once the backup can undo the reset, so a
+    // failed backup can be retried with the same confirmation
+    let tokens = app.state::<ResetTokens>();
+    tokens.check(token, model, scope)?;
+
+    let known_lighting = app.state::<LastApplied>().lighting(model);
+    let backup = akko::api::akko_export_config(model, known_lighting)
+        .map_err(|e| format!("Backup failed, reset aborted: {}", e))?;
+    akko::backup::check_reset_backup(&backup, scope)?;
+    tokens.redeem(token, model, scope)?;
+
+    let path = storage::app_data_subdir(app, "backups")?.join(backup.file_name("pre-reset"));
+    storage::write_json(&path, &backup)?;
+    warn!("Backup written to {}, resetting", path.display());
+
+    let result = akko::api::akko_reset(model, scope, &backup)?;
+
+    Ok(ResetOutcome {
+        backup_path: path.display().to_string(),
+        result,
+    })
+}
+
+/// Tauri command: Read typed keyboard state
+/// Lighting is only included once it was set from the app (the effect
+/// cannot be read back)
+#[tauri::command]
+fn akko_read_state(app: AppHandle, model: String) -> Result<KeyboardState, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    let known_lighting = app.state::<LastApplied>().lighting(akko_model);
+    akko::api::akko_read_state(akko_model, known_lighting)
+}
+
+/// Tauri command: Export full configuration to a JSON file
+#[tauri::command]
+async fn akko_export_config(
+    app: AppHandle,
+    model: String,
+    path: String,
+) -> Result<ConfigDocument, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    info!("Tauri command: akko_export_config({}, {})", model, path);
+    let known_lighting = app.state::<LastApplied>().lighting(akko_model);
+    tauri::async_runtime::spawn_blocking(move || -> Result<ConfigDocument, String> {
+        let doc = akko::api::akko_export_config(akko_model, known_lighting)?;
+        storage::write_json(Path::new(&path), &doc)?;
+        Ok(doc)
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+}
+
+/// Load a configuration document from disk
+fn load_config_document(path: &str) -> Result<ConfigDocument, String> {
+    storage::read_json(Path::new(path))?.ok_or_else(|| format!("File not found: {}", path))
+}
+
+/// Tauri command: Show what restoring a backup file would change
+#[tauri::command]
+fn akko_preview_restore(
+    model: String,
+    path: String,
+    sections: Vec<ConfigSection>,
+    allow_model_mapping: bool,
+) -> Result<RestorePreview, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    let doc = load_config_document(&path)?;
+    akko::api::akko_preview_restore(akko_model, &doc, &sections, allow_model_mapping)
+}
+
+/// Tauri command: Restore selected sections of a backup file
+#[tauri::command]
+fn akko_restore_config(
+    model: String,
+    path: String,
+    sections: Vec<ConfigSection>,
+    allow_model_mapping: bool,
+) -> Result<RestoreReport, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    info!(
+        "Tauri command: akko_restore_config({}, {}, {:?})",
+        model, path, sections
+    );
+    let doc = load_config_document(&path)?;
+    akko::api::akko_restore_config(akko_model, &doc, &sections, allow_model_mapping)
+}
+
+/// Tauri command: Parse an Akko Cloud profile export (preview before import)
+#[tauri::command]
+fn akko_parse_cloud_export(path: String) -> Result<CloudImport, String> {
+    let json =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    CloudImport::parse(&json)
+}
+
+/// Tauri command: Write selected profiles from an Akko Cloud export
+/// Only the profile active on the keyboard is written; others come back skipped
+#[tauri::command]
+fn akko_import_cloud_profiles(
+    model: String,
+    path: String,
+    profiles: Vec<u8>,
+    allow_model_mapping: bool,
+) -> Result<Vec<RestoreReport>, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    info!(
+        "Tauri command: akko_import_cloud_profiles({}, {}, {:?})",
+        model, path, profiles
+    );
+    let import = akko_parse_cloud_export(path)?;
+    akko::api::akko_import_cloud_profiles(akko_model, &import, &profiles, allow_model_mapping)
+}
+
+/// Tauri command: Decode a usbmon capture (pcap / pcapng) of a keyboard's traffic
+/// `address` ("bus:device") selects the device when the capture lacks its enumeration
+#[tauri::command]
+fn akko_import_capture(
+    model: String,
+    path: String,
+    address: Option<String>,
+) -> Result<CaptureTranscript, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+    let address = address
+        .map(|a| UsbAddress::parse(&a).ok_or_else(|| format!("Invalid USB address: {}", a)))
+        .transpose()?;
+
+    info!("Tauri command: akko_import_capture({}, {})", model, path);
+    let file = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    capture::import_capture(&file, akko_model.vid(), akko_model.pid(), address)
+}
+
+/// Tauri command: List lighting presets
+#[tauri::command]
+fn list_presets(presets: State<'_, PresetStore>) -> Vec<LightingPreset> {
+    presets.list()
+}
+
+/// Tauri command: Create or replace a lighting preset (by name)
+#[tauri::command]
+fn save_preset(
+    app: AppHandle,
+    presets: State<'_, PresetStore>,
+    preset: LightingPreset,
+) -> Result<(), String> {
+    info!("Tauri command: save_preset({})", preset.name);
+    presets.update(|library| library.upsert(preset))?;
+    tray::refresh(&app);
+    Ok(())
+}
+
+/// Tauri command: Rename a lighting preset
+#[tauri::command]
+fn rename_preset(
+    app: AppHandle,
+    presets: State<'_, PresetStore>,
+    from: String,
+    to: String,
+) -> Result<(), String> {
+    presets.update(|library| library.rename(&from, &to))?;
+    tray::refresh(&app);
+    Ok(())
+}
+
+/// Tauri command: Delete a lighting preset
+#[tauri::command]
+fn delete_preset(
+    app: AppHandle,
+    presets: State<'_, PresetStore>,
+    name: String,
+) -> Result<(), String> {
+    info!("Tauri command: delete_preset({})", name);
+    presets.update(|library| library.delete(&name))?;
+    tray::refresh(&app);
+    Ok(())
+}
+
+/// Tauri command: Apply a lighting preset by name
+#[tauri::command]
+async fn akko_apply_preset(
+    app: AppHandle,
+    model: String,
+    name: String,
+) -> Result<CommandResult, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    info!("Tauri command: akko_apply_preset({}, {})", model, name);
+    tauri::async_runtime::spawn_blocking(move || actions::apply_preset(&app, akko_model, &name))
+        .await
+        .map_err(|e| format!("Lighting write task failed: {}", e))?
+}
+
+/// Tauri command: Import a preset pack; returns names added or replaced
+#[tauri::command]
+fn import_presets(
+    app: AppHandle,
+    presets: State<'_, PresetStore>,
+    path: String,
+    overwrite: bool,
+) -> Result<Vec<String>, String> {
+    let pack = PresetStore::read_pack(Path::new(&path))?;
+    let names = presets.update(|library| library.merge(pack, overwrite))?;
+    tray::refresh(&app);
+    Ok(names)
+}
+
+/// Tauri command: Export presets to a pack file (all when names is empty)
+#[tauri::command]
+fn export_presets(
+    presets: State<'_, PresetStore>,
+    path: String,
+    names: Vec<String>,
+) -> Result<(), String> {
+    let pack = presets.export(&names)?;
+    storage::write_json(Path::new(&path), &pack)
+}
+
+/// Tauri command: Change brightness only (0 = LEDs off)
+#[tauri::command]
+async fn akko_set_brightness(
+    app: AppHandle,
+    model: String,
+    brightness: u8,
+) -> Result<CommandResult, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    info!(
+        "Tauri command: akko_set_brightness({}, {})",
+        model, brightness
+    );
+    tauri::async_runtime::spawn_blocking(move || {
+        actions::set_brightness(&app, akko_model, brightness)
+    })
+    .await
+    .map_err(|e| format!("Lighting write task failed: {}", e))?
+}
+
+/// Tauri command: Switch the active profile (0-based)
+/// Experimental: the opcode is unverified; the read-back reports whether it took
+#[tauri::command]
+fn akko_set_active_profile(
+    app: AppHandle,
+    model: String,
+    profile: u8,
+) -> Result<ProfileInfo, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    info!(
+        "Tauri command: akko_set_active_profile({}, {})",
+        model, profile
+    );
+    actions::set_active_profile(&app, akko_model, profile)
+}
+
+/// Tauri command: Whether app-based profile switching is enabled
+#[tauri::command]
+fn get_auto_switch(app: AppHandle) -> bool {
+    tray::auto_switch(&app)
+}
+
+/// Tauri command: Enable or disable app-based profile switching (mirrored in the tray)
+#[tauri::command]
+fn set_auto_switch(app: AppHandle, enabled: bool) -> Result<(), String> {
+    info!("Tauri command: set_auto_switch({})", enabled);
+    tray::set_auto_switch(&app, enabled)
+}
+
+/// Tauri command: Get protocol inspector settings (pause, buffer size, filter)
+#[tauri::command]
+fn get_inspector_settings() -> InspectorSettings {
+    inspector::settings()
+}
+
+/// Tauri command: Change protocol inspector settings
+#[tauri::command]
+fn set_inspector_settings(settings: InspectorSettings) {
+    info!("Tauri command: set_inspector_settings({:?})", settings);
+    inspector::set_settings(settings);
+}
+
+/// Tauri command: Buffered packets matching the inspector filter, oldest first
+#[tauri::command]
+fn get_inspector_packets() -> Vec<InspectorPacket> {
+    inspector::snapshot()
+}
+
+/// Tauri command: Empty the protocol inspector buffer
+#[tauri::command]
+fn clear_inspector() {
+    inspector::clear();
+}
+
+/// Tauri command: Support report for a keyboard (interfaces, selected interface
+/// and why, handshake, every known GET with timings, hidraw permissions)
+#[tauri::command]
+async fn akko_diagnose(model: String) -> Result<DiagnoseReport, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    info!("Tauri command: akko_diagnose({})", model);
+    tauri::async_runtime::spawn_blocking(move || diagnose::diagnose(akko_model))
+        .await
+        .map_err(|e| format!("Diagnose task failed: {}", e))
+}
+
+/// Tauri command: HID transport used to open keyboards
+#[tauri::command]
+fn get_hid_transport() -> TransportKind {
+    akko::transport::selected()
+}
+
+/// Tauri command: Switch HID transport ("hidapi" | "hidraw", the latter Linux only)
+/// Applies from the next open
+#[tauri::command]
+fn set_hid_transport(transport: TransportKind) -> Result<(), String> {
+    info!("Tauri command: set_hid_transport({:?})", transport);
+    akko::transport::select(transport)
+}
+
+/// Tauri command: Timeouts and retries used for keyboard requests
+#[tauri::command]
+fn get_transfer_policy() -> TransferPolicy {
+    akko::hid::transfer_policy()
+}
+
+/// Tauri command: Change request timeouts and retries
+/// Applies from the next open
+#[tauri::command]
+fn set_transfer_policy(policy: TransferPolicy) -> Result<(), String> {
+    info!("Tauri command: set_transfer_policy({:?})", policy);
+    akko::hid::set_transfer_policy(policy)
+}
+
+/// Tauri command: udev rule state and any hidraw permission problem (Linux)
+#[tauri::command]
+fn get_udev_status(model: String) -> Result<UdevStatus, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    info!("Tauri command: get_udev_status({})", model);
+    Ok(udev::status(akko_model))
+}
+
+/// Tauri command: Install the udev rule via pkexec, re-trigger udev and reopen
+/// (off the async runtime: pkexec waits for the password prompt)
+#[tauri::command]
+async fn install_udev_rule(model: String) -> Result<(), String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    info!("Tauri command: install_udev_rule({})", model);
+    tauri::async_runtime::spawn_blocking(move || udev::install_rule(akko_model))
+        .await
+        .map_err(|e| format!("udev rule install task failed: {}", e))?
+}
+
+/// Tauri command: Current log level ("off", "error", ..., "trace")
+#[tauri::command]
+fn get_log_level() -> String {
+    logging::level().to_string().to_lowercase()
+}
+
+/// Tauri command: Change the log level at runtime
+#[tauri::command]
+fn set_log_level(level: String) -> Result<(), String> {
+    let filter =
+        logging::parse_level(&level).ok_or_else(|| format!("Unknown log level: {}", level))?;
+
+    info!("Tauri command: set_log_level({})", level);
+    logging::set_level(filter);
+    Ok(())
+}
+
+/// Tauri command: Zip logs, device descriptors and firmware versions to `path`
+#[tauri::command]
+fn collect_diagnostics(app: AppHandle, path: String) -> Result<String, String> {
+    info!("Tauri command: collect_diagnostics({})", path);
+
+    let system =
+        diagnostics::SystemInfo::current(&app.package_info().version.to_string(), &get_log_level());
+    let devices = diagnostics::collect_devices();
+
+    logging::flush();
+    let logs = logging::log_dir()
+        .map(|dir| logging::log_files(&dir))
+        .unwrap_or_default();
+
+    diagnostics::write_bundle(Path::new(&path), &system, &devices, &logs)?;
+    Ok(path)
+}
+
+/// Tauri command: Get global hotkey bindings
+#[tauri::command]
+fn get_hotkeys(hotkeys: State<'_, HotkeyStore>) -> HotkeySettings {
+    hotkeys.get()
+}
+
+/// Tauri command: Save and register global hotkey bindings
+/// Nothing is saved if a binding conflicts or the OS refuses a shortcut
+#[tauri::command]
+fn set_hotkeys(
+    app: AppHandle,
+    hotkeys: State<'_, HotkeyStore>,
+    settings: HotkeySettings,
+) -> Result<(), String> {
+    info!(
+        "Tauri command: set_hotkeys({} bindings)",
+        settings.bindings.len()
+    );
+    settings.validate()?;
+
+    if let Err(e) = services::hotkeys::register(&app, &settings) {
+        if let Err(restore) = services::hotkeys::register(&app, &hotkeys.get()) {
+            warn!("Failed to restore previous hotkeys: {}", restore);
+        }
+        return Err(e);
+    }
+    hotkeys.set(settings)
+}
+
+/// Tauri command: Re-send settings that differ from the last applied state
+#[tauri::command]
+fn akko_reapply_last_state(app: AppHandle, model: String) -> Result<Vec<ConfigSection>, String> {
+    let akko_model =
+        AkkoModel::from_str(&model).ok_or_else(|| format!("Unknown Akko model: {}", model))?;
+
+    services::reapply::reapply(&app, akko_model)
+}
+
+/// Tauri command: List lighting schedules
+#[tauri::command]
+fn list_schedules(schedules: State<'_, ScheduleStore>) -> Vec<Schedule> {
+    schedules.list()
+}
+
+/// Tauri command: Create or replace a lighting schedule (by name)
+#[tauri::command]
+fn save_schedule(schedules: State<'_, ScheduleStore>, schedule: Schedule) -> Result<(), String> {
+    info!("Tauri command: save_schedule({})", schedule.name);
+    schedules.save(schedule)
+}
+
+/// Tauri command: Delete a lighting schedule
+#[tauri::command]
+fn delete_schedule(schedules: State<'_, ScheduleStore>, name: String) -> Result<(), String> {
+    info!("Tauri command: delete_schedule({})", name);
+    schedules.delete(&name)
+}
+
+/// Tauri command: Get idle lighting settings
+#[tauri::command]
+fn get_idle_settings(idle: State<'_, IdleStore>) -> IdleSettings {
+    idle.get()
+}
+
+/// Tauri command: Save idle lighting settings
+#[tauri::command]
+fn set_idle_settings(idle: State<'_, IdleStore>, settings: IdleSettings) -> Result<(), String> {
+    info!("Tauri command: set_idle_settings({:?})", settings);
+    idle.set(settings)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    env_logger::init();
+    logging::init();
 
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
-        .plugin(tauri_plugin_autostart::init(tauri_plugin_autostart::MacosLauncher::LaunchAgent, Some(vec!["--flag1", "--flag2"])))
+        .plugin(services::hotkeys::plugin())
+        .plugin(tauri_plugin_autostart::init(
+            tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+            Some(vec![tray::HIDDEN_ARG]),
+        ))
+        .manage(ResetTokens::default())
+        .manage(LightingQueues::default())
+        .setup(|app| {
+            let log_dir = storage::app_data_subdir(app.handle(), logging::LOG_DIR)?;
+            if let Err(e) = logging::attach_dir(&log_dir) {
+                warn!("{}", e);
+            }
+
+            let presets_path = storage::app_config_file(app.handle(), presets::PRESETS_FILE)?;
+            app.manage(PresetStore::load(presets_path)?);
+
+            let schedules_path = storage::app_config_file(app.handle(), schedules::SCHEDULES_FILE)?;
+            app.manage(ScheduleStore::load(schedules_path)?);
+
+            let idle_path = storage::app_config_file(app.handle(), idle::IDLE_FILE)?;
+            app.manage(IdleStore::load(idle_path)?);
+
+            let hotkeys_path = storage::app_config_file(app.handle(), hotkeys::HOTKEYS_FILE)?;
+            app.manage(HotkeyStore::load(hotkeys_path)?);
+
+            let last_path = storage::app_data_subdir(app.handle(), "state")?
+                .join(last_applied::LAST_APPLIED_FILE);
+            app.manage(LastApplied::load(last_path)?);
+
+            let tray_path = storage::app_config_file(app.handle(), tray::TRAY_FILE)?;
+            app.manage(TrayState::load(tray_path)?);
+            tray::init(app.handle())?;
+            if std::env::args().any(|arg| arg == tray::HIDDEN_ARG) {
+                if let Some(window) = app.get_webview_window("main") {
+                    window.hide()?;
+                }
+            }
+
+            services::battery::spawn(app.handle().clone());
+            services::scheduler::spawn(app.handle().clone(), Arc::new(schedules::SystemClock));
+            services::idle::spawn(app.handle().clone());
+            services::hotplug::spawn(app.handle().clone());
+            services::inspector::install(app.handle().clone());
+            let hotkey_settings = app.state::<HotkeyStore>().get();
+            if let Err(e) = services::hotkeys::register(app.handle(), &hotkey_settings) {
+                warn!("{}", e);
+            }
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            // Closing the window keeps the app running in the tray
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                api.prevent_close();
+                if let Err(e) = window.hide() {
+                    warn!("Failed to hide window: {}", e);
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             akko_handshake,
+            akko_get_firmware_version,
+            akko_get_device_info,
             detect_akko_devices,
             akko_probe_opcode,
             akko_probe_range,
@@ -204,6 +963,55 @@ pub fn run() {
             akko_set_rgb_with_mode,
             akko_set_rgb_with_mode,
             akko_send_raw,
+            akko_get_fn_lock,
+            akko_set_fn_lock,
+            akko_get_indicator_led,
+            akko_set_indicator_led,
+            akko_get_sleep_settings,
+            akko_set_sleep_settings,
+            akko_get_battery_status,
+            akko_request_reset,
+            akko_confirm_reset,
+            akko_read_state,
+            akko_export_config,
+            akko_preview_restore,
+            akko_restore_config,
+            akko_parse_cloud_export,
+            akko_import_cloud_profiles,
+            akko_import_capture,
+            list_presets,
+            save_preset,
+            rename_preset,
+            delete_preset,
+            akko_apply_preset,
+            import_presets,
+            export_presets,
+            akko_set_brightness,
+            list_schedules,
+            save_schedule,
+            delete_schedule,
+            get_idle_settings,
+            set_idle_settings,
+            akko_set_active_profile,
+            akko_reapply_last_state,
+            get_auto_switch,
+            set_auto_switch,
+            get_hotkeys,
+            set_hotkeys,
+            get_inspector_settings,
+            set_inspector_settings,
+            get_inspector_packets,
+            clear_inspector,
+            akko_diagnose,
+            get_udev_status,
+            install_udev_rule,
+            get_hid_transport,
+            set_hid_transport,
+            get_transfer_policy,
+            set_transfer_policy,
+            get_log_level,
+            set_log_level,
+            collect_diagnostics,
             get_active_app
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,289 @@
+//! Application logging
+//! Records go to stderr and, once the app data directory is known, to
+//! size-rotated files (`keyboard.log`, `keyboard.1.log`, ...). The level can be
+//! changed at runtime; other crates stay capped at Info so Debug/Trace only
+//! adds our own output.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Local};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Sub-directory of the app data directory
+pub const LOG_DIR: &str = "logs";
+/// Current log file; rotated ones are `keyboard.1.log` (newest) and up
+pub const LOG_FILE_STEM: &str = "keyboard";
+/// Rotate once the current file would exceed this size
+pub const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024;
+/// Files kept, including the current one
+pub const KEEP_FILES: usize = 5;
+
+/// Level when RUST_LOG is unset or not a plain level
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+const CRATE_TARGET: &str = env!("CARGO_CRATE_NAME");
+
+/// `keyboard.log` for index 0, `keyboard.<index>.log` otherwise
+pub fn file_path(dir: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        dir.join(format!("{}.log", LOG_FILE_STEM))
+    } else {
+        dir.join(format!("{}.{}.log", LOG_FILE_STEM, index))
+    }
+}
+
+/// Log file that moves to `.1`, `.2`, ... when it grows too large
+pub struct RotatingFile {
+    dir: PathBuf,
+    max_size: u64,
+    keep: usize,
+    file: Option<File>,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn open(dir: &Path, max_size: u64, keep: usize) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = Self::open_current(dir)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            max_size,
+            keep: keep.max(1),
+            file: Some(file),
+            size,
+        })
+    }
+
+    fn open_current(dir: &Path) -> io::Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_path(dir, 0))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_size {
+            self.rotate()?;
+        }
+
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => self.file.insert(Self::open_current(&self.dir)?),
+        };
+        writeln!(file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Shift every file up one index, dropping the oldest
+    fn rotate(&mut self) -> io::Result<()> {
+        // Closed first: Windows cannot rename a file that is still open
+        self.file = None;
+
+        let oldest = file_path(&self.dir, self.keep - 1);
+        if oldest.exists() {
+            fs::remove_file(oldest)?;
+        }
+        for index in (0..self.keep - 1).rev() {
+            let from = file_path(&self.dir, index);
+            if from.exists() {
+                fs::rename(&from, file_path(&self.dir, index + 1))?;
+            }
+        }
+
+        self.file = Some(Self::open_current(&self.dir)?);
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// Existing log files in `dir`, newest first
+pub fn log_files(dir: &Path) -> Vec<PathBuf> {
+    (0..KEEP_FILES)
+        .map(|index| file_path(dir, index))
+        .filter(|path| path.exists())
+        .collect()
+}
+
+/// "2024-05-01 12:00:00.123 INFO  keyboard_lib::tray: message"
+pub fn format_record(record: &Record, time: DateTime<Local>) -> String {
+    format!(
+        "{} {:<5} {}: {}",
+        time.format("%Y-%m-%d %H:%M:%S%.3f"),
+        record.level(),
+        record.target(),
+        record.args()
+    )
+}
+
+/// Our own targets pass at any level, others only up to Info
+fn is_enabled(metadata: &Metadata, max: LevelFilter) -> bool {
+    let target = metadata.target();
+    let ours = target == CRATE_TARGET
+        || target
+            .strip_prefix(CRATE_TARGET)
+            .is_some_and(|rest| rest.starts_with("::"));
+
+    metadata.level() <= max && (ours || metadata.level() <= Level::Info)
+}
+
+struct AppLogger {
+    file: Mutex<Option<RotatingFile>>,
+}
+
+impl AppLogger {
+    fn with_file<T>(&self, f: impl FnOnce(&mut Option<RotatingFile>) -> T) -> T {
+        f(&mut self.file.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl Log for AppLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        is_enabled(metadata, log::max_level())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format_record(record, Local::now());
+        eprintln!("{}", line);
+        self.with_file(|file| {
+            if let Some(file) = file {
+                // Nowhere to report a failing log file but stderr
+                if let Err(e) = file.write_line(&line) {
+                    eprintln!("Failed to write log file: {}", e);
+                }
+            }
+        });
+    }
+
+    fn flush(&self) {
+        self.with_file(|file| {
+            if let Some(file) = file {
+                let _ = file.flush();
+            }
+        });
+    }
+}
+
+static LOGGER: AppLogger = AppLogger {
+    file: Mutex::new(None),
+};
+
+/// Parse "off", "error", ..., "trace" (case-insensitive)
+pub fn parse_level(level: &str) -> Option<LevelFilter> {
+    level.trim().parse().ok()
+}
+
+/// Install the logger (stderr only until `attach_dir`)
+/// RUST_LOG sets the initial level if it is a plain level name
+pub fn init() {
+    let level = std::env::var("RUST_LOG")
+        .ok()
+        .and_then(|value| parse_level(&value))
+        .unwrap_or(DEFAULT_LEVEL);
+
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+/// Start writing rotated log files in `dir`
+pub fn attach_dir(dir: &Path) -> Result<(), String> {
+    let file = RotatingFile::open(dir, MAX_FILE_SIZE, KEEP_FILES)
+        .map_err(|e| format!("Failed to open log file in {}: {}", dir.display(), e))?;
+    LOGGER.with_file(|current| *current = Some(file));
+    Ok(())
+}
+
+/// Directory log files are written to, once attached
+pub fn log_dir() -> Option<PathBuf> {
+    LOGGER.with_file(|file| file.as_ref().map(|f| f.dir().to_path_buf()))
+}
+
+pub fn level() -> LevelFilter {
+    log::max_level()
+}
+
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+/// Write buffered lines to disk (before bundling the files)
+pub fn flush() {
+    LOGGER.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("keyboard-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_rotation_keeps_newest_files() {
+        let dir = temp_dir("log-rotation");
+        let mut file = RotatingFile::open(&dir, 20, 3).unwrap();
+
+        // 10 bytes per line (with newline): two lines per file
+        for n in 0..8 {
+            file.write_line(&format!("line {:04}", n)).unwrap();
+        }
+        file.flush().unwrap();
+
+        assert_eq!(log_files(&dir).len(), 3);
+        let read = |index| fs::read_to_string(file_path(&dir, index)).unwrap();
+        assert_eq!(read(0), "line 0006\nline 0007\n");
+        assert_eq!(read(1), "line 0004\nline 0005\n");
+        assert_eq!(read(2), "line 0002\nline 0003\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_levels_and_dependency_cap() {
+        assert_eq!(parse_level(" Debug "), Some(LevelFilter::Debug));
+        assert_eq!(parse_level("off"), Some(LevelFilter::Off));
+        assert_eq!(parse_level("keyboard_lib=debug"), None);
+
+        let ours = format!("{}::devices::akko::hid", CRATE_TARGET);
+        let meta = |level, target| Metadata::builder().level(level).target(target).build();
+
+        assert!(is_enabled(&meta(Level::Debug, &ours), LevelFilter::Debug));
+        assert!(!is_enabled(
+            &meta(Level::Debug, "tao::platform"),
+            LevelFilter::Debug
+        ));
+        assert!(is_enabled(
+            &meta(Level::Info, "tao::platform"),
+            LevelFilter::Debug
+        ));
+        assert!(!is_enabled(&meta(Level::Info, &ours), LevelFilter::Warn));
+        assert!(!is_enabled(
+            &meta(Level::Debug, &format!("{}_extra", CRATE_TARGET)),
+            LevelFilter::Trace
+        ));
+    }
+}
@@ -0,0 +1,308 @@
+//! Lighting presets library
+//! Named lighting settings persisted in the app config directory
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::devices::akko::protocol::{rgb_mode_byte, RgbSettings};
+use crate::devices::akko::ColorMode;
+use crate::storage;
+
+/// File name in the app config directory
+pub const PRESETS_FILE: &str = "presets.json";
+
+/// Bump when the pack layout changes incompatibly
+pub const PRESET_PACK_VERSION: u32 = 1;
+
+const MAX_NAME_LEN: usize = 64;
+
+/// A named lighting configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LightingPreset {
+    pub name: String,
+    /// Effect ID (byte 1 of SetRgbSettings)
+    pub effect: u8,
+    /// Direction / sub-mode 0-3 (Right, Left, Down, Up)
+    pub direction: u8,
+    pub color_mode: ColorMode,
+    pub color: (u8, u8, u8),
+    /// UI speed 0-4
+    pub speed: u8,
+    /// Brightness 0-4 (0 = off)
+    pub brightness: u8,
+}
+
+impl LightingPreset {
+    /// Validate values before storing or sending
+    pub fn validate(&self) -> Result<(), String> {
+        let name = self.name.trim();
+        if name.is_empty() || name.len() > MAX_NAME_LEN {
+            return Err(format!("Preset name must be 1-{} characters", MAX_NAME_LEN));
+        }
+        if self.direction > 3 {
+            return Err(format!("Direction must be 0-3, got {}", self.direction));
+        }
+        if self.speed > 4 {
+            return Err(format!("Speed must be 0-4, got {}", self.speed));
+        }
+        if self.brightness > 4 {
+            return Err(format!("Brightness must be 0-4, got {}", self.brightness));
+        }
+        Ok(())
+    }
+
+    /// Mode byte for SetRgbSettings
+    pub fn mode(&self) -> u8 {
+        rgb_mode_byte(self.color_mode, self.direction)
+    }
+
+    /// Protocol lighting settings for this preset
+    pub fn to_rgb_settings(&self) -> RgbSettings {
+        RgbSettings {
+            direction: self.effect,
+            speed: self.speed,
+            brightness: self.brightness,
+            mode: self.mode(),
+            color: self.color,
+        }
+    }
+}
+
+/// Import/export file format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresetPack {
+    pub format_version: u32,
+    pub presets: Vec<LightingPreset>,
+}
+
+/// In-memory preset list (names are unique, case-insensitive)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PresetLibrary {
+    presets: Vec<LightingPreset>,
+}
+
+impl PresetLibrary {
+    pub fn list(&self) -> &[LightingPreset] {
+        &self.presets
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LightingPreset> {
+        self.presets
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Create or replace a preset by name
+    pub fn upsert(&mut self, mut preset: LightingPreset) -> Result<(), String> {
+        preset.validate()?;
+        preset.name = preset.name.trim().to_string();
+
+        match self
+            .presets
+            .iter_mut()
+            .find(|p| p.name.eq_ignore_ascii_case(&preset.name))
+        {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+        Ok(())
+    }
+
+    /// Rename a preset; fails if the new name is taken
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<(), String> {
+        if !from.trim().eq_ignore_ascii_case(to.trim()) && self.get(to).is_some() {
+            return Err(format!("A preset named \"{}\" already exists", to.trim()));
+        }
+
+        let mut preset = self
+            .get(from)
+            .cloned()
+            .ok_or_else(|| format!("Preset \"{}\" not found", from))?;
+        preset.name = to.to_string();
+        preset.validate()?;
+
+        self.delete(from)?;
+        self.upsert(preset)
+    }
+
+    pub fn delete(&mut self, name: &str) -> Result<(), String> {
+        let before = self.presets.len();
+        self.presets
+            .retain(|p| !p.name.eq_ignore_ascii_case(name.trim()));
+
+        if self.presets.len() == before {
+            return Err(format!("Preset \"{}\" not found", name));
+        }
+        Ok(())
+    }
+
+    /// Merge a pack; existing names are kept unless `overwrite` is set
+    /// Returns the names that were added or replaced
+    pub fn merge(&mut self, pack: PresetPack, overwrite: bool) -> Result<Vec<String>, String> {
+        if pack.format_version > PRESET_PACK_VERSION {
+            return Err(format!(
+                "Preset pack v{} is newer than supported v{}",
+                pack.format_version, PRESET_PACK_VERSION
+            ));
+        }
+
+        // Validate everything first so a bad pack changes nothing
+        for preset in &pack.presets {
+            preset
+                .validate()
+                .map_err(|e| format!("Preset \"{}\": {}", preset.name, e))?;
+        }
+
+        let mut imported = Vec::new();
+        for preset in pack.presets {
+            if !overwrite && self.get(&preset.name).is_some() {
+                continue;
+            }
+            imported.push(preset.name.trim().to_string());
+            self.upsert(preset)?;
+        }
+        Ok(imported)
+    }
+
+    /// Build a pack from selected presets (all when `names` is empty)
+    pub fn export(&self, names: &[String]) -> Result<PresetPack, String> {
+        let presets = if names.is_empty() {
+            self.presets.clone()
+        } else {
+            names
+                .iter()
+                .map(|name| {
+                    self.get(name)
+                        .cloned()
+                        .ok_or_else(|| format!("Preset \"{}\" not found", name))
+                })
+                .collect::<Result<_, _>>()?
+        };
+
+        Ok(PresetPack {
+            format_version: PRESET_PACK_VERSION,
+            presets,
+        })
+    }
+}
+
+/// Persisted preset library (Tauri managed state)
+pub struct PresetStore {
+    path: PathBuf,
+    library: Mutex<PresetLibrary>,
+}
+
+impl PresetStore {
+    /// Load from disk (empty library if the file does not exist yet)
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let library = storage::read_json(&path)?.unwrap_or_default();
+        Ok(Self {
+            path,
+            library: Mutex::new(library),
+        })
+    }
+
+    pub fn list(&self) -> Vec<LightingPreset> {
+        self.lock().list().to_vec()
+    }
+
+    pub fn get(&self, name: &str) -> Result<LightingPreset, String> {
+        self.lock()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Preset \"{}\" not found", name))
+    }
+
+    /// Build a pack from selected presets (all when `names` is empty)
+    pub fn export(&self, names: &[String]) -> Result<PresetPack, String> {
+        self.lock().export(names)
+    }
+
+    /// Run a mutation and persist on success
+    pub fn update<T>(
+        &self,
+        f: impl FnOnce(&mut PresetLibrary) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let mut library = self.lock();
+        let mut updated = library.clone();
+
+        let result = f(&mut updated)?;
+        storage::write_json(&self.path, &updated)?;
+
+        *library = updated;
+        Ok(result)
+    }
+
+    /// Read a pack file
+    pub fn read_pack(path: &Path) -> Result<PresetPack, String> {
+        storage::read_json(path)?.ok_or_else(|| format!("File not found: {}", path.display()))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PresetLibrary> {
+        self.library.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset(name: &str, brightness: u8) -> LightingPreset {
+        LightingPreset {
+            name: name.to_string(),
+            effect: 4,
+            direction: 1,
+            color_mode: ColorMode::Color,
+            color: (255, 0, 0),
+            speed: 2,
+            brightness,
+        }
+    }
+
+    #[test]
+    fn test_crud() {
+        let mut library = PresetLibrary::default();
+        library.upsert(preset("Night", 1)).unwrap();
+        library.upsert(preset("night", 2)).unwrap();
+
+        assert_eq!(library.list().len(), 1);
+        assert_eq!(library.get("NIGHT").unwrap().brightness, 2);
+        assert_eq!(library.get("Night").unwrap().mode(), 0x18);
+
+        library.upsert(preset("Day", 4)).unwrap();
+        assert!(library.rename("Day", "Night").is_err());
+        library.rename("Day", "Work").unwrap();
+        assert!(library.get("Work").is_some());
+
+        library.delete("work").unwrap();
+        assert!(library.delete("work").is_err());
+        assert!(library.upsert(preset("Bright", 9)).is_err());
+    }
+
+    #[test]
+    fn test_merge_pack() {
+        let mut library = PresetLibrary::default();
+        library.upsert(preset("Night", 1)).unwrap();
+
+        let pack = PresetPack {
+            format_version: PRESET_PACK_VERSION,
+            presets: vec![preset("Night", 3), preset("Party", 4)],
+        };
+
+        let imported = library.merge(pack.clone(), false).unwrap();
+        assert_eq!(imported, vec!["Party".to_string()]);
+        assert_eq!(library.get("Night").unwrap().brightness, 1);
+
+        library.merge(pack, true).unwrap();
+        assert_eq!(library.get("Night").unwrap().brightness, 3);
+
+        let bad = PresetPack {
+            format_version: PRESET_PACK_VERSION,
+            presets: vec![preset("Ok", 1), preset("Bad", 7)],
+        };
+        assert!(library.merge(bad, true).is_err());
+        assert!(library.get("Ok").is_none());
+    }
+}
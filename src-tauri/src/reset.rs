@@ -0,0 +1,189 @@
+//! Confirmation tokens for destructive reset commands
+//!
+//! Flow: akko_request_reset issues a short-lived, single-use token bound to
+//! (model, scope) for scopes that can be reset; akko_confirm_reset checks it,
+//! backs up, redeems it once the backup can undo the reset, then resets.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::devices::akko::{AkkoModel, CommandResult, ResetScope};
+
+/// How long a token stays valid
+pub const TOKEN_TTL: Duration = Duration::from_secs(60);
+
+/// Returned by akko_request_reset, shown to the user for confirmation
+#[derive(Debug, Clone, Serialize)]
+pub struct ResetRequest {
+    pub token: String,
+    pub description: String,
+    pub expires_in_secs: u64,
+}
+
+/// Returned by akko_confirm_reset
+#[derive(Debug, Clone, Serialize)]
+pub struct ResetOutcome {
+    /// Backup written before the reset was sent
+    pub backup_path: String,
+    pub result: CommandResult,
+}
+
+struct PendingReset {
+    model: AkkoModel,
+    scope: ResetScope,
+    issued: Instant,
+}
+
+/// Pending reset confirmations (Tauri managed state)
+#[derive(Default)]
+pub struct ResetTokens {
+    pending: Mutex<HashMap<String, PendingReset>>,
+}
+
+impl ResetTokens {
+    /// Issue a new token for (model, scope)
+    pub fn issue(&self, model: AkkoModel, scope: ResetScope) -> String {
+        self.issue_at(model, scope, Instant::now())
+    }
+
+    /// Check a token without using it up
+    pub fn check(&self, token: &str, model: AkkoModel, scope: ResetScope) -> Result<(), String> {
+        self.check_at(token, model, scope, Instant::now())
+    }
+
+    /// Redeem a token; must match the model and scope it was issued for
+    pub fn redeem(&self, token: &str, model: AkkoModel, scope: ResetScope) -> Result<(), String> {
+        self.redeem_at(token, model, scope, Instant::now())
+    }
+
+    fn issue_at(&self, model: AkkoModel, scope: ResetScope, now: Instant) -> String {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.retain(|_, p| now.duration_since(p.issued) < TOKEN_TTL);
+
+        let token = new_token();
+        pending.insert(
+            token.clone(),
+            PendingReset {
+                model,
+                scope,
+                issued: now,
+            },
+        );
+        token
+    }
+
+    fn check_at(
+        &self,
+        token: &str,
+        model: AkkoModel,
+        scope: ResetScope,
+        now: Instant,
+    ) -> Result<(), String> {
+        let pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = pending.get(token).ok_or_else(invalid_token)?;
+        entry.matches(model, scope, now)
+    }
+
+    fn redeem_at(
+        &self,
+        token: &str,
+        model: AkkoModel,
+        scope: ResetScope,
+        now: Instant,
+    ) -> Result<(), String> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Single use: removed even when it does not match
+        let entry = pending.remove(token).ok_or_else(invalid_token)?;
+        entry.matches(model, scope, now)
+    }
+}
+
+fn invalid_token() -> String {
+    "Invalid or already used confirmation token".to_string()
+}
+
+impl PendingReset {
+    /// Still valid and issued for (model, scope)
+    fn matches(&self, model: AkkoModel, scope: ResetScope, now: Instant) -> Result<(), String> {
+        if now.duration_since(self.issued) >= TOKEN_TTL {
+            return Err("Confirmation token expired, request the reset again".to_string());
+        }
+        if self.model != model || self.scope != scope {
+            return Err(format!(
+                "Confirmation token was issued for {} on {}",
+                self.scope.describe(),
+                self.model.name()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Random token like "RESET-1A2B3C4D"
+fn new_token() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0),
+    );
+    format!("RESET-{:08X}", hasher.finish() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCOPE: ResetScope = ResetScope::Lighting { profile: 1 };
+
+    #[test]
+    fn test_token_single_use() {
+        let tokens = ResetTokens::default();
+        let token = tokens.issue(AkkoModel::Mod007b, SCOPE);
+
+        assert!(tokens.redeem(&token, AkkoModel::Mod007b, SCOPE).is_ok());
+        assert!(tokens.redeem(&token, AkkoModel::Mod007b, SCOPE).is_err());
+    }
+
+    #[test]
+    fn test_check_keeps_token() {
+        let tokens = ResetTokens::default();
+        let token = tokens.issue(AkkoModel::Mod007b, SCOPE);
+
+        assert!(tokens.check(&token, AkkoModel::Mod007b, SCOPE).is_ok());
+        assert!(tokens
+            .check(&token, AkkoModel::Mod007b, ResetScope::Factory)
+            .is_err());
+        assert!(tokens.redeem(&token, AkkoModel::Mod007b, SCOPE).is_ok());
+        assert!(tokens.check(&token, AkkoModel::Mod007b, SCOPE).is_err());
+    }
+
+    #[test]
+    fn test_token_bound_to_request() {
+        let tokens = ResetTokens::default();
+        let token = tokens.issue(AkkoModel::Mod007b, SCOPE);
+
+        assert!(tokens
+            .redeem(&token, AkkoModel::Mod007b, ResetScope::Factory)
+            .is_err());
+    }
+
+    #[test]
+    fn test_token_expires() {
+        let tokens = ResetTokens::default();
+        let issued = Instant::now();
+        let token = tokens.issue_at(AkkoModel::Mod007b, SCOPE, issued);
+
+        let later = issued + TOKEN_TTL + Duration::from_secs(1);
+        assert!(tokens
+            .redeem_at(&token, AkkoModel::Mod007b, SCOPE, later)
+            .is_err());
+    }
+}
@@ -0,0 +1,389 @@
+//! Time-of-day lighting schedules
+//! Pure evaluation (with an injectable clock) plus the persisted schedule list
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use chrono::{Datelike, Local, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::devices::akko::protocol::RgbSettings;
+use crate::devices::akko::AkkoModel;
+use crate::storage;
+
+/// File name in the app config directory
+pub const SCHEDULES_FILE: &str = "schedules.json";
+
+/// Source of local wall-clock time (replaced in tests)
+pub trait Clock: Send + Sync {
+    fn now(&self) -> NaiveDateTime;
+}
+
+/// Local system time
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+}
+
+/// What a schedule does while active
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleAction {
+    /// Apply a lighting preset by name
+    Preset { name: String },
+    /// Change brightness only (0 = LEDs off)
+    Brightness { level: u8 },
+}
+
+/// A recurring time window, e.g. weekdays 22:00-07:00
+/// Windows with end <= start wrap past midnight (and belong to the start day)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schedule {
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Days the window starts on; empty = every day
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    /// Only for this model; None = all connected keyboards
+    #[serde(default)]
+    pub model: Option<AkkoModel>,
+    pub action: ScheduleAction,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Schedule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Schedule name must not be empty".to_string());
+        }
+        if self.start == self.end {
+            return Err("Schedule start and end must differ".to_string());
+        }
+        match &self.action {
+            ScheduleAction::Brightness { level } if *level > 4 => {
+                Err(format!("Brightness must be 0-4, got {}", level))
+            }
+            ScheduleAction::Preset { name } if name.trim().is_empty() => {
+                Err("Preset name must not be empty".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the window covers `now`
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        let time = now.time();
+        let today = now.weekday();
+
+        if self.start < self.end {
+            self.runs_on(today) && time >= self.start && time < self.end
+        } else {
+            // Wraps midnight: late part belongs to today, early part to yesterday
+            (self.runs_on(today) && time >= self.start)
+                || (self.runs_on(today.pred()) && time < self.end)
+        }
+    }
+
+    fn runs_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    fn applies_to(&self, model: AkkoModel) -> bool {
+        self.model.is_none_or(|m| m == model)
+    }
+}
+
+/// What the scheduler wants done to a keyboard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleStep {
+    /// A window is active: apply its action (snapshot the lighting first
+    /// unless a snapshot is already held)
+    Apply(ScheduleAction),
+    /// No window is active any more: write the snapshot back
+    Restore,
+}
+
+/// Decides which actions to send; remembers what each keyboard last got
+pub struct Scheduler {
+    clock: Arc<dyn Clock>,
+    /// Action last applied per connected keyboard (None = no schedule active)
+    applied: HashMap<AkkoModel, Option<ScheduleAction>>,
+    /// Lighting from before the first window started, until restored
+    snapshots: HashMap<AkkoModel, RgbSettings>,
+}
+
+impl Scheduler {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            applied: HashMap::new(),
+            snapshots: HashMap::new(),
+        }
+    }
+
+    /// Evaluate schedules for the connected keyboards
+    ///
+    /// Returns steps that must be run now: Apply when the active schedule
+    /// changes or a keyboard (re)appears while one is active, Restore when
+    /// no schedule is active and a snapshot is still held. The last
+    /// matching schedule in the list wins.
+    pub fn tick(
+        &mut self,
+        schedules: &[Schedule],
+        connected: &[AkkoModel],
+    ) -> Vec<(AkkoModel, ScheduleStep)> {
+        let now = self.clock.now();
+
+        // Forget unplugged keyboards so they get reapplied on reconnect
+        // (snapshots are kept: the keyboard may still show the schedule)
+        self.applied.retain(|model, _| connected.contains(model));
+
+        let mut due = Vec::new();
+        for &model in connected {
+            let wanted = schedules
+                .iter()
+                .rev()
+                .find(|s| s.applies_to(model) && s.is_active(now))
+                .map(|s| s.action.clone());
+
+            if self.applied.get(&model) == Some(&wanted) {
+                continue;
+            }
+
+            match &wanted {
+                Some(action) => due.push((model, ScheduleStep::Apply(action.clone()))),
+                None if self.snapshots.contains_key(&model) => {
+                    due.push((model, ScheduleStep::Restore))
+                }
+                None => {}
+            }
+            self.applied.insert(model, wanted);
+        }
+        due
+    }
+
+    /// Mark a step as failed so the next tick retries it
+    pub fn retry(&mut self, model: AkkoModel) {
+        self.applied.remove(&model);
+    }
+
+    /// Lighting to restore when the windows end
+    pub fn snapshot(&self, model: AkkoModel) -> Option<RgbSettings> {
+        self.snapshots.get(&model).copied()
+    }
+
+    /// Remember the lighting from before the first window (kept if one is held)
+    pub fn save_snapshot(&mut self, model: AkkoModel, lighting: RgbSettings) {
+        self.snapshots.entry(model).or_insert(lighting);
+    }
+
+    /// The snapshot was written back
+    pub fn restored(&mut self, model: AkkoModel) {
+        self.snapshots.remove(&model);
+    }
+}
+
+/// Persisted schedules (Tauri managed state)
+pub struct ScheduleStore {
+    path: PathBuf,
+    schedules: Mutex<Vec<Schedule>>,
+}
+
+impl ScheduleStore {
+    /// Load from disk (empty if the file does not exist yet)
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let schedules = storage::read_json(&path)?.unwrap_or_default();
+        Ok(Self {
+            path,
+            schedules: Mutex::new(schedules),
+        })
+    }
+
+    pub fn list(&self) -> Vec<Schedule> {
+        self.lock().clone()
+    }
+
+    /// Create or replace a schedule by name
+    pub fn save(&self, schedule: Schedule) -> Result<(), String> {
+        schedule.validate()?;
+        self.update(|schedules| {
+            match schedules
+                .iter_mut()
+                .find(|s| s.name.eq_ignore_ascii_case(&schedule.name))
+            {
+                Some(existing) => *existing = schedule,
+                None => schedules.push(schedule),
+            }
+            Ok(())
+        })
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), String> {
+        self.update(|schedules| {
+            let before = schedules.len();
+            schedules.retain(|s| !s.name.eq_ignore_ascii_case(name));
+            if schedules.len() == before {
+                return Err(format!("Schedule \"{}\" not found", name));
+            }
+            Ok(())
+        })
+    }
+
+    fn update(
+        &self,
+        f: impl FnOnce(&mut Vec<Schedule>) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut schedules = self.lock();
+        let mut updated = schedules.clone();
+
+        f(&mut updated)?;
+        storage::write_json(&self.path, &updated)?;
+
+        *schedules = updated;
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Schedule>> {
+        self.schedules.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    struct FixedClock(Mutex<NaiveDateTime>);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> NaiveDateTime {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    impl FixedClock {
+        fn set(&self, time: NaiveDateTime) {
+            *self.0.lock().unwrap() = time;
+        }
+    }
+
+    /// 2026-10-16 is a Friday
+    fn at(day: u32, hour: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, min, 0)
+            .unwrap()
+    }
+
+    fn time(hour: u32, min: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, min, 0).unwrap()
+    }
+
+    fn dim_at_night() -> Schedule {
+        Schedule {
+            name: "Night".to_string(),
+            enabled: true,
+            days: vec![],
+            start: time(22, 0),
+            end: time(7, 0),
+            model: None,
+            action: ScheduleAction::Brightness { level: 1 },
+        }
+    }
+
+    #[test]
+    fn test_window_wraps_midnight() {
+        let night = dim_at_night();
+        assert!(night.is_active(at(16, 23, 0)));
+        assert!(night.is_active(at(17, 6, 59)));
+        assert!(!night.is_active(at(17, 7, 0)));
+        assert!(!night.is_active(at(16, 21, 59)));
+    }
+
+    #[test]
+    fn test_days_filter() {
+        let friday_party = Schedule {
+            name: "Friday".to_string(),
+            days: vec![Weekday::Fri],
+            start: time(14, 0),
+            end: time(18, 0),
+            action: ScheduleAction::Preset {
+                name: "Spectrum".to_string(),
+            },
+            ..dim_at_night()
+        };
+        assert!(friday_party.is_active(at(16, 15, 0)));
+        assert!(!friday_party.is_active(at(15, 15, 0)));
+    }
+
+    #[test]
+    fn test_scheduler_edges_and_reconnect() {
+        let clock = Arc::new(FixedClock(Mutex::new(at(16, 21, 0))));
+        let mut scheduler = Scheduler::new(clock.clone());
+        let schedules = [dim_at_night()];
+        let kb = [AkkoModel::Mod007b];
+
+        assert!(scheduler.tick(&schedules, &kb).is_empty());
+
+        clock.set(at(16, 22, 0));
+        assert_eq!(scheduler.tick(&schedules, &kb).len(), 1);
+        // Already applied: nothing to do
+        assert!(scheduler.tick(&schedules, &kb).is_empty());
+
+        // Unplugged, then reconnected: reapply
+        assert!(scheduler.tick(&schedules, &[]).is_empty());
+        assert_eq!(scheduler.tick(&schedules, &kb).len(), 1);
+    }
+
+    #[test]
+    fn test_window_end_restores_snapshot() {
+        let clock = Arc::new(FixedClock(Mutex::new(at(16, 22, 0))));
+        let mut scheduler = Scheduler::new(clock.clone());
+        let schedules = [dim_at_night()];
+        let kb = [AkkoModel::Mod007b];
+        let before = RgbSettings {
+            direction: 4,
+            speed: 2,
+            brightness: 4,
+            mode: 0x08,
+            color: (255, 0, 0),
+        };
+
+        let due = scheduler.tick(&schedules, &kb);
+        assert_eq!(
+            due,
+            [(
+                AkkoModel::Mod007b,
+                ScheduleStep::Apply(ScheduleAction::Brightness { level: 1 })
+            )]
+        );
+        scheduler.save_snapshot(AkkoModel::Mod007b, before);
+
+        // Past the end of the window: put the old lighting back
+        clock.set(at(17, 7, 0));
+        let due = scheduler.tick(&schedules, &kb);
+        assert_eq!(due, [(AkkoModel::Mod007b, ScheduleStep::Restore)]);
+        assert_eq!(scheduler.snapshot(AkkoModel::Mod007b), Some(before));
+
+        // The restore failed: it is retried on the next tick
+        scheduler.retry(AkkoModel::Mod007b);
+        assert_eq!(scheduler.tick(&schedules, &kb).len(), 1);
+        scheduler.restored(AkkoModel::Mod007b);
+        scheduler.retry(AkkoModel::Mod007b);
+        assert!(scheduler.tick(&schedules, &kb).is_empty());
+    }
+}
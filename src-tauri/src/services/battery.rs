@@ -0,0 +1,90 @@
+//! Background battery monitor for wireless Akko keyboards
+//! Polls GetBatteryStatus, emits events to the frontend and updates the tray
+
+use std::collections::HashSet;
+use std::thread;
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::devices::akko::{self, AkkoModel, BatteryStatus};
+use crate::tray;
+
+/// Emitted on every successful battery read
+pub const BATTERY_EVENT: &str = "akko-battery";
+
+/// Emitted once when a keyboard drops to LOW_BATTERY_PERCENT or below
+pub const BATTERY_LOW_EVENT: &str = "akko-battery-low";
+
+/// Warn at or below this level
+pub const LOW_BATTERY_PERCENT: u8 = 20;
+
+/// Re-arm the warning once the level recovers above LOW + hysteresis
+const LOW_BATTERY_HYSTERESIS: u8 = 5;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Event payload for battery events
+#[derive(Debug, Clone, Serialize)]
+pub struct BatteryEvent {
+    pub model: String,
+    pub status: BatteryStatus,
+}
+
+/// Start the monitor thread
+pub fn spawn(app: AppHandle) {
+    thread::spawn(move || {
+        info!("Battery monitor started");
+
+        // Models we already warned about (until charged)
+        let mut warned: HashSet<AkkoModel> = HashSet::new();
+
+        loop {
+            poll_once(&app, &mut warned);
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+fn poll_once(app: &AppHandle, warned: &mut HashSet<AkkoModel>) {
+    let models = akko::detector::detect_akko_devices();
+
+    for model in models.into_iter().filter(|m| m.has_battery()) {
+        let status = match akko::api::akko_get_battery_status(model) {
+            Ok(status) => status,
+            Err(e) => {
+                debug!("Battery read failed for {}: {}", model.name(), e);
+                continue;
+            }
+        };
+
+        let payload = BatteryEvent {
+            model: model.name().to_string(),
+            status,
+        };
+
+        if let Err(e) = app.emit(BATTERY_EVENT, &payload) {
+            warn!("Failed to emit {}: {}", BATTERY_EVENT, e);
+        }
+
+        if is_low(&status) {
+            if warned.insert(model) {
+                info!("Low battery on {}: {}%", model.name(), status.percent);
+                if let Err(e) = app.emit(BATTERY_LOW_EVENT, &payload) {
+                    warn!("Failed to emit {}: {}", BATTERY_LOW_EVENT, e);
+                }
+            }
+        } else if status.charging || status.percent > LOW_BATTERY_PERCENT + LOW_BATTERY_HYSTERESIS {
+            warned.remove(&model);
+        }
+
+        tray::set_battery(app, model, status);
+    }
+}
+
+/// Low = at or below threshold, on battery power
+fn is_low(status: &BatteryStatus) -> bool {
+    !status.charging && status.percent <= LOW_BATTERY_PERCENT
+}
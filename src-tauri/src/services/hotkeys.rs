@@ -0,0 +1,104 @@
+//! Global hotkeys
+//! Registers the saved bindings with the OS and runs their actions; works
+//! while the window is hidden
+
+use std::thread;
+
+use log::{info, warn};
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::actions;
+use crate::devices::akko::{self, AkkoModel};
+use crate::hotkeys::{HotkeyAction, HotkeyBinding, HotkeySettings, HotkeyStore};
+
+/// Global shortcut plugin with our key handler
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, shortcut, event| {
+            if event.state() == ShortcutState::Pressed {
+                on_shortcut(app, shortcut);
+            }
+        })
+        .build()
+}
+
+/// Replace all registered shortcuts with `settings`
+/// Errors name the accelerators the OS refused (usually taken by another app)
+pub fn register(app: &AppHandle, settings: &HotkeySettings) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    shortcuts
+        .unregister_all()
+        .map_err(|e| format!("Failed to clear hotkeys: {}", e))?;
+
+    let mut failed = Vec::new();
+    for binding in &settings.bindings {
+        let result = parse(binding).and_then(|shortcut| {
+            shortcuts
+                .register(shortcut)
+                .map_err(|e| format!("{}: {}", binding.accelerator, e))
+        });
+        if let Err(e) = result {
+            failed.push(e);
+        }
+    }
+
+    if failed.is_empty() {
+        info!("Registered {} hotkeys", settings.bindings.len());
+        Ok(())
+    } else {
+        Err(format!("Could not register hotkeys: {}", failed.join("; ")))
+    }
+}
+
+fn parse(binding: &HotkeyBinding) -> Result<Shortcut, String> {
+    binding
+        .accelerator
+        .parse::<Shortcut>()
+        .map_err(|e| format!("{}: {}", binding.accelerator, e))
+}
+
+fn on_shortcut(app: &AppHandle, shortcut: &Shortcut) {
+    let settings = app.state::<HotkeyStore>().get();
+    let Some(binding) = settings
+        .bindings
+        .into_iter()
+        .find(|b| parse(b).ok().as_ref() == Some(shortcut))
+    else {
+        return;
+    };
+
+    // Keep HID traffic off the event loop
+    let app = app.clone();
+    thread::spawn(move || {
+        info!("Hotkey {}: {:?}", binding.accelerator, binding.action);
+
+        let targets = akko::detector::detect_akko_devices()
+            .into_iter()
+            .filter(|m| binding.applies_to(*m));
+        for model in targets {
+            if let Err(e) = run(&app, model, &binding.action) {
+                warn!(
+                    "Hotkey {:?} on {} failed: {}",
+                    binding.action,
+                    model.name(),
+                    e
+                );
+            }
+        }
+    });
+}
+
+fn run(app: &AppHandle, model: AkkoModel, action: &HotkeyAction) -> Result<(), String> {
+    match action {
+        HotkeyAction::BrightnessUp => actions::step_brightness(app, model, 1).map(|_| ()),
+        HotkeyAction::BrightnessDown => actions::step_brightness(app, model, -1).map(|_| ()),
+        HotkeyAction::CycleEffect => actions::cycle_effect(app, model).map(|_| ()),
+        HotkeyAction::ToggleLights => actions::toggle_lights(app, model).map(|_| ()),
+        HotkeyAction::NextProfile => actions::next_profile(app, model).map(|_| ()),
+        HotkeyAction::ApplyPreset { slot } => {
+            actions::apply_preset_slot(app, model, *slot).map(|_| ())
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Hotplug and resume watcher
+//! Polls for connected keyboards and detects suspend/resume from wall-clock
+//! gaps (the loop does not run while the machine is asleep)
+
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use log::{info, warn};
+use tauri::{AppHandle, Emitter};
+
+use crate::devices::akko::{self, AkkoModel};
+use crate::services::reapply;
+use crate::tray;
+
+/// Payload: model name
+pub const DEVICE_CONNECTED_EVENT: &str = "akko-device-connected";
+
+/// Payload: model name
+pub const DEVICE_DISCONNECTED_EVENT: &str = "akko-device-disconnected";
+
+/// Emitted after the system resumes from suspend
+pub const SYSTEM_RESUMED_EVENT: &str = "system-resumed";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A tick gap longer than this (on top of POLL_INTERVAL) means we were suspended
+const SUSPEND_GAP: Duration = Duration::from_secs(30);
+
+/// Start the watcher thread
+pub fn spawn(app: AppHandle) {
+    thread::spawn(move || {
+        info!("Hotplug watcher started");
+
+        let mut known: Vec<AkkoModel> = akko::detector::detect_akko_devices();
+        for &model in &known {
+            tray::sync_device(&app, model);
+        }
+        let mut last_tick = SystemTime::now();
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            let now = SystemTime::now();
+            let resumed = now
+                .duration_since(last_tick)
+                .is_ok_and(|gap| gap > POLL_INTERVAL + SUSPEND_GAP);
+            last_tick = now;
+
+            let connected = akko::detector::detect_akko_devices();
+
+            for model in known.iter().filter(|m| !connected.contains(m)) {
+                info!("Disconnected: {}", model.name());
+                // hidraw paths are reassigned on reconnect
+                akko::hid::forget_device(model.vid(), model.pid());
+                emit(&app, DEVICE_DISCONNECTED_EVENT, model.name());
+                tray::device_disconnected(&app, *model);
+            }
+
+            for &model in &connected {
+                let is_new = !known.contains(&model);
+                if is_new {
+                    info!("Connected: {}", model.name());
+                    emit(&app, DEVICE_CONNECTED_EVENT, model.name());
+                }
+                if is_new || resumed {
+                    reapply::reapply_logged(&app, model);
+                    tray::sync_device(&app, model);
+                }
+            }
+
+            if resumed {
+                info!("Resumed from suspend");
+                emit(&app, SYSTEM_RESUMED_EVENT, ());
+            }
+
+            known = connected;
+        }
+    });
+}
+
+fn emit<S: serde::Serialize + Clone>(app: &AppHandle, event: &str, payload: S) {
+    if let Err(e) = app.emit(event, payload) {
+        warn!("Failed to emit {}: {}", event, e);
+    }
+}
@@ -0,0 +1,110 @@
+//! Idle lighting service
+//! Snapshots each keyboard's lighting when the system goes idle, applies
+//! the idle action, and writes the snapshot back on activity. A snapshot is
+//! only dropped once it was written back; failed restores are retried on
+//! every poll while the system is active.
+//!
+//! Only lighting is snapshotted: an idle action (ScheduleAction) is a preset
+//! or a brightness, so lighting is all it changes, and lighting writes go
+//! through the keyboard's lighting queue like every other lighting change.
+//! Writing back a full KeyboardState would also re-send profile and system
+//! settings the action never touched, with setters that are unverified.
+
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use tauri::{AppHandle, Manager};
+
+use crate::actions;
+use crate::devices::akko::protocol::RgbSettings;
+use crate::devices::akko::{self, AkkoModel};
+use crate::idle::{self, IdleStore, IdleTracker, IdleTransition};
+use crate::schedules::ScheduleAction;
+use crate::services::lighting::LightingChange;
+use crate::services::scheduler;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Start the idle thread
+pub fn spawn(app: AppHandle) {
+    thread::spawn(move || {
+        info!("Idle monitor started");
+
+        let mut tracker = IdleTracker::default();
+        let mut snapshots: HashMap<AkkoModel, RgbSettings> = HashMap::new();
+
+        loop {
+            let settings = app.state::<IdleStore>().get();
+
+            let status = match idle::system_idle() {
+                Ok(status) => status,
+                Err(e) => {
+                    debug!("Idle probe failed: {}", e);
+                    thread::sleep(POLL_INTERVAL);
+                    continue;
+                }
+            };
+
+            match tracker.update(&settings, status) {
+                IdleTransition::EnterIdle => {
+                    info!("System idle, applying {:?}", settings.action);
+                    for model in akko::detector::detect_akko_devices() {
+                        enter_idle(&app, model, &settings.action, &mut snapshots);
+                    }
+                }
+                IdleTransition::Resume => info!("System active, restoring lighting"),
+                IdleTransition::None => {}
+            }
+
+            if !tracker.is_idle() && !snapshots.is_empty() {
+                restore(&app, &mut snapshots);
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+/// Write snapshots back to connected keyboards; keep the ones that failed
+fn restore(app: &AppHandle, snapshots: &mut HashMap<AkkoModel, RgbSettings>) {
+    let connected = akko::detector::detect_akko_devices();
+
+    snapshots.retain(|model, lighting| {
+        if !connected.contains(model) {
+            return true;
+        }
+        match actions::write_lighting(app, *model, LightingChange::Set(*lighting)) {
+            Ok(_) => false,
+            Err(e) => {
+                warn!("Failed to restore {}, retrying: {}", model.name(), e);
+                true
+            }
+        }
+    });
+}
+
+fn enter_idle(
+    app: &AppHandle,
+    model: AkkoModel,
+    action: &ScheduleAction,
+    snapshots: &mut HashMap<AkkoModel, RgbSettings>,
+) {
+    // A snapshot still waiting to be restored holds the user's lighting;
+    // reading now would capture the idle look instead
+    if !snapshots.contains_key(&model) {
+        // Never apply without a snapshot, or the user's lighting would be lost
+        match actions::current_lighting(app, model) {
+            Ok(lighting) => snapshots.insert(model, lighting),
+            Err(e) => {
+                warn!("Snapshot of {} failed: {}", model.name(), e);
+                return;
+            }
+        };
+    }
+
+    if let Err(e) = scheduler::apply(app, model, action) {
+        warn!("Idle action on {} failed: {}", model.name(), e);
+    }
+}
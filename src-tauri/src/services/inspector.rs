@@ -0,0 +1,19 @@
+//! Protocol inspector stream
+//! Forwards the HID layer's decoded packets to the frontend as events
+
+use log::warn;
+use tauri::{AppHandle, Emitter};
+
+use crate::devices::akko::inspector;
+
+/// Emitted for every packet that passes the inspector filter while not paused
+pub const PACKET_EVENT: &str = "akko-protocol-packet";
+
+/// Route inspector packets to the frontend
+pub fn install(app: AppHandle) {
+    inspector::set_sink(move |packet| {
+        if let Err(e) = app.emit(PACKET_EVENT, packet) {
+            warn!("Failed to emit {}: {}", PACKET_EVENT, e);
+        }
+    });
+}
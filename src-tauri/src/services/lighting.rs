@@ -0,0 +1,138 @@
+//! Coalesced lighting writes
+//! One worker per keyboard sends only the latest of a burst of lighting
+//! changes (slider drags), records it and reports each write to the frontend.
+//! Every lighting writer (commands, tray, hotkeys, scheduler, idle, reapply)
+//! goes through here so an older queued value never lands after a newer one.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use log::warn;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::actions;
+use crate::devices::akko::protocol::RgbSettings;
+use crate::devices::akko::queue::{self, Coalescer, Ticket};
+use crate::devices::akko::{self, AkkoModel, CommandResult};
+
+/// Emitted after every lighting write (successful or not)
+pub const LIGHTING_WRITTEN_EVENT: &str = "akko-lighting-written";
+
+/// Event payload for LIGHTING_WRITTEN_EVENT
+#[derive(Debug, Clone, Serialize)]
+pub struct LightingWritten {
+    pub model: String,
+    /// Lighting sent (None if the write failed before it was known)
+    pub lighting: Option<RgbSettings>,
+    /// How many requests this write covered (>1 = coalesced)
+    pub requests: usize,
+    pub error: Option<String>,
+}
+
+/// A queued lighting change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightingChange {
+    /// Complete lighting settings
+    Set(RgbSettings),
+    /// Only the brightness, keeping the rest of the current lighting
+    Brightness(u8),
+}
+
+impl LightingChange {
+    /// Combine with a change still waiting in the queue
+    fn merge(pending: Self, newer: Self) -> Self {
+        match (pending, newer) {
+            (LightingChange::Set(lighting), LightingChange::Brightness(level)) => {
+                LightingChange::Set(RgbSettings {
+                    brightness: level,
+                    ..lighting
+                })
+            }
+            (_, newer) => newer,
+        }
+    }
+}
+
+type LightingQueue = Coalescer<LightingChange, (RgbSettings, CommandResult)>;
+
+/// Per-keyboard lighting queues (Tauri managed state)
+#[derive(Default)]
+pub struct LightingQueues {
+    queues: Mutex<HashMap<AkkoModel, Arc<LightingQueue>>>,
+}
+
+impl LightingQueues {
+    /// Queue a lighting change for a keyboard; starts its worker on first use
+    pub fn submit(
+        &self,
+        app: &AppHandle,
+        model: AkkoModel,
+        change: LightingChange,
+    ) -> Arc<Ticket<(RgbSettings, CommandResult)>> {
+        let queue = self
+            .queues
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(model)
+            .or_insert_with(|| spawn_worker(app.clone(), model))
+            .clone();
+
+        queue.submit_merged(change, LightingChange::merge)
+    }
+
+    /// Queue a change and block until the write covering it is done
+    pub fn write(
+        &self,
+        app: &AppHandle,
+        model: AkkoModel,
+        change: LightingChange,
+    ) -> Result<CommandResult, String> {
+        self.submit(app, model, change)
+            .wait()
+            .map(|(_, result)| result)
+    }
+}
+
+fn spawn_worker(app: AppHandle, model: AkkoModel) -> Arc<LightingQueue> {
+    let queue = Arc::new(LightingQueue::default());
+    let writer = app.clone();
+
+    queue::run_worker(
+        queue.clone(),
+        queue::MIN_WRITE_INTERVAL,
+        move |change: &LightingChange| {
+            let lighting = match *change {
+                LightingChange::Set(lighting) => lighting,
+                // Only this worker writes lighting, so nothing slips in
+                // between reading the current lighting and the write
+                LightingChange::Brightness(level) => RgbSettings {
+                    brightness: level,
+                    ..actions::current_lighting(&writer, model)?
+                },
+            };
+            akko::api::akko_apply_lighting(model, lighting).map(|result| (lighting, result))
+        },
+        move |change, requests, result| {
+            if let Ok((lighting, _)) = result {
+                actions::lighting_applied(&app, model, *lighting);
+            }
+
+            let lighting = match (change, result) {
+                (_, Ok((lighting, _))) | (LightingChange::Set(lighting), _) => Some(*lighting),
+                _ => None,
+            };
+            let payload = LightingWritten {
+                model: model.name().to_string(),
+                lighting,
+                requests,
+                error: result.as_ref().err().cloned(),
+            };
+            if let Err(e) = app.emit(LIGHTING_WRITTEN_EVENT, payload) {
+                warn!("Failed to emit {}: {}", LIGHTING_WRITTEN_EVENT, e);
+            }
+        },
+    );
+
+    queue
+}
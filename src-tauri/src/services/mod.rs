@@ -0,0 +1,8 @@
+pub mod battery;
+pub mod hotkeys;
+pub mod hotplug;
+pub mod idle;
+pub mod inspector;
+pub mod lighting;
+pub mod reapply;
+pub mod scheduler;
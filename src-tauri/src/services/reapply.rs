@@ -0,0 +1,68 @@
+//! Reapply the last configuration set from the app
+//! Compares a fresh read with LastApplied and re-sends only what differs
+
+use log::{info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::actions;
+use crate::devices::akko::{self, AkkoModel, ConfigSection};
+use crate::last_applied::{self, LastApplied};
+use crate::services::lighting::LightingChange;
+
+/// Emitted when settings had to be re-sent
+pub const STATE_REAPPLIED_EVENT: &str = "akko-state-reapplied";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReapplyEvent {
+    pub model: String,
+    pub sections: Vec<ConfigSection>,
+}
+
+/// Verify a keyboard against its last applied state and fix differences
+/// Returns the sections that were re-sent
+pub fn reapply(app: &AppHandle, model: AkkoModel) -> Result<Vec<ConfigSection>, String> {
+    let Some(remembered) = app.state::<LastApplied>().get(model) else {
+        return Ok(Vec::new());
+    };
+
+    let fresh = akko::api::akko_read_state(model, remembered.lighting)?;
+    let sections = last_applied::sections_to_reapply(&fresh.diff(&remembered));
+    if sections.is_empty() {
+        return Ok(sections);
+    }
+
+    info!("Reapplying {:?} on {}", sections, model.name());
+    // Lighting goes through the queue (after the profile switch) so it is
+    // ordered with every other lighting write
+    let (lighting, others): (Vec<_>, Vec<_>) = sections
+        .into_iter()
+        .partition(|section| *section == ConfigSection::Lighting);
+
+    let mut written = if others.is_empty() {
+        Vec::new()
+    } else {
+        akko::api::akko_write_state(model, &remembered, &others)?
+    };
+    if let Some(settings) = remembered.lighting.filter(|_| !lighting.is_empty()) {
+        actions::write_lighting(app, model, LightingChange::Set(settings))?;
+        written.push(ConfigSection::Lighting);
+    }
+
+    let payload = ReapplyEvent {
+        model: model.name().to_string(),
+        sections: written.clone(),
+    };
+    if let Err(e) = app.emit(STATE_REAPPLIED_EVENT, payload) {
+        warn!("Failed to emit {}: {}", STATE_REAPPLIED_EVENT, e);
+    }
+
+    Ok(written)
+}
+
+/// reapply() for background callers
+pub fn reapply_logged(app: &AppHandle, model: AkkoModel) {
+    if let Err(e) = reapply(app, model) {
+        warn!("Reapply on {} failed: {}", model.name(), e);
+    }
+}
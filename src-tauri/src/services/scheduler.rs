@@ -0,0 +1,79 @@
+//! Background lighting scheduler
+//! Ticks the Scheduler and runs due steps; unplugged keyboards are
+//! picked up again on reconnect because the scheduler forgets them.
+//! The lighting from before a window is snapshotted and written back when
+//! the window ends; both transitions are recorded in LastApplied.
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use log::{info, warn};
+use tauri::{AppHandle, Manager};
+
+use crate::actions;
+use crate::devices::akko::{self, AkkoModel};
+use crate::schedules::{Clock, ScheduleAction, ScheduleStep, ScheduleStore, Scheduler};
+use crate::services::lighting::LightingChange;
+
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Start the scheduler thread
+pub fn spawn(app: AppHandle, clock: Arc<dyn Clock>) {
+    thread::spawn(move || {
+        info!("Lighting scheduler started");
+        let mut scheduler = Scheduler::new(clock);
+
+        loop {
+            let schedules = app.state::<ScheduleStore>().list();
+            let connected = akko::detector::detect_akko_devices();
+
+            for (model, step) in scheduler.tick(&schedules, &connected) {
+                if let Err(e) = run_step(&app, &mut scheduler, model, &step) {
+                    warn!("Schedule {:?} on {} failed: {}", step, model.name(), e);
+                    scheduler.retry(model);
+                }
+            }
+
+            thread::sleep(TICK_INTERVAL);
+        }
+    });
+}
+
+/// Apply a window's action or restore the lighting from before it
+fn run_step(
+    app: &AppHandle,
+    scheduler: &mut Scheduler,
+    model: AkkoModel,
+    step: &ScheduleStep,
+) -> Result<(), String> {
+    match step {
+        ScheduleStep::Apply(action) => {
+            // Never apply without a snapshot, or the user's lighting would be lost
+            if scheduler.snapshot(model).is_none() {
+                scheduler.save_snapshot(model, actions::current_lighting(app, model)?);
+            }
+            apply(app, model, action)
+        }
+        ScheduleStep::Restore => {
+            let Some(lighting) = scheduler.snapshot(model) else {
+                return Ok(());
+            };
+            info!("Schedule ended, restoring lighting on {}", model.name());
+            actions::write_lighting(app, model, LightingChange::Set(lighting))?;
+            scheduler.restored(model);
+            Ok(())
+        }
+    }
+}
+
+/// Send one schedule action to a keyboard (through its lighting queue)
+pub fn apply(app: &AppHandle, model: AkkoModel, action: &ScheduleAction) -> Result<(), String> {
+    info!("Schedule: {:?} on {}", action, model.name());
+
+    match action {
+        ScheduleAction::Preset { name } => actions::apply_preset(app, model, name),
+        ScheduleAction::Brightness { level } => actions::set_brightness(app, model, *level),
+    }
+    .map(|_| ())
+}
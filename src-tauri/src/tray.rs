@@ -0,0 +1,539 @@
+//! System tray icon and quick-control menu
+//! The menu is rebuilt from TrayMenuState whenever devices, battery or
+//! lighting change; the window does not need to be open
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::menu::{
+    CheckMenuItemBuilder, Menu, MenuBuilder, MenuItemBuilder, Submenu, SubmenuBuilder,
+};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::actions;
+use crate::devices::akko::protocol::ProfileInfo;
+use crate::devices::akko::{self, AkkoModel, BatteryStatus, KeyboardState};
+use crate::last_applied::LastApplied;
+use crate::presets::PresetStore;
+use crate::storage;
+
+/// File name in the app config directory
+pub const TRAY_FILE: &str = "tray.json";
+
+/// Tray icon id (used to look the icon up from background services)
+pub const TRAY_ID: &str = "main";
+
+/// Passed by the autostart launcher: start in the tray without showing the window
+pub const HIDDEN_ARG: &str = "--hidden";
+
+/// Emitted when auto-switch is toggled from the tray. Payload: bool
+pub const AUTO_SWITCH_EVENT: &str = "auto-switch-changed";
+
+const TOOLTIP: &str = "KeyHub";
+
+const MAIN_WINDOW: &str = "main";
+
+/// What the menu shows for one connected keyboard
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceMenu {
+    pub profiles: Option<ProfileInfo>,
+    pub brightness: Option<u8>,
+    pub battery: Option<BatteryStatus>,
+    /// Last non-zero brightness, restored by the lights toggle
+    last_on: Option<u8>,
+}
+
+impl DeviceMenu {
+    fn set_brightness(&mut self, level: u8) {
+        self.brightness = Some(level);
+        if level > 0 {
+            self.last_on = Some(level);
+        }
+    }
+
+    pub fn lights_on(&self) -> bool {
+        self.brightness.is_some_and(|b| b > 0)
+    }
+
+    /// Brightness the lights toggle should send
+    pub fn toggle_target(&self) -> u8 {
+        if self.lights_on() {
+            0
+        } else {
+            self.last_on.unwrap_or(4)
+        }
+    }
+}
+
+/// Tray settings kept across restarts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TraySettings {
+    pub auto_switch: bool,
+}
+
+impl Default for TraySettings {
+    fn default() -> Self {
+        Self { auto_switch: true }
+    }
+}
+
+/// Menu state owned by the backend
+#[derive(Debug, Clone)]
+pub struct TrayMenuState {
+    pub auto_switch: bool,
+    devices: HashMap<AkkoModel, DeviceMenu>,
+}
+
+impl Default for TrayMenuState {
+    fn default() -> Self {
+        Self {
+            auto_switch: true,
+            devices: HashMap::new(),
+        }
+    }
+}
+
+impl TrayMenuState {
+    /// Connected keyboards in a stable order
+    pub fn devices(&self) -> Vec<(AkkoModel, &DeviceMenu)> {
+        AkkoModel::ALL
+            .iter()
+            .filter_map(|m| self.devices.get(m).map(|d| (*m, d)))
+            .collect()
+    }
+
+    pub fn device(&self, model: AkkoModel) -> Option<&DeviceMenu> {
+        self.devices.get(&model)
+    }
+
+    /// Add or refresh a keyboard from a state read
+    pub fn connect(&mut self, model: AkkoModel, state: Option<&KeyboardState>) {
+        let device = self.devices.entry(model).or_default();
+        if let Some(state) = state {
+            device.profiles = state.profiles;
+            if let Some(lighting) = &state.lighting {
+                device.set_brightness(lighting.brightness);
+            }
+        }
+    }
+
+    pub fn disconnect(&mut self, model: AkkoModel) {
+        self.devices.remove(&model);
+    }
+
+    pub fn set_battery(&mut self, model: AkkoModel, status: BatteryStatus) {
+        self.devices.entry(model).or_default().battery = Some(status);
+    }
+
+    pub fn set_brightness(&mut self, model: AkkoModel, level: u8) {
+        self.devices.entry(model).or_default().set_brightness(level);
+    }
+
+    /// Tooltip text: app name plus battery levels
+    pub fn tooltip(&self) -> String {
+        let mut lines = vec![TOOLTIP.to_string()];
+        for (model, device) in self.devices() {
+            if let Some(status) = &device.battery {
+                lines.push(format!(
+                    "{}: {}%{} ({})",
+                    model.name(),
+                    status.percent,
+                    if status.charging { " charging" } else { "" },
+                    status.connection.label()
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// A clicked menu entry, encoded in the menu item id
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuAction {
+    Profile(AkkoModel, u8),
+    Preset(AkkoModel, String),
+    Brightness(AkkoModel, u8),
+    ToggleLights(AkkoModel),
+    AutoSwitch,
+    Show,
+    Quit,
+}
+
+impl MenuAction {
+    pub fn id(&self) -> String {
+        match self {
+            MenuAction::Profile(m, p) => format!("device:{}:profile:{}", m.id(), p),
+            MenuAction::Preset(m, name) => format!("device:{}:preset:{}", m.id(), name),
+            MenuAction::Brightness(m, b) => format!("device:{}:brightness:{}", m.id(), b),
+            MenuAction::ToggleLights(m) => format!("device:{}:lights", m.id()),
+            MenuAction::AutoSwitch => "auto_switch".to_string(),
+            MenuAction::Show => "show".to_string(),
+            MenuAction::Quit => "quit".to_string(),
+        }
+    }
+
+    pub fn parse(id: &str) -> Option<Self> {
+        match id {
+            "auto_switch" => return Some(MenuAction::AutoSwitch),
+            "show" => return Some(MenuAction::Show),
+            "quit" => return Some(MenuAction::Quit),
+            _ => {}
+        }
+
+        // Preset names may contain ':' so only split off the known parts
+        let mut parts = id.splitn(4, ':');
+        if parts.next()? != "device" {
+            return None;
+        }
+        let model = AkkoModel::from_str(parts.next()?)?;
+
+        match (parts.next()?, parts.next()) {
+            ("profile", Some(p)) => p.parse().ok().map(|p| MenuAction::Profile(model, p)),
+            ("preset", Some(name)) => Some(MenuAction::Preset(model, name.to_string())),
+            ("brightness", Some(b)) => b
+                .parse()
+                .ok()
+                .filter(|b| *b <= 4)
+                .map(|b| MenuAction::Brightness(model, b)),
+            ("lights", None) => Some(MenuAction::ToggleLights(model)),
+            _ => None,
+        }
+    }
+}
+
+/// Tray menu state and its persisted settings (Tauri managed state)
+pub struct TrayState {
+    path: PathBuf,
+    menu: Mutex<TrayMenuState>,
+}
+
+impl TrayState {
+    /// Load settings from disk (defaults if the file does not exist yet)
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let settings: TraySettings = storage::read_json(&path)?.unwrap_or_default();
+        Ok(Self {
+            path,
+            menu: Mutex::new(TrayMenuState {
+                auto_switch: settings.auto_switch,
+                ..Default::default()
+            }),
+        })
+    }
+
+    pub fn snapshot(&self) -> TrayMenuState {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TrayMenuState> {
+        self.menu.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Create the tray icon (TrayState must be managed first)
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let state = app.state::<TrayState>().snapshot();
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(state.tooltip())
+        .menu(&build_menu(app, &state)?)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()));
+
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+
+    builder.build(app)?;
+    Ok(())
+}
+
+/// Change the menu state and rebuild the menu
+pub fn update(app: &AppHandle, f: impl FnOnce(&mut TrayMenuState)) {
+    if let Some(state) = app.try_state::<TrayState>() {
+        f(&mut state.lock());
+    }
+    refresh(app);
+}
+
+/// Rebuild the menu and tooltip from the current state
+pub fn refresh(app: &AppHandle) {
+    let (Some(tray), Some(state)) = (app.tray_by_id(TRAY_ID), app.try_state::<TrayState>()) else {
+        return;
+    };
+    let state = state.snapshot();
+
+    let result = build_menu(app, &state)
+        .and_then(|menu| tray.set_menu(Some(menu)))
+        .and_then(|_| tray.set_tooltip(Some(state.tooltip())));
+    if let Err(e) = result {
+        warn!("Failed to update tray menu: {}", e);
+    }
+}
+
+/// Read a keyboard's profile and lighting into the menu
+pub fn sync_device(app: &AppHandle, model: AkkoModel) {
+    let known_lighting = app.state::<LastApplied>().lighting(model);
+    let state = match akko::api::akko_read_state(model, known_lighting) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            warn!("Tray: could not read {}: {}", model.name(), e);
+            None
+        }
+    };
+    update(app, |menu| menu.connect(model, state.as_ref()));
+}
+
+pub fn device_disconnected(app: &AppHandle, model: AkkoModel) {
+    update(app, |menu| menu.disconnect(model));
+}
+
+pub fn set_battery(app: &AppHandle, model: AkkoModel, status: BatteryStatus) {
+    update(app, |menu| menu.set_battery(model, status));
+}
+
+/// Brightness the lights toggle should send (4 when nothing is known yet)
+pub fn lights_toggle_target(app: &AppHandle, model: AkkoModel) -> u8 {
+    app.try_state::<TrayState>()
+        .and_then(|s| s.lock().device(model).map(|d| d.toggle_target()))
+        .unwrap_or(4)
+}
+
+pub fn auto_switch(app: &AppHandle) -> bool {
+    app.try_state::<TrayState>()
+        .map(|s| s.lock().auto_switch)
+        .unwrap_or(true)
+}
+
+/// Enable or disable app-based profile switching, save it and tell the frontend
+pub fn set_auto_switch(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    if let Some(state) = app.try_state::<TrayState>() {
+        storage::write_json(
+            &state.path,
+            &TraySettings {
+                auto_switch: enabled,
+            },
+        )?;
+    }
+    update(app, |menu| menu.auto_switch = enabled);
+    if let Err(e) = app.emit(AUTO_SWITCH_EVENT, enabled) {
+        warn!("Failed to emit {}: {}", AUTO_SWITCH_EVENT, e);
+    }
+    Ok(())
+}
+
+/// Show and focus the main window
+pub fn show_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    let Some(action) = MenuAction::parse(id) else {
+        warn!("Unknown tray menu id: {}", id);
+        return;
+    };
+    info!("Tray: {:?}", action);
+
+    match action {
+        MenuAction::AutoSwitch => {
+            if let Err(e) = set_auto_switch(app, !auto_switch(app)) {
+                warn!("Tray action {} failed: {}", id, e);
+                refresh(app);
+            }
+        }
+        MenuAction::Show => show_window(app),
+        MenuAction::Quit => app.exit(0),
+        action => {
+            // Keyboard writes block on HID; keep them off the menu event thread
+            let app = app.clone();
+            let id = id.to_string();
+            tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = run_device_action(&app, action) {
+                    warn!("Tray action {} failed: {}", id, e);
+                    // Put check marks back the way the keyboard actually is
+                    refresh(&app);
+                }
+            });
+        }
+    }
+}
+
+/// Menu entries that write to a keyboard
+fn run_device_action(app: &AppHandle, action: MenuAction) -> Result<(), String> {
+    match action {
+        MenuAction::Profile(model, profile) => {
+            actions::set_active_profile(app, model, profile).map(|_| ())
+        }
+        MenuAction::Preset(model, name) => actions::apply_preset(app, model, &name).map(|_| ()),
+        MenuAction::Brightness(model, level) => {
+            actions::set_brightness(app, model, level).map(|_| ())
+        }
+        MenuAction::ToggleLights(model) => actions::toggle_lights(app, model).map(|_| ()),
+        MenuAction::AutoSwitch | MenuAction::Show | MenuAction::Quit => Ok(()),
+    }
+}
+
+fn build_menu(app: &AppHandle, state: &TrayMenuState) -> tauri::Result<Menu<tauri::Wry>> {
+    let presets: Vec<String> = app
+        .try_state::<PresetStore>()
+        .map(|s| s.list().into_iter().map(|p| p.name).collect())
+        .unwrap_or_default();
+
+    let mut menu = MenuBuilder::new(app);
+
+    let devices = state.devices();
+    if devices.is_empty() {
+        menu = menu.item(
+            &MenuItemBuilder::new("No keyboard connected")
+                .enabled(false)
+                .build(app)?,
+        );
+    }
+    for (model, device) in devices {
+        menu = menu.item(&build_device_menu(app, model, device, &presets)?);
+    }
+
+    menu.separator()
+        .item(
+            &CheckMenuItemBuilder::with_id(MenuAction::AutoSwitch.id(), "Auto-switch profiles")
+                .checked(state.auto_switch)
+                .build(app)?,
+        )
+        .separator()
+        .text(MenuAction::Show.id(), "Open KeyHub")
+        .text(MenuAction::Quit.id(), "Quit")
+        .build()
+}
+
+fn build_device_menu(
+    app: &AppHandle,
+    model: AkkoModel,
+    device: &DeviceMenu,
+    presets: &[String],
+) -> tauri::Result<Submenu<tauri::Wry>> {
+    let mut submenu = SubmenuBuilder::new(app, model.name());
+
+    if let Some(status) = &device.battery {
+        let label = format!(
+            "Battery: {}%{}",
+            status.percent,
+            if status.charging { " (charging)" } else { "" }
+        );
+        submenu = submenu
+            .item(&MenuItemBuilder::new(label).enabled(false).build(app)?)
+            .separator();
+    }
+
+    let mut profiles = SubmenuBuilder::new(app, "Profile");
+    if let Some(info) = device.profiles {
+        for p in 0..info.count {
+            profiles = profiles.item(
+                &CheckMenuItemBuilder::with_id(
+                    MenuAction::Profile(model, p).id(),
+                    format!("Profile {}", p + 1),
+                )
+                .checked(p == info.active)
+                .build(app)?,
+            );
+        }
+    } else {
+        profiles = profiles.item(
+            &MenuItemBuilder::new("Unavailable")
+                .enabled(false)
+                .build(app)?,
+        );
+    }
+
+    let mut preset_menu = SubmenuBuilder::new(app, "Lighting preset");
+    if presets.is_empty() {
+        preset_menu = preset_menu.item(
+            &MenuItemBuilder::new("No presets")
+                .enabled(false)
+                .build(app)?,
+        );
+    }
+    for name in presets {
+        preset_menu = preset_menu.text(MenuAction::Preset(model, name.clone()).id(), name);
+    }
+
+    let mut brightness = SubmenuBuilder::new(app, "Brightness");
+    for level in 0..=4 {
+        brightness = brightness.item(
+            &CheckMenuItemBuilder::with_id(
+                MenuAction::Brightness(model, level).id(),
+                level.to_string(),
+            )
+            .checked(device.brightness == Some(level))
+            .build(app)?,
+        );
+    }
+
+    submenu
+        .item(&profiles.build()?)
+        .item(&preset_menu.build()?)
+        .item(&brightness.build()?)
+        .item(
+            &CheckMenuItemBuilder::with_id(MenuAction::ToggleLights(model).id(), "Lights")
+                .checked(device.lights_on())
+                .build(app)?,
+        )
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_menu_ids_round_trip() {
+        let model = AkkoModel::Mod007b;
+        let actions = [
+            MenuAction::Profile(model, 2),
+            MenuAction::Preset(model, "Night: blue".to_string()),
+            MenuAction::Brightness(model, 4),
+            MenuAction::ToggleLights(model),
+            MenuAction::AutoSwitch,
+            MenuAction::Show,
+            MenuAction::Quit,
+        ];
+        for action in actions {
+            assert_eq!(MenuAction::parse(&action.id()), Some(action));
+        }
+
+        assert_eq!(MenuAction::parse("device:mod007b:brightness:5"), None);
+        assert_eq!(MenuAction::parse("device:unknown:lights"), None);
+    }
+
+    #[test]
+    fn test_lights_toggle_restores_last_brightness() {
+        let model = AkkoModel::Mod007b;
+        let mut state = TrayMenuState::default();
+        state.connect(model, None);
+        assert_eq!(state.device(model).unwrap().toggle_target(), 4);
+
+        state.set_brightness(model, 2);
+        assert_eq!(state.device(model).unwrap().toggle_target(), 0);
+
+        state.set_brightness(model, 0);
+        assert!(!state.device(model).unwrap().lights_on());
+        assert_eq!(state.device(model).unwrap().toggle_target(), 2);
+
+        state.disconnect(model);
+        assert!(state.devices().is_empty());
+    }
+
+    #[test]
+    fn test_auto_switch_defaults_on() {
+        assert!(TraySettings::default().auto_switch);
+        let saved: TraySettings = serde_json::from_str("{}").unwrap();
+        assert!(saved.auto_switch);
+        let saved: TraySettings = serde_json::from_str(r#"{"auto_switch": false}"#).unwrap();
+        assert!(!saved.auto_switch);
+    }
+}
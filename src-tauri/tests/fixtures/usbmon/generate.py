@@ -0,0 +1,121 @@
+#!/usr/bin/env python3
+"""Generate the synthetic usbmon captures used by capture.rs tests.
+
+Both files hold the same session on bus 1: enumeration of a MOD007B
+(3151:5009, device 7) and an unrelated mouse (046d:c077, device 3), then a
+handshake, a GET_RGB, a SET_RGB with its echo, an unknown probe and one
+mouse feature report that must be filtered out.
+
+- mod007b_session.pcap:   classic pcap, LINKTYPE_USB_LINUX (189)
+- mod007b_session.pcapng: pcapng, LINKTYPE_USB_LINUX_MMAPPED (220)
+"""
+import struct
+from pathlib import Path
+
+HERE = Path(__file__).parent
+BASE = 1_700_000_000
+
+
+def packet(opcode, fields=b"", checksum_at=7, sum_checksum=False):
+    data = bytearray(64)
+    data[0] = opcode
+    data[1:1 + len(fields)] = fields
+    if sum_checksum:
+        data[checksum_at] = (0xFF - sum(data[:checksum_at]) % 256) & 0xFF
+    else:
+        data[checksum_at] = 0xFF - opcode
+    return bytes(data)
+
+
+def response(*values):
+    data = bytearray(64)
+    data[:len(values)] = bytes(values)
+    return bytes(data)
+
+
+def device_descriptor(vid, pid):
+    return struct.pack("<BBHBBBBHHHBBBB", 18, 1, 0x0200, 0, 0, 0, 64, vid, pid, 0x0100, 1, 2, 0, 1)
+
+
+def setup(request_type, request, value, index, length):
+    return struct.pack("<BBHHH", request_type, request, value, index, length)
+
+
+def session():
+    """(urb id, type, bus, dev, setup or None, data, t_usec)"""
+    urbs = []
+    t = 0
+    urb_id = 0x1000
+
+    def control(dev, setup_bytes, out_data=b"", in_data=None):
+        nonlocal t, urb_id
+        urb_id += 0x40
+        urbs.append((urb_id, b"S", dev, setup_bytes, out_data, t))
+        t += 350
+        urbs.append((urb_id, b"C", dev, None, in_data or b"", t))
+        t += 1200
+
+    control(3, setup(0x80, 6, 0x0100, 0, 18), in_data=device_descriptor(0x046D, 0xC077))
+    control(7, setup(0x80, 6, 0x0100, 0, 18), in_data=device_descriptor(0x3151, 0x5009))
+
+    set_report = setup(0x21, 0x09, 0x0300, 1, 64)
+    get_report = setup(0xA1, 0x01, 0x0300, 1, 64)
+
+    def exchange(tx, rx):
+        control(7, set_report, out_data=tx)
+        control(7, get_report, in_data=rx)
+
+    exchange(packet(0x8F), response(0x8F, 1, 7))
+    exchange(packet(0x87), response(0x87, 4, 3, 4, 0x18, 0xFF, 0x80, 0x00))
+    rgb = packet(0x07, bytes([4, 2, 3, 0x18, 0x33, 0xCC, 0xFF]), checksum_at=8, sum_checksum=True)
+    exchange(rgb, rgb)
+    exchange(packet(0xA3), response(0xA3, 0, 0x42))
+    control(3, setup(0x21, 0x09, 0x0300, 0, 64), out_data=packet(0x8F))
+    return urbs
+
+
+def usbmon(urb, mmapped):
+    urb_id, kind, dev, setup_bytes, data, t = urb
+    flag_setup = 0 if setup_bytes is not None else ord("-")
+    header = struct.pack(
+        "<QcBBBHbbqiiII8s",
+        urb_id, kind, 2, 0x80 if kind == b"C" else 0x00, dev, 1,
+        flag_setup, 0 if data else ord("<"),
+        BASE + t // 1_000_000, t % 1_000_000, 0,
+        len(data), len(data), setup_bytes or bytes(8),
+    )
+    if mmapped:
+        header += struct.pack("<iiII", 0, 0, 0, 0)
+    return header + data, t
+
+
+def write_pcap(path):
+    out = struct.pack("<IHHiIII", 0xA1B2C3D4, 2, 4, 0, 0, 65535, 189)
+    for urb in session():
+        frame, t = usbmon(urb, mmapped=False)
+        out += struct.pack("<IIII", BASE + t // 1_000_000, t % 1_000_000, len(frame), len(frame))
+        out += frame
+    path.write_bytes(out)
+
+
+def block(block_type, body):
+    body += bytes(-len(body) % 4)
+    length = len(body) + 12
+    return struct.pack("<II", block_type, length) + body + struct.pack("<I", length)
+
+
+def write_pcapng(path):
+    out = block(0x0A0D0D0A, struct.pack("<IHHq", 0x1A2B3C4D, 1, 0, -1))
+    # if_tsresol = 6 (microseconds), then opt_endofopt
+    options = struct.pack("<HHB3x", 9, 1, 6) + struct.pack("<HH", 0, 0)
+    out += block(1, struct.pack("<HHI", 220, 0, 65535) + options)
+    for urb in session():
+        frame, t = usbmon(urb, mmapped=True)
+        ts = BASE * 1_000_000 + t
+        out += block(6, struct.pack("<IIIII", 0, ts >> 32, ts & 0xFFFFFFFF, len(frame), len(frame)) + frame)
+    path.write_bytes(out)
+
+
+if __name__ == "__main__":
+    write_pcap(HERE / "mod007b_session.pcap")
+    write_pcapng(HERE / "mod007b_session.pcapng")
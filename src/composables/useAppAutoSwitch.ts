@@ -1,5 +1,6 @@
 import { ref, onMounted, onUnmounted } from 'vue';
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 
 export interface AppProfileMap {
     [appName: string]: number; // Maps app name (e.g. "code") to profile index (1-based)
@@ -12,6 +13,7 @@ export function useAppAutoSwitch() {
     const isAutoSwitchEnabled = ref(true);
 
     let pollingInterval: number | null = null;
+    let unlistenAutoSwitch: UnlistenFn | null = null;
 
     // Load map from local storage
     function loadMap() {
@@ -50,6 +52,23 @@ export function useAppAutoSwitch() {
         }
     }
 
+    // The backend owns the flag so the tray menu and the window stay in sync
+    async function loadAutoSwitch() {
+        try {
+            isAutoSwitchEnabled.value = await invoke<boolean>('get_auto_switch');
+            unlistenAutoSwitch = await listen<boolean>('auto-switch-changed', (event) => {
+                isAutoSwitchEnabled.value = event.payload;
+            });
+        } catch (e) {
+            console.error("Error loading auto-switch state:", e);
+        }
+    }
+
+    async function setAutoSwitchEnabled(enabled: boolean) {
+        await invoke('set_auto_switch', { enabled });
+        isAutoSwitchEnabled.value = enabled;
+    }
+
     function startPolling() {
         if (pollingInterval) return;
         loadMap();
@@ -64,11 +83,13 @@ export function useAppAutoSwitch() {
     }
 
     onMounted(() => {
+        loadAutoSwitch();
         startPolling();
     });
 
     onUnmounted(() => {
         stopPolling();
+        unlistenAutoSwitch?.();
     });
 
     return {
@@ -76,6 +97,7 @@ export function useAppAutoSwitch() {
         activeProfileIndex,
         profileMap,
         isAutoSwitchEnabled,
+        setAutoSwitchEnabled,
         setProfileForApp,
         checkActiveApp
     };
@@ -34,6 +34,21 @@ export interface ProfileInfo {
     active: number;
 }
 
+export interface PermissionProblem {
+    vid: number;
+    pid: number;
+    denied: string[];
+    rule: 'missing' | 'outdated' | 'current';
+}
+
+export interface UdevStatus {
+    supported: boolean;
+    path: string;
+    rule: string;
+    state: 'missing' | 'outdated' | 'current';
+    problem: PermissionProblem | null;
+}
+
 export function useDevice() {
     // Device State
     const devices = ref<string[]>([]);
@@ -41,6 +56,9 @@ export function useDevice() {
     const isConnecting = ref(false);
     const isConnected = ref(false);
     const firmwareVersion = ref<string>("");
+    // Set when the keyboard could not be opened for lack of hidraw permissions (Linux)
+    const permissionProblem = ref<{ model: string; status: UdevStatus } | null>(null);
+    const isInstallingRule = ref(false);
 
     // Keyboard Data State
     const profileInfo = ref<ProfileInfo | null>(null);
@@ -104,11 +122,40 @@ export function useDevice() {
             addLog("error", `Handshake failed: ${e}`);
             isConnected.value = false;
             selectedDevice.value = null;
+            await checkPermissions(modelName);
         } finally {
             isConnecting.value = false;
         }
     }
 
+    async function checkPermissions(modelName: string) {
+        try {
+            const status = await invoke<UdevStatus>("get_udev_status", { model: modelName.toLowerCase() });
+            permissionProblem.value = status.problem ? { model: modelName, status } : null;
+        } catch (e: any) {
+            addLog("error", `Permission check failed: ${e}`);
+        }
+    }
+
+    // Install the udev rule (pkexec asks for the password), then connect again
+    async function installUdevRule() {
+        if (!permissionProblem.value) return;
+        const modelName = permissionProblem.value.model;
+
+        isInstallingRule.value = true;
+        try {
+            addLog("info", `Installing udev rule to ${permissionProblem.value.status.path}...`);
+            await invoke("install_udev_rule", { model: modelName.toLowerCase() });
+            addLog("success", "udev rule installed");
+            permissionProblem.value = null;
+            await performHandshake(modelName);
+        } catch (e: any) {
+            addLog("error", `udev rule install failed: ${e}`);
+        } finally {
+            isInstallingRule.value = false;
+        }
+    }
+
     async function fetchAllData(modelName: string) {
         isLoadingData.value = true;
         addLog("info", "Fetching keyboard configuration...");
@@ -237,6 +284,8 @@ export function useDevice() {
         isConnecting,
         isConnected,
         firmwareVersion,
+        permissionProblem,
+        isInstallingRule,
         profileInfo,
         rgbSettings,
         performanceSettings,
@@ -246,6 +295,7 @@ export function useDevice() {
         clearLogs,
         detectDevices,
         performHandshake,
+        installUdevRule,
         fetchAllData,
         startDevicePolling
     };
@@ -0,0 +1,102 @@
+import { ref, onMounted, onUnmounted } from 'vue';
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+export type PacketDirection = 'tx' | 'rx';
+
+export interface DecodedField {
+    name: string;
+    value: number;
+}
+
+export interface InspectorPacket {
+    seq: number;
+    timestamp_ms: number;
+    direction: PacketDirection;
+    vid: number;
+    pid: number;
+    opcode: number;
+    opcode_name: string;
+    known: boolean;
+    checksum_valid: boolean;
+    empty: boolean;
+    fields: DecodedField[];
+    hex: string;
+}
+
+export interface InspectorFilter {
+    opcodes: number[]; // empty = all
+    direction: PacketDirection | null;
+    include_empty: boolean;
+    bad_checksum_only: boolean;
+}
+
+export interface InspectorSettings {
+    paused: boolean;
+    capacity: number;
+    filter: InspectorFilter;
+}
+
+export function useProtocolInspector() {
+    const packets = ref<InspectorPacket[]>([]);
+    const settings = ref<InspectorSettings>({
+        paused: false,
+        capacity: 500,
+        filter: { opcodes: [], direction: null, include_empty: false, bad_checksum_only: false },
+    });
+
+    let unlistenPackets: UnlistenFn | null = null;
+
+    // The backend keeps a ring buffer; reload it whenever the view changes
+    async function refresh() {
+        packets.value = await invoke<InspectorPacket[]>('get_inspector_packets');
+    }
+
+    async function updateSettings(patch: Partial<InspectorSettings>) {
+        settings.value = { ...settings.value, ...patch };
+        await invoke('set_inspector_settings', { settings: settings.value });
+        await refresh();
+    }
+
+    function setPaused(paused: boolean) {
+        return updateSettings({ paused });
+    }
+
+    function setFilter(patch: Partial<InspectorFilter>) {
+        return updateSettings({ filter: { ...settings.value.filter, ...patch } });
+    }
+
+    async function clear() {
+        await invoke('clear_inspector');
+        packets.value = [];
+    }
+
+    onMounted(async () => {
+        try {
+            settings.value = await invoke<InspectorSettings>('get_inspector_settings');
+            await refresh();
+            unlistenPackets = await listen<InspectorPacket>('akko-protocol-packet', (event) => {
+                packets.value.push(event.payload);
+                const overflow = packets.value.length - settings.value.capacity;
+                if (overflow > 0) {
+                    packets.value.splice(0, overflow);
+                }
+            });
+        } catch (e) {
+            console.error("Error starting protocol inspector:", e);
+        }
+    });
+
+    onUnmounted(() => {
+        unlistenPackets?.();
+    });
+
+    return {
+        packets,
+        settings,
+        setPaused,
+        setFilter,
+        clear,
+        refresh
+    };
+}